@@ -0,0 +1,212 @@
+use anyhow::{anyhow, bail, Result};
+
+/// Notations the color actions convert between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    Hex,
+    Rgb,
+    Hsl,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// 0.0 to 1.0
+    pub a: f64,
+}
+
+impl Color {
+    /// Parse `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`/`rgba()` and
+    /// `hsl()`/`hsla()` with comma or space separated arguments
+    pub fn parse(text: &str) -> Result<(Color, Notation)> {
+        let text = text.trim();
+        if let Some(hex) = text.strip_prefix('#') {
+            return Ok((parse_hex(hex)?, Notation::Hex));
+        }
+
+        let lower = text.to_ascii_lowercase();
+        let (function, arguments) = lower
+            .split_once('(')
+            .and_then(|(function, rest)| Some((function.trim(), rest.strip_suffix(')')?)))
+            .ok_or_else(|| anyhow!("Not a color: {}", text))?;
+        let arguments: Vec<&str> = arguments.split([',', ' ', '/']).filter(|part| !part.is_empty()).collect();
+        if !(3..=4).contains(&arguments.len()) {
+            bail!("Expected 3 or 4 color components in {}", text);
+        }
+        let alpha = arguments.get(3).map(|alpha| parse_alpha(alpha)).transpose()?.unwrap_or(1.0);
+
+        match function {
+            "rgb" | "rgba" => {
+                let channel = |part: &str| -> Result<u8> {
+                    let value = match part.strip_suffix('%') {
+                        Some(percent) => percent.parse::<f64>()? * 2.55,
+                        None => part.parse::<f64>()?,
+                    };
+                    Ok(value.clamp(0.0, 255.0).round() as u8)
+                };
+                let color = Color { r: channel(arguments[0])?, g: channel(arguments[1])?, b: channel(arguments[2])?, a: alpha };
+                Ok((color, Notation::Rgb))
+            }
+            "hsl" | "hsla" => {
+                let hue = arguments[0].trim_end_matches("deg").parse::<f64>()?;
+                let percent = |part: &str| -> Result<f64> { Ok(part.trim_end_matches('%').parse::<f64>()?.clamp(0.0, 100.0) / 100.0) };
+                let (r, g, b) = hsl_to_rgb(hue, percent(arguments[1])?, percent(arguments[2])?);
+                Ok((Color { r, g, b, a: alpha }, Notation::Hsl))
+            }
+            _ => bail!("Not a color: {}", text),
+        }
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` when not opaque
+    pub fn to_hex(self) -> String {
+        let mut hex = format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b);
+        if self.a < 1.0 {
+            hex.push_str(&format!("{:02x}", (self.a * 255.0).round() as u8));
+        }
+        hex
+    }
+
+    pub fn to_rgb(self) -> String {
+        if self.a < 1.0 {
+            format!("rgba({}, {}, {}, {})", self.r, self.g, self.b, format_alpha(self.a))
+        } else {
+            format!("rgb({}, {}, {})", self.r, self.g, self.b)
+        }
+    }
+
+    pub fn to_hsl(self) -> String {
+        let (h, s, l) = rgb_to_hsl(self.r, self.g, self.b);
+        let (h, s, l) = (h.round() as u32 % 360, (s * 100.0).round(), (l * 100.0).round());
+        if self.a < 1.0 {
+            format!("hsla({}, {}%, {}%, {})", h, s, l, format_alpha(self.a))
+        } else {
+            format!("hsl({}, {}%, {}%)", h, s, l)
+        }
+    }
+
+    pub fn format(self, notation: Notation) -> String {
+        match notation {
+            Notation::Hex => self.to_hex(),
+            Notation::Rgb => self.to_rgb(),
+            Notation::Hsl => self.to_hsl(),
+        }
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Invalid hex color #{}", hex);
+    }
+    // "#abc" is short for "#aabbcc"
+    let expanded: String = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex.to_string(),
+        _ => bail!("Invalid hex color #{}", hex),
+    };
+    let byte = |i: usize| u8::from_str_radix(&expanded[i..i + 2], 16);
+    let a = if expanded.len() == 8 { byte(6)? as f64 / 255.0 } else { 1.0 };
+    Ok(Color { r: byte(0)?, g: byte(2)?, b: byte(4)?, a })
+}
+
+fn parse_alpha(part: &str) -> Result<f64> {
+    let alpha = match part.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>()? / 100.0,
+        None => part.parse::<f64>()?,
+    };
+    Ok(alpha.clamp(0.0, 1.0))
+}
+
+/// Alpha with at most two decimals and no trailing zeros: 0.5, 0.25, 0
+fn format_alpha(alpha: f64) -> String {
+    let formatted = format!("{:.2}", alpha);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Hue in degrees, saturation and lightness from 0 to 1
+fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, l);
+    }
+
+    let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (h, s, l)
+}
+
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    let channel = |v: f64| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(text: &str, notation: Notation) -> String {
+        Color::parse(text).unwrap().0.format(notation)
+    }
+
+    #[test]
+    fn test_hex_conversions() {
+        assert_eq!(convert("#ff0000", Notation::Rgb), "rgb(255, 0, 0)");
+        assert_eq!(convert("#ff0000", Notation::Hsl), "hsl(0, 100%, 50%)");
+        assert_eq!(convert("#ABC", Notation::Hex), "#aabbcc");
+        assert_eq!(convert("#00ff0080", Notation::Rgb), "rgba(0, 255, 0, 0.5)");
+        assert_eq!(convert("#fff8", Notation::Hex), "#ffffff88");
+    }
+
+    #[test]
+    fn test_rgb_and_hsl_parsing() {
+        assert_eq!(convert("rgb(0, 128, 255)", Notation::Hex), "#0080ff");
+        assert_eq!(convert("rgb(100% 0% 0% / 25%)", Notation::Rgb), "rgba(255, 0, 0, 0.25)");
+        assert_eq!(convert("hsl(120deg 100% 25%)", Notation::Hex), "#008000");
+        assert_eq!(convert("hsla(240, 100%, 50%, 0.3)", Notation::Rgb), "rgba(0, 0, 255, 0.3)");
+        assert_eq!(convert("hsl(-120, 100%, 50%)", Notation::Hex), "#0000ff", "Hue wraps around");
+    }
+
+    #[test]
+    fn test_round_trips() {
+        for hex in ["#000000", "#ffffff", "#336699", "#ff8800", "#808080", "#123456"] {
+            let (color, notation) = Color::parse(hex).unwrap();
+            assert_eq!(notation, Notation::Hex);
+            assert_eq!(Color::parse(&color.to_rgb()).unwrap().0.to_hex(), hex);
+            // HSL rounds to whole percents, so allow one step per channel
+            let back = Color::parse(&color.to_hsl()).unwrap().0;
+            for (a, b) in [(back.r, color.r), (back.g, color.g), (back.b, color.b)] {
+                assert!(a.abs_diff(b) <= 2, "{hex} -> {} -> {}", color.to_hsl(), back.to_hex());
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_colors() {
+        for text in ["#12345", "#ggg", "rgb(1, 2)", "cmyk(0, 0, 0, 0)", "red", "rgb(a, b, c)"] {
+            assert!(Color::parse(text).is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,296 @@
+// Quick actions offered for an entry depending on its detected kind. Actions
+// are pure: they return what should happen and the command layer carries out
+// opening URLs or revealing files. Support for a new kind is a few more
+// entries in ACTIONS.
+
+pub mod color;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Local, TimeZone, Utc};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::clipboard::ContentKind;
+use color::{Color, Notation};
+
+/// What running an action produced
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActionOutput {
+    /// Converted text for the picker to show, copy or paste
+    Text { text: String },
+    /// Opened with the default handler (browser, mail client, dialer)
+    OpenUrl { url: String },
+    /// Shown selected in the file manager
+    RevealPath { path: String },
+    /// Opened with the default application
+    OpenPath { path: String },
+}
+
+pub struct QuickAction {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub kinds: &'static [ContentKind],
+    /// Whether the action makes sense for this particular text, e.g. no
+    /// "to hex" for a color that already is hex
+    pub available: fn(&str) -> bool,
+    pub run: fn(&str) -> Result<ActionOutput>,
+}
+
+/// Shown in the picker's action menu
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActionInfo {
+    pub id: &'static str,
+    pub label: &'static str,
+}
+
+pub const ACTIONS: &[QuickAction] = &[
+    QuickAction { id: "open_url", label: "Open in browser", kinds: &[ContentKind::Url], available: always, run: open_url },
+    QuickAction { id: "strip_tracking", label: "Remove tracking parameters", kinds: &[ContentKind::Url], available: has_tracking, run: strip_tracking },
+    QuickAction { id: "compose_email", label: "Write email", kinds: &[ContentKind::Email], available: always, run: compose_email },
+    QuickAction { id: "call", label: "Call", kinds: &[ContentKind::Phone], available: always, run: call },
+    QuickAction { id: "reveal_path", label: "Show in file manager", kinds: &[ContentKind::Path], available: always, run: reveal_path },
+    QuickAction { id: "open_path", label: "Open", kinds: &[ContentKind::Path], available: always, run: open_path },
+    QuickAction { id: "color_to_hex", label: "Convert to hex", kinds: &[ContentKind::Color], available: |text| !is_notation(text, Notation::Hex), run: |text| convert_color(text, Notation::Hex) },
+    QuickAction { id: "color_to_rgb", label: "Convert to rgb()", kinds: &[ContentKind::Color], available: |text| !is_notation(text, Notation::Rgb), run: |text| convert_color(text, Notation::Rgb) },
+    QuickAction { id: "color_to_hsl", label: "Convert to hsl()", kinds: &[ContentKind::Color], available: |text| !is_notation(text, Notation::Hsl), run: |text| convert_color(text, Notation::Hsl) },
+    QuickAction { id: "json_pretty", label: "Pretty-print JSON", kinds: &[ContentKind::Json], available: always, run: json_pretty },
+    QuickAction { id: "json_minify", label: "Minify JSON", kinds: &[ContentKind::Json], available: always, run: json_minify },
+    QuickAction { id: "timestamp_to_utc", label: "Convert to UTC date", kinds: &[ContentKind::Timestamp], available: always, run: timestamp_to_utc },
+    QuickAction { id: "timestamp_to_local", label: "Convert to local date", kinds: &[ContentKind::Timestamp], available: always, run: timestamp_to_local },
+];
+
+/// Actions offered for an entry of `kind`
+pub fn actions_for(kind: ContentKind, text: &str) -> Vec<ActionInfo> {
+    ACTIONS
+        .iter()
+        .filter(|action| action.kinds.contains(&kind) && (action.available)(text))
+        .map(|action| ActionInfo { id: action.id, label: action.label })
+        .collect()
+}
+
+/// Run the action `id` on an entry of `kind`
+pub fn run(id: &str, kind: ContentKind, text: &str) -> Result<ActionOutput> {
+    let action = ACTIONS
+        .iter()
+        .find(|action| action.id == id)
+        .ok_or_else(|| anyhow!("Unknown action: {}", id))?;
+    if !action.kinds.contains(&kind) {
+        bail!("Action {} doesn't apply to {} entries", id, kind.as_str());
+    }
+    (action.run)(text.trim())
+}
+
+fn always(_: &str) -> bool {
+    true
+}
+
+fn open_url(text: &str) -> Result<ActionOutput> {
+    // "www.example.org" needs a scheme for the browser
+    let url = if text.contains("://") || text.starts_with("mailto:") { text.to_string() } else { format!("https://{}", text) };
+    Ok(ActionOutput::OpenUrl { url })
+}
+
+const TRACKING_PARAMETERS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "mc_eid", "igshid", "ref_src", "si"];
+
+fn is_tracking_parameter(pair: &str) -> bool {
+    let name = pair.split('=').next().unwrap_or("");
+    name.starts_with("utm_") || TRACKING_PARAMETERS.contains(&name)
+}
+
+fn has_tracking(text: &str) -> bool {
+    text.split_once('?')
+        .is_some_and(|(_, query)| query.split(['&', '#']).any(is_tracking_parameter))
+}
+
+fn strip_tracking(text: &str) -> Result<ActionOutput> {
+    let (base, fragment) = match text.split_once('#') {
+        Some((base, fragment)) => (base, Some(fragment)),
+        None => (text, None),
+    };
+    let mut url = match base.split_once('?') {
+        Some((path, query)) => {
+            let kept: Vec<&str> = query.split('&').filter(|pair| !pair.is_empty() && !is_tracking_parameter(pair)).collect();
+            if kept.is_empty() { path.to_string() } else { format!("{}?{}", path, kept.join("&")) }
+        }
+        None => base.to_string(),
+    };
+    if let Some(fragment) = fragment {
+        url.push('#');
+        url.push_str(fragment);
+    }
+    Ok(ActionOutput::Text { text: url })
+}
+
+fn compose_email(text: &str) -> Result<ActionOutput> {
+    Ok(ActionOutput::OpenUrl { url: format!("mailto:{}", text) })
+}
+
+fn call(text: &str) -> Result<ActionOutput> {
+    let number: String = text.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
+    Ok(ActionOutput::OpenUrl { url: format!("tel:{}", number) })
+}
+
+/// "~/notes.txt" with the home directory filled in
+fn expand_path(text: &str) -> Result<PathBuf> {
+    match text.strip_prefix("~/") {
+        Some(rest) => Ok(dirs::home_dir().context("No home directory")?.join(rest)),
+        None => Ok(PathBuf::from(text)),
+    }
+}
+
+fn reveal_path(text: &str) -> Result<ActionOutput> {
+    let path = expand_path(text)?;
+    if !path.exists() {
+        bail!("{} doesn't exist", path.display());
+    }
+    Ok(ActionOutput::RevealPath { path: path.to_string_lossy().into_owned() })
+}
+
+fn open_path(text: &str) -> Result<ActionOutput> {
+    let path = expand_path(text)?;
+    if !path.exists() {
+        bail!("{} doesn't exist", path.display());
+    }
+    Ok(ActionOutput::OpenPath { path: path.to_string_lossy().into_owned() })
+}
+
+fn is_notation(text: &str, notation: Notation) -> bool {
+    Color::parse(text).is_ok_and(|(_, parsed)| parsed == notation)
+}
+
+fn convert_color(text: &str, notation: Notation) -> Result<ActionOutput> {
+    let (color, _) = Color::parse(text)?;
+    Ok(ActionOutput::Text { text: color.format(notation) })
+}
+
+fn json_pretty(text: &str) -> Result<ActionOutput> {
+    let value: serde_json::Value = serde_json::from_str(text).context("Invalid JSON")?;
+    Ok(ActionOutput::Text { text: serde_json::to_string_pretty(&value)? })
+}
+
+fn json_minify(text: &str) -> Result<ActionOutput> {
+    let value: serde_json::Value = serde_json::from_str(text).context("Invalid JSON")?;
+    Ok(ActionOutput::Text { text: serde_json::to_string(&value)? })
+}
+
+/// Seconds or milliseconds since the epoch, told apart by length
+fn parse_timestamp(text: &str) -> Result<chrono::DateTime<Utc>> {
+    let value: i64 = text.parse().context("Not a timestamp")?;
+    let millis = if text.len() >= 13 { value } else { value * 1000 };
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .ok_or_else(|| anyhow!("Timestamp out of range: {}", text))
+}
+
+fn timestamp_to_utc(text: &str) -> Result<ActionOutput> {
+    let date = parse_timestamp(text)?;
+    Ok(ActionOutput::Text { text: date.format("%Y-%m-%d %H:%M:%S UTC").to_string() })
+}
+
+fn timestamp_to_local(text: &str) -> Result<ActionOutput> {
+    let date = parse_timestamp(text)?.with_timezone(&Local);
+    Ok(ActionOutput::Text { text: date.format("%Y-%m-%d %H:%M:%S %:z").to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(kind: ContentKind, text: &str) -> Vec<&'static str> {
+        actions_for(kind, text).into_iter().map(|action| action.id).collect()
+    }
+
+    fn text_output(id: &str, kind: ContentKind, text: &str) -> String {
+        match run(id, kind, text).unwrap() {
+            ActionOutput::Text { text } => text,
+            other => panic!("Expected text, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_action_ids_are_unique() {
+        let mut ids: Vec<&str> = ACTIONS.iter().map(|action| action.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), ACTIONS.len());
+    }
+
+    #[test]
+    fn test_actions_depend_on_kind() {
+        assert_eq!(ids(ContentKind::Url, "https://example.org"), vec!["open_url"]);
+        assert_eq!(ids(ContentKind::Color, "#ff0000"), vec!["color_to_rgb", "color_to_hsl"]);
+        assert_eq!(ids(ContentKind::Color, "rgb(1, 2, 3)"), vec!["color_to_hex", "color_to_hsl"]);
+        assert_eq!(ids(ContentKind::Json, "{}"), vec!["json_pretty", "json_minify"]);
+        assert!(ids(ContentKind::Text, "hello").is_empty());
+        assert!(ids(ContentKind::Code, "fn main() {}").is_empty());
+    }
+
+    #[test]
+    fn test_run_checks_the_kind() {
+        assert!(run("json_pretty", ContentKind::Url, "{}").is_err());
+        assert!(run("no_such_action", ContentKind::Url, "https://example.org").is_err());
+    }
+
+    #[test]
+    fn test_url_actions() {
+        assert_eq!(
+            run("open_url", ContentKind::Url, "www.example.org").unwrap(),
+            ActionOutput::OpenUrl { url: "https://www.example.org".to_string() }
+        );
+
+        let tracked = "https://example.org/post?id=7&utm_source=news&fbclid=abc#comments";
+        assert!(ids(ContentKind::Url, tracked).contains(&"strip_tracking"));
+        assert_eq!(text_output("strip_tracking", ContentKind::Url, tracked), "https://example.org/post?id=7#comments");
+        assert_eq!(
+            text_output("strip_tracking", ContentKind::Url, "https://example.org/?utm_medium=x"),
+            "https://example.org/"
+        );
+    }
+
+    #[test]
+    fn test_email_and_phone_open_handlers() {
+        assert_eq!(
+            run("compose_email", ContentKind::Email, "me@example.org").unwrap(),
+            ActionOutput::OpenUrl { url: "mailto:me@example.org".to_string() }
+        );
+        assert_eq!(
+            run("call", ContentKind::Phone, "+1 (555) 123-4567").unwrap(),
+            ActionOutput::OpenUrl { url: "tel:+15551234567".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_path_actions_need_an_existing_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "hi").unwrap();
+        let path = file.to_string_lossy().into_owned();
+
+        assert_eq!(run("reveal_path", ContentKind::Path, &path).unwrap(), ActionOutput::RevealPath { path: path.clone() });
+        assert_eq!(run("open_path", ContentKind::Path, &path).unwrap(), ActionOutput::OpenPath { path });
+        assert!(run("reveal_path", ContentKind::Path, "/no/such/file").is_err());
+    }
+
+    #[test]
+    fn test_color_conversion() {
+        assert_eq!(text_output("color_to_rgb", ContentKind::Color, "#336699"), "rgb(51, 102, 153)");
+        assert_eq!(text_output("color_to_hsl", ContentKind::Color, "#336699"), "hsl(210, 50%, 40%)");
+        assert_eq!(text_output("color_to_hex", ContentKind::Color, "hsl(210, 50%, 40%)"), "#336699");
+    }
+
+    #[test]
+    fn test_json_actions_validate() {
+        assert_eq!(text_output("json_pretty", ContentKind::Json, "{\"a\":[1,2]}"), "{\n  \"a\": [\n    1,\n    2\n  ]\n}");
+        assert_eq!(text_output("json_minify", ContentKind::Json, "{ \"a\" : [1, 2] }"), "{\"a\":[1,2]}");
+        assert!(run("json_pretty", ContentKind::Json, "{\"a\":").is_err());
+    }
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(text_output("timestamp_to_utc", ContentKind::Timestamp, "1700000000"), "2023-11-14 22:13:20 UTC");
+        assert_eq!(text_output("timestamp_to_utc", ContentKind::Timestamp, "1700000000123"), "2023-11-14 22:13:20 UTC");
+        assert!(text_output("timestamp_to_local", ContentKind::Timestamp, "1700000000").starts_with("2023-11-1"));
+        assert!(run("timestamp_to_utc", ContentKind::Timestamp, "soon").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+// Evaluates search queries like "42*1.19" or "3 km to mi" so the picker can
+// offer the result as a pasteable item. Parsing is strict: anything that is
+// not entirely a well-formed expression or conversion yields None, so plain
+// searches never turn into surprising results.
+
+pub mod units;
+
+use serde::Serialize;
+
+/// A query that evaluated to something worth pasting
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Evaluation {
+    /// The query as typed, trimmed
+    pub expression: String,
+    /// What gets pasted, e.g. "49.98" or "1.864114 mi"
+    pub result: String,
+}
+
+/// Evaluate a search query as a unit conversion or math expression
+pub fn evaluate(query: &str) -> Option<Evaluation> {
+    let query = query.trim();
+    if query.is_empty() || query.len() > 200 {
+        return None;
+    }
+
+    let result = units::convert(query).or_else(|| evaluate_math(query).map(format_number))?;
+    Some(Evaluation { expression: query.to_string(), result })
+}
+
+/// Evaluate an arithmetic expression. A lone number or constant is not a
+/// calculation and yields None.
+pub fn evaluate_math(text: &str) -> Option<f64> {
+    let tokens = tokenize(text)?;
+    let mut parser = Parser { tokens: &tokens, position: 0, operations: 0 };
+    let value = parser.expression()?;
+    if parser.position != tokens.len() || parser.operations == 0 || !value.is_finite() {
+        return None;
+    }
+    Some(value)
+}
+
+/// Up to 10 decimals without trailing zeros, scientific notation for very
+/// large or small magnitudes
+pub fn format_number(value: f64) -> String {
+    format_decimals(value, 10)
+}
+
+fn format_decimals(value: f64, decimals: usize) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let magnitude = value.abs();
+    if !(1e-6..1e15).contains(&magnitude) {
+        return format!("{:e}", value);
+    }
+    let formatted = format!("{:.*}", decimals, value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                // "2024-01-15" is a date, not a subtraction
+                if number.len() > 1 && number.starts_with('0') && !number.starts_with("0.") {
+                    return None;
+                }
+                tokens.push(Token::Number(number.parse().ok()?));
+            }
+            'a'..='z' | 'A'..='Z' => {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                tokens.push(Token::Identifier(name.to_ascii_lowercase()));
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' => {
+                tokens.push(Token::Operator(c));
+                i += 1;
+            }
+            '×' => {
+                tokens.push(Token::Operator('*'));
+                i += 1;
+            }
+            '÷' => {
+                tokens.push(Token::Operator('/'));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+// Recursive descent over:
+//   expression = term (("+" | "-") term)*
+//   term       = unary (("*" | "/" | "%") unary)*
+//   unary      = "-" unary | "+" unary | power
+//   power      = primary ("^" unary)?
+//   primary    = number | constant | function "(" expression ")" | "(" expression ")"
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// Operators and function calls seen, to tell "2+2" from "2"
+    operations: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn operator(&mut self, operators: &[char]) -> Option<char> {
+        match self.peek() {
+            Some(Token::Operator(op)) if operators.contains(op) => {
+                let op = *op;
+                self.position += 1;
+                self.operations += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expression(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        while let Some(op) = self.operator(&['+', '-']) {
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Some(value)
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        while let Some(op) = self.operator(&['*', '/', '%']) {
+            let rhs = self.unary()?;
+            if op != '*' && rhs == 0.0 {
+                return None;
+            }
+            value = match op {
+                '*' => value * rhs,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Some(value)
+    }
+
+    fn unary(&mut self) -> Option<f64> {
+        // A sign alone doesn't make "-5" a calculation
+        match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.position += 1;
+                Some(-self.unary()?)
+            }
+            Some(Token::Operator('+')) => {
+                self.position += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Option<f64> {
+        let base = self.primary()?;
+        if self.operator(&['^']).is_some() {
+            return Some(base.powf(self.unary()?));
+        }
+        Some(base)
+    }
+
+    fn primary(&mut self) -> Option<f64> {
+        match self.next()? {
+            Token::Number(value) => Some(value),
+            Token::Open => {
+                let value = self.expression()?;
+                (self.next()? == Token::Close).then_some(value)
+            }
+            Token::Identifier(name) => match name.as_str() {
+                "pi" => Some(std::f64::consts::PI),
+                "e" => Some(std::f64::consts::E),
+                _ => {
+                    if self.next()? != Token::Open {
+                        return None;
+                    }
+                    let argument = self.expression()?;
+                    if self.next()? != Token::Close {
+                        return None;
+                    }
+                    self.operations += 1;
+                    apply_function(&name, argument)
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+fn apply_function(name: &str, x: f64) -> Option<f64> {
+    let value = match name {
+        "sqrt" if x >= 0.0 => x.sqrt(),
+        "abs" => x.abs(),
+        "round" => x.round(),
+        "floor" => x.floor(),
+        "ceil" => x.ceil(),
+        "ln" if x > 0.0 => x.ln(),
+        "log" if x > 0.0 => x.log10(),
+        "sin" => x.sin(),
+        "cos" => x.cos(),
+        "tan" => x.tan(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(query: &str) -> Option<String> {
+        evaluate(query).map(|evaluation| evaluation.result)
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(result("42*1.19").as_deref(), Some("49.98"));
+        assert_eq!(result("1 + 2 * 3").as_deref(), Some("7"));
+        assert_eq!(result("(1 + 2) * 3").as_deref(), Some("9"));
+        assert_eq!(result("10 / 4").as_deref(), Some("2.5"));
+        assert_eq!(result("10 % 4").as_deref(), Some("2"));
+        assert_eq!(result("2^10").as_deref(), Some("1024"));
+        assert_eq!(result("2^3^2").as_deref(), Some("512"), "Powers are right associative");
+        assert_eq!(result("-2^2").as_deref(), Some("-4"));
+        assert_eq!(result("6 × 7 ÷ 2").as_deref(), Some("21"));
+        assert_eq!(result("0.1 + 0.2").as_deref(), Some("0.3"));
+    }
+
+    #[test]
+    fn test_functions_and_constants() {
+        assert_eq!(result("sqrt(16)").as_deref(), Some("4"));
+        assert_eq!(result("2 * pi").as_deref(), Some("6.2831853072"));
+        assert_eq!(result("round(2.5) + floor(1.9)").as_deref(), Some("4"));
+        assert_eq!(result("log(1000)").as_deref(), Some("3"));
+        assert_eq!(result("abs(-3)").as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn test_rejects_non_calculations() {
+        for query in [
+            "", "42", "-5", "pi", "hello", "2 +", "(1 + 2", "1 + 2)", "1..2 + 1", "1 / 0", "5 % 0",
+            "sqrt(-1)", "foo(2)", "2 2", "1,000 + 1", "type:url", "2024-01-15", "x + 1",
+        ] {
+            assert_eq!(result(query), None, "{query:?}");
+        }
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(3.0), "3");
+        assert_eq!(format_number(-0.0), "0");
+        assert_eq!(format_number(1.0 / 3.0), "0.3333333333");
+        assert_eq!(format_number(2e20), "2e20");
+        assert_eq!(format_number(-1.5e-9), "-1.5e-9");
+    }
+}
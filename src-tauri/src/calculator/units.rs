@@ -0,0 +1,172 @@
+// Unit conversions written as "<number> <unit> to <unit>", with "in", "as"
+// and "->" accepted in place of "to". Both units must measure the same
+// dimension.
+
+use super::format_decimals;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Volume,
+    Time,
+    Data,
+    Speed,
+    Area,
+    Temperature,
+}
+
+struct Unit {
+    /// How the unit is written in results
+    symbol: &'static str,
+    /// Lowercase spellings accepted in queries, besides the symbol
+    aliases: &'static [&'static str],
+    dimension: Dimension,
+    /// Value of one unit in the dimension's base unit
+    factor: f64,
+    /// Added after scaling; only temperatures have one
+    offset: f64,
+}
+
+const fn unit(symbol: &'static str, aliases: &'static [&'static str], dimension: Dimension, factor: f64) -> Unit {
+    Unit { symbol, aliases, dimension, factor, offset: 0.0 }
+}
+
+// Base units: metre, kilogram, litre, second, byte, metre per second,
+// square metre and kelvin
+const UNITS: &[Unit] = &[
+    unit("mm", &["millimeter", "millimeters", "millimetre", "millimetres"], Dimension::Length, 0.001),
+    unit("cm", &["centimeter", "centimeters", "centimetre", "centimetres"], Dimension::Length, 0.01),
+    unit("m", &["meter", "meters", "metre", "metres"], Dimension::Length, 1.0),
+    unit("km", &["kilometer", "kilometers", "kilometre", "kilometres"], Dimension::Length, 1000.0),
+    unit("in", &["inch", "inches", "\""], Dimension::Length, 0.0254),
+    unit("ft", &["foot", "feet", "'"], Dimension::Length, 0.3048),
+    unit("yd", &["yard", "yards"], Dimension::Length, 0.9144),
+    unit("mi", &["mile", "miles"], Dimension::Length, 1609.344),
+    unit("nmi", &["nautical mile", "nautical miles"], Dimension::Length, 1852.0),
+    unit("mg", &["milligram", "milligrams"], Dimension::Mass, 0.000001),
+    unit("g", &["gram", "grams"], Dimension::Mass, 0.001),
+    unit("kg", &["kilogram", "kilograms", "kilo", "kilos"], Dimension::Mass, 1.0),
+    unit("t", &["tonne", "tonnes"], Dimension::Mass, 1000.0),
+    unit("oz", &["ounce", "ounces"], Dimension::Mass, 0.028349523125),
+    unit("lb", &["lbs", "pound", "pounds"], Dimension::Mass, 0.45359237),
+    unit("st", &["stone", "stones"], Dimension::Mass, 6.35029318),
+    unit("ml", &["milliliter", "milliliters", "millilitre", "millilitres"], Dimension::Volume, 0.001),
+    unit("l", &["liter", "liters", "litre", "litres"], Dimension::Volume, 1.0),
+    unit("tsp", &["teaspoon", "teaspoons"], Dimension::Volume, 0.00492892159375),
+    unit("tbsp", &["tablespoon", "tablespoons"], Dimension::Volume, 0.01478676478125),
+    unit("fl oz", &["floz", "fluid ounce", "fluid ounces"], Dimension::Volume, 0.0295735295625),
+    unit("cup", &["cups"], Dimension::Volume, 0.2365882365),
+    unit("pt", &["pint", "pints"], Dimension::Volume, 0.473176473),
+    unit("qt", &["quart", "quarts"], Dimension::Volume, 0.946352946),
+    unit("gal", &["gallon", "gallons"], Dimension::Volume, 3.785411784),
+    unit("ms", &["millisecond", "milliseconds"], Dimension::Time, 0.001),
+    unit("s", &["sec", "secs", "second", "seconds"], Dimension::Time, 1.0),
+    unit("min", &["mins", "minute", "minutes"], Dimension::Time, 60.0),
+    unit("h", &["hr", "hrs", "hour", "hours"], Dimension::Time, 3600.0),
+    unit("d", &["day", "days"], Dimension::Time, 86400.0),
+    unit("wk", &["week", "weeks"], Dimension::Time, 604800.0),
+    unit("B", &["byte", "bytes"], Dimension::Data, 1.0),
+    unit("KB", &["kilobyte", "kilobytes"], Dimension::Data, 1e3),
+    unit("MB", &["megabyte", "megabytes"], Dimension::Data, 1e6),
+    unit("GB", &["gigabyte", "gigabytes"], Dimension::Data, 1e9),
+    unit("TB", &["terabyte", "terabytes"], Dimension::Data, 1e12),
+    unit("KiB", &["kibibyte", "kibibytes"], Dimension::Data, 1024.0),
+    unit("MiB", &["mebibyte", "mebibytes"], Dimension::Data, 1048576.0),
+    unit("GiB", &["gibibyte", "gibibytes"], Dimension::Data, 1073741824.0),
+    unit("TiB", &["tebibyte", "tebibytes"], Dimension::Data, 1099511627776.0),
+    unit("m/s", &["mps"], Dimension::Speed, 1.0),
+    unit("km/h", &["kmh", "kph"], Dimension::Speed, 1000.0 / 3600.0),
+    unit("mph", &[], Dimension::Speed, 0.44704),
+    unit("kn", &["knot", "knots", "kt"], Dimension::Speed, 1852.0 / 3600.0),
+    unit("m²", &["m2", "sqm"], Dimension::Area, 1.0),
+    unit("km²", &["km2"], Dimension::Area, 1e6),
+    unit("ft²", &["ft2", "sqft"], Dimension::Area, 0.09290304),
+    unit("ha", &["hectare", "hectares"], Dimension::Area, 10000.0),
+    unit("ac", &["acre", "acres"], Dimension::Area, 4046.8564224),
+    Unit { symbol: "°C", aliases: &["c", "celsius"], dimension: Dimension::Temperature, factor: 1.0, offset: 273.15 },
+    Unit { symbol: "°F", aliases: &["f", "fahrenheit"], dimension: Dimension::Temperature, factor: 5.0 / 9.0, offset: 459.67 * 5.0 / 9.0 },
+    Unit { symbol: "K", aliases: &["k", "kelvin"], dimension: Dimension::Temperature, factor: 1.0, offset: 0.0 },
+];
+
+const SEPARATORS: [&str; 4] = [" to ", " in ", " as ", "->"];
+
+/// Convert "3 km to mi" into "1.864114 mi"
+pub fn convert(query: &str) -> Option<String> {
+    let lower = query.to_lowercase();
+    // The last separator wins so "5 in in cm" reads as inches to centimetres
+    let (index, separator) = SEPARATORS
+        .iter()
+        .filter_map(|separator| lower.rfind(separator).map(|index| (index, *separator)))
+        .max_by_key(|(index, _)| *index)?;
+    let source = &lower[..index];
+    let target = find_unit(&lower[index + separator.len()..])?;
+
+    let (value, source) = split_quantity(source)?;
+    let source = find_unit(source)?;
+    if source.dimension != target.dimension {
+        return None;
+    }
+
+    let base = value * source.factor + source.offset;
+    let converted = (base - target.offset) / target.factor;
+    if !converted.is_finite() {
+        return None;
+    }
+    Some(format!("{} {}", format_decimals(converted, 6), target.symbol))
+}
+
+/// Split "3.5km" or "-40 f" into the number and the unit text
+fn split_quantity(text: &str) -> Option<(f64, &str)> {
+    let text = text.trim();
+    let end = text
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && (*c == '-' || *c == '+'))))
+        .map(|(i, _)| i)?;
+    let value = text[..end].parse::<f64>().ok()?;
+    Some((value, &text[end..]))
+}
+
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim();
+    let name = name.strip_prefix('°').unwrap_or(name);
+    UNITS
+        .iter()
+        .find(|unit| unit.symbol.to_lowercase() == name || unit.aliases.contains(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(convert("3 km to mi").as_deref(), Some("1.864114 mi"));
+        assert_eq!(convert("10 in to cm").as_deref(), Some("25.4 cm"));
+        assert_eq!(convert("5 in in cm").as_deref(), Some("12.7 cm"));
+        assert_eq!(convert("1.5kg in lb").as_deref(), Some("3.306934 lb"));
+        assert_eq!(convert("2 hours to minutes").as_deref(), Some("120 min"));
+        assert_eq!(convert("1 GiB to MB").as_deref(), Some("1073.741824 MB"));
+        assert_eq!(convert("100 km/h -> mph").as_deref(), Some("62.137119 mph"));
+        assert_eq!(convert("1 gallon as l").as_deref(), Some("3.785412 l"));
+        assert_eq!(convert("1 acre to m2").as_deref(), Some("4046.856422 m²"));
+    }
+
+    #[test]
+    fn test_temperatures() {
+        assert_eq!(convert("100 c to f").as_deref(), Some("212 °F"));
+        assert_eq!(convert("-40 °F to °C").as_deref(), Some("-40 °C"));
+        assert_eq!(convert("0 k to celsius").as_deref(), Some("-273.15 °C"));
+        assert_eq!(convert("98.6 fahrenheit to c").as_deref(), Some("37 °C"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_conversions() {
+        for query in [
+            "3 km to kg", "km to mi", "3 km to", "3 parsecs to km", "to mi", "how to cook rice",
+            "3 km mi", "1.2.3 km to mi", "- km to mi", "welcome in the city",
+        ] {
+            assert_eq!(convert(query), None, "{query:?}");
+        }
+    }
+}
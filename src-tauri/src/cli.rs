@@ -0,0 +1,536 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::json;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use std::path::{Path, PathBuf};
+
+use crate::database::{self, ClipboardEntry, ClipboardSearchParams, Database, DatabaseOptions, ExportFilter};
+use crate::settings;
+use crate::transfer::{self, ExportFormat, ForeignSource};
+
+// Must match `identifier` in tauri.conf.json, which decides the app data dir
+pub(crate) const APP_IDENTIFIER: &str = "com.so2liu.superkbd";
+
+const USAGE: &str = "Usage: superkbd <command> [options]
+
+Commands:
+  list [--limit N] [--favorites]   Show recent entries
+  search <query> [--limit N]       Show entries containing <query>
+  get <id>                         Print an entry's content
+  pin <id> / unpin <id>            Mark or unmark an entry as favorite
+  delete <id>                      Delete an entry
+  clear --older-than <age>         Delete non-favorite entries older than <age> (e.g. 30d, 2w)
+  export <file> [--archive] [--favorites]
+                                   Export history as JSON Lines, or as a .tar.gz with images
+  import <file> [--dry-run]        Merge an export into the history, skipping duplicates
+  import --from <manager> [path] [--dry-run]
+                                   Import from copyq (a synced tab directory), gpaste or clipman
+
+Options:
+  --json        Print JSON instead of plain text
+  --db <path>   Use this database instead of the app's (or set SUPERKBD_DB)
+
+Run without a command to start the app.";
+
+const DEFAULT_LIMIT: i64 = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    List { limit: i64, favorites_only: bool },
+    Search { query: String, limit: i64 },
+    Get { id: i64 },
+    SetFavorite { id: i64, favorite: bool },
+    Delete { id: i64 },
+    Clear { older_than_days: i64 },
+    Export { path: PathBuf, format: ExportFormat, favorites_only: bool },
+    Import { path: PathBuf, dry_run: bool },
+    ImportFrom { source: ForeignSource, path: Option<PathBuf>, dry_run: bool },
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Invocation {
+    command: Command,
+    json: bool,
+    db_path: Option<PathBuf>,
+}
+
+/// Run a CLI command if `args` (without the program name) start with one.
+/// Returns the exit code, or None when the app should start normally.
+pub fn run(args: &[String]) -> Option<i32> {
+    let first = args.first()?;
+    if !is_command(first) {
+        return None;
+    }
+
+    let invocation = match parse_args(args) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("superkbd: {}\n\n{}", e, USAGE);
+            return Some(2);
+        }
+    };
+
+    if invocation.command == Command::Help {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("superkbd: {}", e);
+            return Some(1);
+        }
+    };
+
+    match runtime.block_on(execute(invocation)) {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            Some(0)
+        }
+        Err(e) => {
+            eprintln!("superkbd: {:#}", e);
+            Some(1)
+        }
+    }
+}
+
+fn is_command(arg: &str) -> bool {
+    matches!(
+        arg,
+        "list" | "search" | "get" | "pin" | "unpin" | "delete" | "clear" | "export" | "import" | "help" | "--help" | "-h"
+    )
+}
+
+fn parse_args(args: &[String]) -> Result<Invocation> {
+    let mut json = false;
+    let mut db_path = None;
+    let mut limit = None;
+    let mut favorites_only = false;
+    let mut older_than = None;
+    let mut archive = false;
+    let mut dry_run = false;
+    let mut from = None;
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--help" | "-h" => positional.push("help"),
+            "--json" => json = true,
+            "--favorites" => favorites_only = true,
+            "--archive" => archive = true,
+            "--dry-run" => dry_run = true,
+            "--db" => db_path = Some(PathBuf::from(flag_value(&mut iter, "--db")?)),
+            "--limit" => {
+                let value = flag_value(&mut iter, "--limit")?;
+                let value: i64 = value.parse().map_err(|_| anyhow!("Invalid limit: {}", value))?;
+                if value < 1 {
+                    bail!("--limit must be at least 1");
+                }
+                limit = Some(value);
+            }
+            "--from" => {
+                let value = flag_value(&mut iter, "--from")?;
+                from = Some(ForeignSource::parse(value).ok_or_else(|| anyhow!("Unknown clipboard manager: {}", value))?);
+            }
+            "--older-than" => older_than = Some(parse_age_days(flag_value(&mut iter, "--older-than")?)?),
+            other if other.starts_with("--") && other.len() > 2 => bail!("Unknown option {}", other),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let command = match positional.as_slice() {
+        ["list"] => Command::List { limit, favorites_only },
+        ["search", query @ ..] if !query.is_empty() => Command::Search { query: query.join(" "), limit },
+        ["get", id] => Command::Get { id: parse_id(id)? },
+        ["pin", id] => Command::SetFavorite { id: parse_id(id)?, favorite: true },
+        ["unpin", id] => Command::SetFavorite { id: parse_id(id)?, favorite: false },
+        ["delete", id] => Command::Delete { id: parse_id(id)? },
+        ["clear"] => Command::Clear {
+            older_than_days: older_than.ok_or_else(|| anyhow!("clear needs --older-than <age>"))?,
+        },
+        ["export", path] => Command::Export {
+            path: PathBuf::from(path),
+            format: if archive { ExportFormat::Archive } else { ExportFormat::Jsonl },
+            favorites_only,
+        },
+        ["import"] => Command::ImportFrom {
+            source: from.ok_or_else(|| anyhow!("import needs a file or --from <manager>"))?,
+            path: None,
+            dry_run,
+        },
+        ["import", path] => match from {
+            Some(source) => Command::ImportFrom { source, path: Some(PathBuf::from(path)), dry_run },
+            None => Command::Import { path: PathBuf::from(path), dry_run },
+        },
+        ["help", ..] | [_, .., "help"] => Command::Help,
+        [command, ..] => bail!("Wrong arguments for {}", command),
+        [] => bail!("Missing command"),
+    };
+
+    Ok(Invocation { command, json, db_path })
+}
+
+fn flag_value<'a>(iter: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<&'a str> {
+    iter.next()
+        .map(String::as_str)
+        .ok_or_else(|| anyhow!("{} needs a value", flag))
+}
+
+fn parse_id(value: &str) -> Result<i64> {
+    value.parse().map_err(|_| anyhow!("Invalid entry id: {}", value))
+}
+
+/// "30d", "2w" or a plain number of days
+fn parse_age_days(value: &str) -> Result<i64> {
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 'd')) => (&value[..i], 1),
+        Some((i, 'w')) => (&value[..i], 7),
+        _ => (value, 1),
+    };
+
+    let number: i64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid age {}. Use e.g. 30d or 2w", value))?;
+    if number < 0 {
+        bail!("Age can't be negative");
+    }
+
+    Ok(number * multiplier)
+}
+
+fn default_db_path() -> Result<PathBuf> {
+    if let Ok(path) = std::env::var("SUPERKBD_DB") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let data_dir = dirs::data_dir().ok_or_else(|| anyhow!("Can't find the app data directory"))?;
+    Ok(data_dir.join(APP_IDENTIFIER).join("clipboard.db"))
+}
+
+/// Connection settings stored with the app's other settings. They're read
+/// over a read-only connection that sets no pragmas: the journal mode sticks
+/// to the file, so opening with the defaults would switch it back to WAL,
+/// even under the running app.
+async fn stored_database_options(db_path: &Path) -> DatabaseOptions {
+    if !db_path.exists() {
+        return DatabaseOptions::default();
+    }
+
+    let stored = async {
+        let options = SqliteConnectOptions::new().filename(db_path).read_only(true);
+        let pool = SqlitePoolOptions::new().max_connections(1).connect_with(options).await?;
+        let loaded = settings::load(&pool).await;
+        pool.close().await;
+        loaded
+    };
+    match stored.await {
+        Ok(loaded) => loaded.database,
+        Err(e) => {
+            println!("⚠️  [WARNING] Couldn't read the database settings, using defaults: {:#}", e);
+            DatabaseOptions::default()
+        }
+    }
+}
+
+async fn execute(invocation: Invocation) -> Result<String> {
+    let db_path = match invocation.db_path {
+        Some(path) => path,
+        None => default_db_path()?,
+    };
+    let options = stored_database_options(&db_path).await;
+    let db = Database::open(db_path.clone(), &options)
+        .await
+        .with_context(|| format!("Failed to open {}", db_path.display()))?;
+    let pool = db.pool();
+    let json = invocation.json;
+
+    let output = match invocation.command {
+        Command::List { limit, favorites_only } => {
+            let params = ClipboardSearchParams { favorites_only, limit, ..Default::default() };
+            format_entries(&database::search_entries(pool, params).await?, json)?
+        }
+        Command::Search { query, limit } => {
+            let params = ClipboardSearchParams { query: Some(query), limit, ..Default::default() };
+            format_entries(&database::search_entries(pool, params).await?, json)?
+        }
+        Command::Get { id } => {
+            let entry = database::get_entry(pool, id)
+                .await?
+                .ok_or_else(|| anyhow!("No entry with id {}", id))?;
+            if json {
+                serde_json::to_string_pretty(&entry)?
+            } else {
+                match database::offload::load_text(&entry).await? {
+                    Some(text) => text,
+                    None => entry.file_path.unwrap_or_default(),
+                }
+            }
+        }
+        Command::SetFavorite { id, favorite } => {
+            if !database::set_favorite(pool, id, favorite).await? {
+                bail!("No entry with id {}", id);
+            }
+            if json {
+                json!({ "id": id, "favorite": favorite }).to_string()
+            } else if favorite {
+                format!("Pinned {}", id)
+            } else {
+                format!("Unpinned {}", id)
+            }
+        }
+        Command::Delete { id } => {
+            if !database::delete_entry(pool, id).await? {
+                bail!("No entry with id {}", id);
+            }
+            if json {
+                json!({ "id": id, "deleted": true }).to_string()
+            } else {
+                format!("Deleted {}", id)
+            }
+        }
+        Command::Clear { older_than_days } => {
+            let deleted = database::delete_old_entries(pool, older_than_days).await?;
+            if json {
+                json!({ "deleted": deleted }).to_string()
+            } else {
+                format!("Deleted {} entries", deleted)
+            }
+        }
+        Command::Export { path, format, favorites_only } => {
+            let filter = ExportFilter { favorites_only, ..Default::default() };
+            let summary = transfer::export_history(pool, &path, format, &filter).await?;
+            if json {
+                serde_json::to_string_pretty(&summary)?
+            } else {
+                format!("Exported {} entries ({} images) to {}", summary.entries, summary.blobs, path.display())
+            }
+        }
+        Command::Import { path, dry_run } => {
+            let report = transfer::import_history(pool, &path, &blob_dir(&db_path), dry_run).await?;
+            if json {
+                serde_json::to_string_pretty(&report)?
+            } else {
+                format_import_report(&report)
+            }
+        }
+        Command::ImportFrom { source, path, dry_run } => {
+            let report = transfer::import_from_manager(pool, source, path.as_deref(), &blob_dir(&db_path), dry_run).await?;
+            if json {
+                serde_json::to_string_pretty(&report)?
+            } else {
+                format_import_report(&report)
+            }
+        }
+        Command::Help => USAGE.to_string(),
+    };
+
+    db.pool().close().await;
+    Ok(output)
+}
+
+/// Same place the app keeps images, next to the database
+fn blob_dir(db_path: &std::path::Path) -> PathBuf {
+    db_path.parent().map(|dir| dir.join("blobs")).unwrap_or_else(|| PathBuf::from("blobs"))
+}
+
+fn format_import_report(report: &transfer::ImportReport) -> String {
+    let mut lines = vec![format!(
+        "{}{} imported, {} duplicates ({} favorites updated), {} skipped, {} invalid of {} entries",
+        if report.dry_run { "Dry run: " } else { "" },
+        report.imported,
+        report.duplicates,
+        report.favorites_updated,
+        report.skipped,
+        report.invalid,
+        report.total
+    )];
+    lines.extend(report.errors.iter().map(|error| format!("  {}", error)));
+    lines.join("\n")
+}
+
+fn format_entries(entries: &[ClipboardEntry], json: bool) -> Result<String> {
+    if json {
+        return Ok(serde_json::to_string_pretty(entries)?);
+    }
+
+    Ok(entries.iter().map(format_entry_line).collect::<Vec<_>>().join("\n"))
+}
+
+/// One line per entry: id, favorite marker, time and a single-line preview
+fn format_entry_line(entry: &ClipboardEntry) -> String {
+    let time = chrono::DateTime::from_timestamp(entry.created_at, 0)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let content = entry.text_content.as_deref().or(entry.file_path.as_deref()).unwrap_or("");
+
+    format!(
+        "{}\t{}\t{}\t{}",
+        entry.id,
+        if entry.favorite { "*" } else { " " },
+        time,
+        preview(content, 80)
+    )
+}
+
+fn preview(content: &str, max_chars: usize) -> String {
+    let single_line = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if single_line.chars().count() > max_chars {
+        let truncated: String = single_line.chars().take(max_chars - 1).collect();
+        format!("{}…", truncated)
+    } else {
+        single_line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_gui_launch_is_not_a_command() {
+        assert_eq!(run(&[]), None);
+        assert_eq!(run(&args("-psn_0_12345")), None, "macOS may pass a process serial number");
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let invocation = parse_args(&args("list --limit 5 --favorites --json")).unwrap();
+        assert_eq!(invocation.command, Command::List { limit: 5, favorites_only: true });
+        assert!(invocation.json);
+
+        let invocation = parse_args(&args("list")).unwrap();
+        assert_eq!(invocation.command, Command::List { limit: DEFAULT_LIMIT, favorites_only: false });
+        assert!(!invocation.json);
+    }
+
+    #[test]
+    fn test_parse_search_joins_words() {
+        let invocation = parse_args(&args("search hello world --db /tmp/x.db")).unwrap();
+        assert_eq!(invocation.command, Command::Search { query: "hello world".to_string(), limit: DEFAULT_LIMIT });
+        assert_eq!(invocation.db_path, Some(PathBuf::from("/tmp/x.db")));
+    }
+
+    #[test]
+    fn test_parse_id_commands() {
+        assert_eq!(parse_args(&args("get 42")).unwrap().command, Command::Get { id: 42 });
+        assert_eq!(parse_args(&args("pin 42")).unwrap().command, Command::SetFavorite { id: 42, favorite: true });
+        assert_eq!(parse_args(&args("unpin 42")).unwrap().command, Command::SetFavorite { id: 42, favorite: false });
+        assert!(parse_args(&args("get abc")).is_err());
+        assert!(parse_args(&args("get")).is_err());
+    }
+
+    #[test]
+    fn test_parse_clear() {
+        assert_eq!(parse_args(&args("clear --older-than 30d")).unwrap().command, Command::Clear { older_than_days: 30 });
+        assert_eq!(parse_args(&args("clear --older-than 2w")).unwrap().command, Command::Clear { older_than_days: 14 });
+        assert!(parse_args(&args("clear")).is_err(), "Clearing needs an explicit age");
+    }
+
+    #[test]
+    fn test_parse_export_import() {
+        assert_eq!(
+            parse_args(&args("export backup.tar.gz --archive")).unwrap().command,
+            Command::Export { path: PathBuf::from("backup.tar.gz"), format: ExportFormat::Archive, favorites_only: false }
+        );
+        assert_eq!(
+            parse_args(&args("import backup.jsonl --dry-run")).unwrap().command,
+            Command::Import { path: PathBuf::from("backup.jsonl"), dry_run: true }
+        );
+        assert!(parse_args(&args("import")).is_err());
+    }
+
+    #[test]
+    fn test_parse_import_from_manager() {
+        assert_eq!(
+            parse_args(&args("import --from gpaste")).unwrap().command,
+            Command::ImportFrom { source: ForeignSource::GPaste, path: None, dry_run: false }
+        );
+        assert_eq!(
+            parse_args(&args("import --from copyq /tmp/tab --dry-run")).unwrap().command,
+            Command::ImportFrom { source: ForeignSource::CopyQ, path: Some(PathBuf::from("/tmp/tab")), dry_run: true }
+        );
+        assert!(parse_args(&args("import --from klipper")).is_err());
+    }
+
+    #[test]
+    fn test_parse_age_days() {
+        assert_eq!(parse_age_days("7").unwrap(), 7);
+        assert_eq!(parse_age_days("0d").unwrap(), 0);
+        assert!(parse_age_days("30m").is_err());
+        assert!(parse_age_days("-1d").is_err());
+        assert!(parse_age_days("d").is_err());
+    }
+
+    #[test]
+    fn test_parse_help() {
+        assert_eq!(parse_args(&args("--help")).unwrap().command, Command::Help);
+        assert_eq!(parse_args(&args("list -h")).unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn test_unknown_option() {
+        assert!(parse_args(&args("list --verbose")).is_err());
+    }
+
+    #[test]
+    fn test_preview_is_single_line_and_truncated() {
+        assert_eq!(preview("hello\n  world", 80), "hello world");
+        assert_eq!(preview("abcdef", 4), "abc…");
+    }
+
+    #[tokio::test]
+    async fn test_execute_against_database_file() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let db_path = temp_dir.path().join("cli.db");
+
+        let db = Database::new(db_path.clone()).await?;
+        let id = database::insert_entry(db.pool(), database::NewClipboardEntry::new_text("from the gui".to_string())).await?;
+
+        let invocation = |command| Invocation { command, json: false, db_path: Some(db_path.clone()) };
+
+        assert_eq!(execute(invocation(Command::Get { id })).await?, "from the gui");
+        assert_eq!(execute(invocation(Command::SetFavorite { id, favorite: true })).await?, format!("Pinned {}", id));
+        assert!(database::get_entry(db.pool(), id).await?.unwrap().favorite, "Changes are visible to the open GUI pool");
+
+        let listed = execute(Invocation { json: true, ..invocation(Command::List { limit: 5, favorites_only: false }) }).await?;
+        let entries: Vec<ClipboardEntry> = serde_json::from_str(&listed)?;
+        assert_eq!(entries.len(), 1);
+
+        assert!(execute(invocation(Command::Get { id: id + 1 })).await.is_err());
+
+        db.pool().close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stored_journal_mode_is_kept() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let db_path = temp_dir.path().join("cli.db");
+
+        let stored = settings::Settings {
+            database: DatabaseOptions { journal_mode: database::options::JournalMode::Delete, ..Default::default() },
+            ..Default::default()
+        };
+        let db = Database::open(db_path.clone(), &stored.database).await?;
+        settings::save(db.pool(), &stored).await?;
+        db.pool().close().await;
+
+        let invocation = Invocation { command: Command::List { limit: 5, favorites_only: false }, json: false, db_path: Some(db_path.clone()) };
+        execute(invocation).await?;
+
+        let pool = SqlitePoolOptions::new().connect_with(SqliteConnectOptions::new().filename(&db_path)).await?;
+        let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode").fetch_one(&pool).await?;
+        assert_eq!(journal_mode, "delete");
+        pool.close().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,450 @@
+use serde::{Deserialize, Serialize};
+
+use super::language::{detect_language, Language};
+
+/// What a text entry looks like it is. Stored in the `kind` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Url,
+    Email,
+    Color,
+    Path,
+    Phone,
+    Timestamp,
+    Json,
+    Code,
+    Text,
+}
+
+impl ContentKind {
+    pub const ALL: [ContentKind; 9] = [
+        ContentKind::Url,
+        ContentKind::Email,
+        ContentKind::Color,
+        ContentKind::Path,
+        ContentKind::Phone,
+        ContentKind::Timestamp,
+        ContentKind::Json,
+        ContentKind::Code,
+        ContentKind::Text,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentKind::Url => "url",
+            ContentKind::Email => "email",
+            ContentKind::Color => "color",
+            ContentKind::Path => "path",
+            ContentKind::Phone => "phone",
+            ContentKind::Timestamp => "timestamp",
+            ContentKind::Json => "json",
+            ContentKind::Code => "code",
+            ContentKind::Text => "text",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Classification {
+    pub kind: ContentKind,
+    /// 0.0 to 1.0; how sure the matching rule is
+    pub confidence: f64,
+    /// Language of code, data formats and shell commands
+    pub language: Option<Language>,
+}
+
+impl Classification {
+    fn new(kind: ContentKind, confidence: f64) -> Self {
+        Self { kind, confidence, language: None }
+    }
+}
+
+/// Texts longer than this are only checked for JSON and code
+const SINGLE_VALUE_MAX_LEN: usize = 2048;
+
+/// Classify copied text. Pure and deterministic: the same text always gets the
+/// same kind, so stored kinds can be recomputed at any time.
+pub fn classify(text: &str) -> Classification {
+    let mut classification = classify_kind(text);
+    classification.language = match classification.kind {
+        ContentKind::Json => Some(Language::Json),
+        // Shell commands and SQL read like text to the kind rules
+        ContentKind::Code | ContentKind::Text => detect_language(text),
+        _ => None,
+    };
+    classification
+}
+
+fn classify_kind(text: &str) -> Classification {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return Classification::new(ContentKind::Text, 1.0);
+    }
+
+    if trimmed.len() <= SINGLE_VALUE_MAX_LEN && !trimmed.contains('\n') {
+        let single_value_rules: [fn(&str) -> Option<Classification>; 6] =
+            [classify_url, classify_email, classify_color, classify_path, classify_timestamp, classify_phone];
+        if let Some(classification) = single_value_rules.iter().find_map(|rule| rule(trimmed)) {
+            return classification;
+        }
+    }
+
+    if let Some(classification) = classify_json(trimmed) {
+        return classification;
+    }
+    if let Some(classification) = classify_code(trimmed) {
+        return classification;
+    }
+
+    Classification::new(ContentKind::Text, 0.5)
+}
+
+fn classify_url(text: &str) -> Option<Classification> {
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let lower = text.to_ascii_lowercase();
+    for scheme in ["https://", "http://", "ftp://", "ftps://", "ws://", "wss://"] {
+        if let Some(rest) = lower.strip_prefix(scheme) {
+            let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+            return is_host(host.rsplit('@').next().unwrap_or("")).then(|| Classification::new(ContentKind::Url, 0.95));
+        }
+    }
+    if lower.starts_with("file:///") || (lower.starts_with("mailto:") && lower.contains('@')) {
+        return Some(Classification::new(ContentKind::Url, 0.9));
+    }
+    if let Some(rest) = lower.strip_prefix("www.") {
+        let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+        return (host.contains('.') && is_host(host)).then(|| Classification::new(ContentKind::Url, 0.8));
+    }
+    None
+}
+
+/// "example.org", "localhost:8080", "127.0.0.1": labels of letters, digits and
+/// hyphens, with an optional port
+fn is_host(host: &str) -> bool {
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) => name,
+        Some(_) => return false,
+        None => host,
+    };
+    !name.is_empty()
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+fn classify_email(text: &str) -> Option<Classification> {
+    let (local, domain) = text.split_once('@')?;
+    let local_ok = !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && local.chars().all(|c| c.is_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c));
+    let tld = domain.rsplit('.').next().unwrap_or("");
+    let domain_ok = domain.contains('.') && is_host(domain) && tld.len() >= 2 && tld.chars().all(char::is_alphabetic);
+
+    (local_ok && domain_ok).then(|| Classification::new(ContentKind::Email, 0.9))
+}
+
+fn classify_color(text: &str) -> Option<Classification> {
+    if let Some(hex) = text.strip_prefix('#') {
+        let valid = matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+        // "#123" is as likely an issue number as a color
+        let confidence = if hex.chars().all(|c| c.is_ascii_digit()) { 0.6 } else { 0.95 };
+        return valid.then(|| Classification::new(ContentKind::Color, confidence));
+    }
+
+    let lower = text.to_ascii_lowercase();
+    let (function, arguments) = lower.split_once('(')?;
+    let arguments = arguments.strip_suffix(')')?;
+    if !matches!(function.trim(), "rgb" | "rgba" | "hsl" | "hsla") {
+        return None;
+    }
+    let count = arguments.split([',', ' ', '/']).filter(|part| !part.is_empty()).count();
+    let numeric = arguments
+        .chars()
+        .all(|c| c.is_ascii_digit() || " ,./%deg".contains(c));
+
+    (numeric && (3..=4).contains(&count)).then(|| Classification::new(ContentKind::Color, 0.9))
+}
+
+fn classify_path(text: &str) -> Option<Classification> {
+    let bytes = text.as_bytes();
+    let windows_drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    if windows_drive || text.starts_with("\\\\") {
+        return Some(Classification::new(ContentKind::Path, 0.85));
+    }
+
+    let rest = ["~/", "./", "../", "/"].iter().find_map(|prefix| text.strip_prefix(prefix))?;
+    // "/" alone, "//comment" and "/ 2" aren't paths
+    if rest.is_empty() || rest.starts_with('/') || rest.starts_with(' ') {
+        return None;
+    }
+    // A path has few spaces; a sentence starting with a slash has many
+    if rest.split_whitespace().count() > 3 {
+        return None;
+    }
+
+    let confidence = if rest.contains('/') { 0.85 } else { 0.7 };
+    Some(Classification::new(ContentKind::Path, confidence))
+}
+
+fn classify_phone(text: &str) -> Option<Classification> {
+    if !text.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c)) {
+        return None;
+    }
+    let digits = text.chars().filter(char::is_ascii_digit).count();
+    if !(7..=15).contains(&digits) {
+        return None;
+    }
+    // A plus may only lead
+    if text[1..].contains('+') {
+        return None;
+    }
+
+    if text.starts_with('+') {
+        return Some(Classification::new(ContentKind::Phone, 0.9));
+    }
+    // Without separators it's just a number; with dots only it's likely a version or a date
+    let separated = text.contains([' ', '-', '(']);
+    separated.then(|| Classification::new(ContentKind::Phone, 0.7))
+}
+
+/// Seconds (10 digits) or milliseconds (13 digits) since the epoch, between
+/// 2001 and 2100
+fn classify_timestamp(text: &str) -> Option<Classification> {
+    if !text.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let seconds = match text.len() {
+        10 => text.parse::<i64>().ok()?,
+        13 => text.parse::<i64>().ok()? / 1000,
+        _ => return None,
+    };
+    (1_000_000_000..4_102_444_800)
+        .contains(&seconds)
+        .then(|| Classification::new(ContentKind::Timestamp, 0.7))
+}
+
+fn classify_json(text: &str) -> Option<Classification> {
+    if !((text.starts_with('{') && text.ends_with('}')) || (text.starts_with('[') && text.ends_with(']'))) {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_str(text).ok()?;
+    // "[1]" parses but says little; objects and nested data are clearly JSON
+    let confidence = match &value {
+        serde_json::Value::Object(map) if !map.is_empty() => 0.95,
+        serde_json::Value::Array(items) if items.iter().any(|item| item.is_object() || item.is_array()) => 0.9,
+        _ => 0.6,
+    };
+    Some(Classification::new(ContentKind::Json, confidence))
+}
+
+const CODE_KEYWORDS: [&str; 24] = [
+    "fn ", "let ", "const ", "var ", "function ", "def ", "class ", "import ", "from ", "return ", "pub ", "impl ",
+    "struct ", "#include", "public ", "private ", "package ", "use ", "if (", "for (", "while (", "} else", "SELECT ",
+    "=> ",
+];
+
+/// Looks for syntax most prose lacks: statement terminators, braces, keywords at
+/// the start of lines, operators and indentation
+fn classify_code(text: &str) -> Option<Classification> {
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut signals = 0usize;
+    for line in &lines {
+        let trimmed = line.trim();
+        if trimmed.ends_with(';') || trimmed.ends_with('{') || trimmed.ends_with('}') || trimmed.ends_with("):") {
+            signals += 1;
+        }
+        if CODE_KEYWORDS.iter().any(|keyword| trimmed.starts_with(keyword)) {
+            signals += 1;
+        }
+        if trimmed.contains("==") || trimmed.contains("->") || trimmed.contains("::") || trimmed.contains("&&") {
+            signals += 1;
+        }
+        if line.starts_with("    ") || line.starts_with('\t') {
+            signals += 1;
+        }
+    }
+
+    // One line needs two signals; longer snippets a signal on most lines
+    let ratio = signals as f64 / lines.len() as f64;
+    let is_code = if lines.len() == 1 { signals >= 2 } else { ratio >= 0.6 };
+    is_code.then(|| Classification::new(ContentKind::Code, (0.5 + ratio / 4.0).min(0.9)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(text: &str) -> ContentKind {
+        classify(text).kind
+    }
+
+    #[test]
+    fn test_urls() {
+        for url in [
+            "https://example.org",
+            "http://localhost:8080/api?x=1",
+            "HTTPS://EXAMPLE.ORG/Path#frag",
+            "https://user@git.example.org/repo.git",
+            "  https://example.org/trailing-space  ",
+            "www.rust-lang.org/learn",
+            "ftp://files.example.org/pub",
+            "file:///home/me/notes.txt",
+            "mailto:me@example.org",
+        ] {
+            assert_eq!(kind(url), ContentKind::Url, "{url}");
+        }
+        for not_url in ["https://", "https://exa mple.org", "see https://example.org", "www.", "http://-bad-.org"] {
+            assert_ne!(kind(not_url), ContentKind::Url, "{not_url}");
+        }
+    }
+
+    #[test]
+    fn test_emails() {
+        for email in ["me@example.org", "first.last+tag@mail.co.uk", "o'brien@example.ie"] {
+            assert_eq!(kind(email), ContentKind::Email, "{email}");
+        }
+        for not_email in ["@example.org", "me@", "me@localhost", "me@example.1", ".me@example.org", "a@b@c.org", "me @example.org"] {
+            assert_ne!(kind(not_email), ContentKind::Email, "{not_email}");
+        }
+    }
+
+    #[test]
+    fn test_colors() {
+        for color in ["#fff", "#FFAA00", "#ffaa0080", "#abcd", "rgb(255, 0, 0)", "rgba(0,0,0,0.5)", "hsl(120deg 50% 50%)", "RGB(1 2 3 / 50%)"] {
+            assert_eq!(kind(color), ContentKind::Color, "{color}");
+        }
+        for not_color in ["#ggg", "#12345", "rgb(1, 2)", "rgb(red, 0, 0)", "color: #fff"] {
+            assert_ne!(kind(not_color), ContentKind::Color, "{not_color}");
+        }
+        assert!(classify("#123").confidence < classify("#abc").confidence, "Could be an issue number");
+    }
+
+    #[test]
+    fn test_paths() {
+        for path in [
+            "/usr/local/bin/superkbd",
+            "~/Documents/report final.pdf",
+            "./src/main.rs",
+            "../README.md",
+            "/etc",
+            "C:\\Users\\me\\Desktop",
+            "D:/games",
+            "\\\\server\\share\\file.txt",
+        ] {
+            assert_eq!(kind(path), ContentKind::Path, "{path}");
+        }
+        for not_path in ["/", "// a comment", "/ 2", "/this is a sentence that starts with a slash", "src/main.rs"] {
+            assert_ne!(kind(not_path), ContentKind::Path, "{not_path}");
+        }
+    }
+
+    #[test]
+    fn test_phone_numbers() {
+        for phone in ["+1 555 123 4567", "+44 20 7946 0958", "(555) 123-4567", "555-123-4567", "+4915112345678"] {
+            assert_eq!(kind(phone), ContentKind::Phone, "{phone}");
+        }
+        for not_phone in ["1234567", "12345", "1.2.3", "2024.01.15", "+1 555 +123 4567", "1234567890123456789"] {
+            assert_ne!(kind(not_phone), ContentKind::Phone, "{not_phone}");
+        }
+    }
+
+    #[test]
+    fn test_timestamps() {
+        for timestamp in ["1700000000", "1700000000123", "  1000000000 "] {
+            assert_eq!(kind(timestamp), ContentKind::Timestamp, "{timestamp}");
+        }
+        for not_timestamp in ["999999999", "9999999999", "170000000012", "1700000000.5", "-1700000000"] {
+            assert_ne!(kind(not_timestamp), ContentKind::Timestamp, "{not_timestamp}");
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(kind(r#"{"name": "superkbd", "version": 1}"#), ContentKind::Json);
+        assert_eq!(kind("[\n  {\"id\": 1},\n  {\"id\": 2}\n]"), ContentKind::Json);
+        assert_eq!(kind("{\n  \"nested\": {\"a\": [1, 2, 3]}\n}"), ContentKind::Json);
+        assert!(classify("[1, 2]").confidence < classify(r#"{"a": 1}"#).confidence);
+
+        assert_ne!(kind("{not json}"), ContentKind::Json);
+        assert_ne!(kind("[link](https://example.org)"), ContentKind::Json);
+    }
+
+    #[test]
+    fn test_code() {
+        let rust = "fn main() {\n    let x = 5;\n    println!(\"{}\", x);\n}";
+        let python = "def greet(name):\n    if name == \"\":\n        return None\n    return f\"hi {name}\"";
+        let javascript = "const add = (a, b) => {\n  return a + b;\n};";
+        let c = "#include <stdio.h>\nint main(void) {\n    printf(\"hi\");\n    return 0;\n}";
+        for code in [rust, python, javascript, c, "let total = items.len();", "if (a == b && c) {"] {
+            assert_eq!(kind(code), ContentKind::Code, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_prose_is_text() {
+        for text in [
+            "Hello world",
+            "Meeting moved to 3pm; bring the slides.",
+            "Dear team,\nthe release is on Friday.\nThanks!",
+            "1234567",
+            "src/main.rs",
+            "   ",
+        ] {
+            assert_eq!(kind(text), ContentKind::Text, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_classification_is_deterministic() {
+        let samples = ["https://example.org", "fn main() {}", "plain words", "#fff"];
+        for sample in samples {
+            assert_eq!(classify(sample), classify(sample));
+        }
+    }
+
+    #[test]
+    fn test_confidence_is_in_range() {
+        for sample in ["https://example.org", "www.example.org", "{\"a\":1}", "[1]", "x == y && z;", "hello", "/etc"] {
+            let confidence = classify(sample).confidence;
+            assert!((0.0..=1.0).contains(&confidence), "{sample}: {confidence}");
+        }
+    }
+
+    #[test]
+    fn test_language_is_detected_for_code_and_commands() {
+        assert_eq!(classify("fn main() {\n    println!(\"hi\");\n}").language, Some(Language::Rust));
+        assert_eq!(classify("{\"a\": 1}").language, Some(Language::Json));
+        assert_eq!(classify("git commit --amend").language, Some(Language::Shell));
+        assert_eq!(classify("https://example.org").language, None);
+        assert_eq!(classify("Hello world").language, None);
+    }
+
+    #[test]
+    fn test_kind_names_round_trip() {
+        for kind in ContentKind::ALL {
+            assert_eq!(ContentKind::parse(kind.as_str()), Some(kind));
+            assert_eq!(serde_json::to_string(&kind).unwrap(), format!("\"{}\"", kind.as_str()));
+        }
+        assert_eq!(ContentKind::parse("URL"), Some(ContentKind::Url));
+        assert_eq!(ContentKind::parse("image"), None);
+    }
+}
@@ -0,0 +1,361 @@
+use serde::Serialize;
+
+use super::language::Language;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Function,
+    /// Object keys, YAML/TOML keys, shell variables, HTML attributes
+    Property,
+    Plain,
+}
+
+/// A run of text with one highlight style. Concatenating the spans gives back
+/// the original text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// What `get_highlighted` returns for the picker preview
+#[derive(Debug, Clone, Serialize)]
+pub struct Highlighted {
+    pub language: Option<Language>,
+    pub spans: Vec<Span>,
+}
+
+/// Beyond this the preview isn't highlighted
+const MAX_HIGHLIGHT_LEN: usize = 100 * 1024;
+
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+    /// SQL keywords match in any case
+    case_insensitive: bool,
+}
+
+const C_FAMILY_COMMENTS: &[&str] = &["//"];
+const HASH_COMMENTS: &[&str] = &["#"];
+
+fn syntax(language: Language) -> Syntax {
+    type Parts = (&'static [&'static str], Option<(&'static str, &'static str)>, &'static [char], &'static [&'static str], bool);
+    let (line_comments, block_comment, quotes, keywords, case_insensitive): Parts = match language {
+        Language::Rust => (C_FAMILY_COMMENTS, Some(("/*", "*/")), &['"'], &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "false", "fn", "for",
+            "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+            "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+        ], false),
+        Language::Python => (HASH_COMMENTS, None, &['"', '\''], &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else",
+            "except", "False", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "None",
+            "nonlocal", "not", "or", "pass", "raise", "return", "True", "try", "while", "with", "yield", "self",
+        ], false),
+        Language::JavaScript | Language::TypeScript => (C_FAMILY_COMMENTS, Some(("/*", "*/")), &['"', '\'', '`'], &[
+            "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "delete", "do",
+            "else", "export", "extends", "false", "finally", "for", "from", "function", "if", "import", "in",
+            "instanceof", "interface", "let", "new", "null", "of", "return", "static", "switch", "this", "throw",
+            "true", "try", "type", "typeof", "undefined", "var", "void", "while", "yield", "string", "number", "boolean",
+        ], false),
+        Language::Go => (C_FAMILY_COMMENTS, Some(("/*", "*/")), &['"', '`', '\''], &[
+            "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "false", "for",
+            "func", "go", "goto", "if", "import", "interface", "map", "nil", "package", "range", "return", "select",
+            "struct", "switch", "true", "type", "var",
+        ], false),
+        Language::Java => (C_FAMILY_COMMENTS, Some(("/*", "*/")), &['"', '\''], &[
+            "abstract", "boolean", "break", "case", "catch", "class", "else", "extends", "false", "final", "finally",
+            "for", "if", "implements", "import", "int", "interface", "long", "new", "null", "package", "private",
+            "protected", "public", "return", "static", "super", "switch", "this", "throw", "throws", "true", "try",
+            "void", "while",
+        ], false),
+        Language::C | Language::Cpp => (C_FAMILY_COMMENTS, Some(("/*", "*/")), &['"', '\''], &[
+            "auto", "bool", "break", "case", "char", "class", "const", "continue", "default", "delete", "do",
+            "double", "else", "enum", "extern", "false", "float", "for", "if", "include", "int", "long", "namespace",
+            "new", "nullptr", "public", "private", "return", "short", "signed", "sizeof", "static", "struct",
+            "switch", "template", "true", "typedef", "unsigned", "using", "void", "while", "define", "NULL",
+        ], false),
+        Language::Shell => (HASH_COMMENTS, None, &['"', '\''], &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in", "local",
+            "return", "then", "while", "sudo",
+        ], false),
+        Language::Sql => (&["--"], Some(("/*", "*/")), &['\'', '"'], &[
+            "add", "alter", "and", "as", "asc", "by", "create", "delete", "desc", "distinct", "drop", "exists",
+            "from", "group", "having", "index", "inner", "insert", "into", "is", "join", "key", "left", "like",
+            "limit", "not", "null", "offset", "on", "or", "order", "primary", "right", "select", "set", "table",
+            "union", "update", "values", "where",
+        ], true),
+        Language::Json => (&[], None, &['"'], &["true", "false", "null"], false),
+        Language::Yaml => (HASH_COMMENTS, None, &['"', '\''], &["true", "false", "null", "yes", "no"], false),
+        Language::Toml => (HASH_COMMENTS, None, &['"', '\''], &["true", "false"], false),
+        Language::Html => (&[], Some(("<!--", "-->")), &['"', '\''], &[], false),
+        Language::Css => (&[], Some(("/*", "*/")), &['"', '\''], &["important"], false),
+    };
+    Syntax { line_comments, block_comment, quotes, keywords, case_insensitive }
+}
+
+/// Split `text` into highlighted spans for `language`. Plain text comes back
+/// as one plain span.
+pub fn highlight(text: &str, language: Option<Language>) -> Vec<Span> {
+    let Some(language) = language.filter(|_| text.len() <= MAX_HIGHLIGHT_LEN) else {
+        return vec![Span { kind: TokenKind::Plain, text: text.to_string() }];
+    };
+
+    let syntax = syntax(language);
+    let mut spans = Spans::default();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let (kind, len) = next_token(rest, c, language, &syntax, spans.previous_char());
+        spans.push(kind, &rest[..len]);
+        rest = &rest[len..];
+    }
+    spans.0
+}
+
+/// Kind and byte length of the token at the start of `rest`
+fn next_token(rest: &str, c: char, language: Language, syntax: &Syntax, previous: Option<char>) -> (TokenKind, usize) {
+    if let Some(prefix) = syntax.line_comments.iter().find(|prefix| rest.starts_with(**prefix)) {
+        // "#" only starts a comment at a word boundary (not in `$#` or `a#b`)
+        if *prefix != "#" || previous.is_none_or(|p| p.is_whitespace() || p == ';') {
+            return (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()));
+        }
+    }
+    if let Some((open, close)) = syntax.block_comment {
+        if let Some(body) = rest.strip_prefix(open) {
+            let end = body.find(close).map_or(rest.len(), |i| open.len() + i + close.len());
+            return (TokenKind::Comment, end);
+        }
+    }
+
+    if syntax.quotes.contains(&c) {
+        let len = string_len(rest, c, language);
+        // A quoted key, as in JSON objects
+        let kind = if rest[len..].trim_start_matches([' ', '\t']).starts_with(':')
+            && matches!(language, Language::Json | Language::Yaml | Language::JavaScript | Language::TypeScript)
+        {
+            TokenKind::Property
+        } else {
+            TokenKind::String
+        };
+        return (kind, len);
+    }
+
+    if c.is_ascii_digit() && !previous.is_some_and(is_word_char) {
+        let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_')).unwrap_or(rest.len());
+        return (TokenKind::Number, len);
+    }
+
+    if language == Language::Shell && c == '$' {
+        let name_len = rest[1..].find(|c: char| !is_word_char(c) && c != '{' && c != '}').unwrap_or(rest.len() - 1);
+        if name_len > 0 {
+            return (TokenKind::Property, 1 + name_len);
+        }
+    }
+
+    if is_word_char(c) || (language == Language::Css && c == '-') {
+        let len = rest
+            .find(|c: char| !(is_word_char(c) || matches!(language, Language::Css | Language::Html | Language::Yaml | Language::Toml) && c == '-'))
+            .unwrap_or(rest.len());
+        return (word_kind(&rest[..len], &rest[len..], language, syntax, previous), len);
+    }
+
+    let len = c.len_utf8();
+    (TokenKind::Plain, len)
+}
+
+fn word_kind(word: &str, after: &str, language: Language, syntax: &Syntax, previous: Option<char>) -> TokenKind {
+    let is_keyword = if syntax.case_insensitive {
+        syntax.keywords.iter().any(|keyword| keyword.eq_ignore_ascii_case(word))
+    } else {
+        syntax.keywords.contains(&word)
+    };
+    let next = after.trim_start_matches([' ', '\t']);
+
+    match language {
+        Language::Html if previous == Some('<') || previous == Some('/') => TokenKind::Keyword,
+        Language::Html if next.starts_with('=') => TokenKind::Property,
+        Language::Yaml | Language::Css if next.starts_with(':') && !next.starts_with("::") => TokenKind::Property,
+        Language::Toml if next.starts_with('=') => TokenKind::Property,
+        _ if is_keyword => TokenKind::Keyword,
+        // Rust macros look like calls with a "!"
+        _ if next.starts_with('(') || (language == Language::Rust && next.starts_with("!(")) => TokenKind::Function,
+        _ => TokenKind::Plain,
+    }
+}
+
+/// Byte length of the string literal at the start of `rest`, quotes included.
+/// Strings end at the line unless the language lets them span lines.
+fn string_len(rest: &str, quote: char, language: Language) -> usize {
+    let triple: String = std::iter::repeat_n(quote, 3).collect();
+    if language == Language::Python && rest.starts_with(&triple) {
+        return rest[3..].find(&triple).map_or(rest.len(), |i| 3 + i + 3);
+    }
+
+    let multiline = quote == '`';
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        } else if c == '\n' && !multiline {
+            return i;
+        }
+    }
+    rest.len()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Spans with neighbouring runs of the same kind merged
+#[derive(Default)]
+struct Spans(Vec<Span>);
+
+impl Spans {
+    fn push(&mut self, kind: TokenKind, text: &str) {
+        match self.0.last_mut() {
+            Some(last) if last.kind == kind && kind == TokenKind::Plain => last.text.push_str(text),
+            _ => self.0.push(Span { kind, text: text.to_string() }),
+        }
+    }
+
+    /// The character just before the current position
+    fn previous_char(&self) -> Option<char> {
+        self.0.last().and_then(|span| span.text.chars().last())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str, language: Language) -> Vec<(TokenKind, String)> {
+        highlight(text, Some(language))
+            .into_iter()
+            .filter(|span| span.kind != TokenKind::Plain)
+            .map(|span| (span.kind, span.text))
+            .collect()
+    }
+
+    fn joined(spans: &[Span]) -> String {
+        spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_spans_reassemble_the_text() {
+        let samples = [
+            ("fn main() {\n    let s = \"hi \\\" there\"; // done\n}", Language::Rust),
+            ("def f():\n    '''doc\n    string'''\n    return 1  # one", Language::Python),
+            ("SELECT * FROM t -- all\nWHERE x = 'ü'", Language::Sql),
+            ("<p class=\"x\">héllo</p><!-- c -->", Language::Html),
+            ("echo \"$HOME\" ${PATH} # unterminated \"", Language::Shell),
+            ("const s = `multi\nline`;", Language::JavaScript),
+            ("unterminated \"string", Language::Json),
+        ];
+        for (text, language) in samples {
+            assert_eq!(joined(&highlight(text, Some(language))), text, "{language:?}");
+        }
+    }
+
+    #[test]
+    fn test_rust_tokens() {
+        let tokens = kinds("pub fn add(a: i32) -> i32 { println!(\"{}\", a + 1); a } // sum", Language::Rust);
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenKind::Keyword, "pub".to_string()),
+                (TokenKind::Keyword, "fn".to_string()),
+                (TokenKind::Function, "add".to_string()),
+                (TokenKind::Function, "println".to_string()),
+                (TokenKind::String, "\"{}\"".to_string()),
+                (TokenKind::Number, "1".to_string()),
+                (TokenKind::Comment, "// sum".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_comments_and_docstrings() {
+        let tokens = kinds("x = 1  # note\n\"\"\"a\nb\"\"\"", Language::Python);
+        assert_eq!(tokens, vec![
+            (TokenKind::Number, "1".to_string()),
+            (TokenKind::Comment, "# note".to_string()),
+            (TokenKind::String, "\"\"\"a\nb\"\"\"".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_sql_keywords_in_any_case() {
+        let tokens = kinds("select name FROM users where id = 7", Language::Sql);
+        assert_eq!(tokens, vec![
+            (TokenKind::Keyword, "select".to_string()),
+            (TokenKind::Keyword, "FROM".to_string()),
+            (TokenKind::Keyword, "where".to_string()),
+            (TokenKind::Number, "7".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_json_keys_are_properties() {
+        let tokens = kinds("{\"name\": \"superkbd\", \"stars\": 42, \"ok\": true}", Language::Json);
+        assert_eq!(tokens, vec![
+            (TokenKind::Property, "\"name\"".to_string()),
+            (TokenKind::String, "\"superkbd\"".to_string()),
+            (TokenKind::Property, "\"stars\"".to_string()),
+            (TokenKind::Number, "42".to_string()),
+            (TokenKind::Property, "\"ok\"".to_string()),
+            (TokenKind::Keyword, "true".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_yaml_and_shell() {
+        let yaml = kinds("build-dir: out # where\nretries: 3", Language::Yaml);
+        assert_eq!(yaml, vec![
+            (TokenKind::Property, "build-dir".to_string()),
+            (TokenKind::Comment, "# where".to_string()),
+            (TokenKind::Property, "retries".to_string()),
+            (TokenKind::Number, "3".to_string()),
+        ]);
+
+        let shell = kinds("echo $HOME/bin#x", Language::Shell);
+        assert_eq!(shell, vec![(TokenKind::Property, "$HOME".to_string())], "# inside a word isn't a comment");
+    }
+
+    #[test]
+    fn test_html_tags_and_attributes() {
+        let tokens = kinds("<a href=\"/\">home</a>", Language::Html);
+        assert_eq!(tokens, vec![
+            (TokenKind::Keyword, "a".to_string()),
+            (TokenKind::Property, "href".to_string()),
+            (TokenKind::String, "\"/\"".to_string()),
+            (TokenKind::Keyword, "a".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_plain_text_is_one_span() {
+        let spans = highlight("just words", None);
+        assert_eq!(spans, vec![Span { kind: TokenKind::Plain, text: "just words".to_string() }]);
+
+        let huge = "x".repeat(MAX_HIGHLIGHT_LEN + 1);
+        assert_eq!(highlight(&huge, Some(Language::Rust)).len(), 1);
+    }
+
+    #[test]
+    fn test_identifiers_with_digits_are_not_numbers() {
+        let tokens = kinds("let v2 = x1 + 3;", Language::Rust);
+        assert_eq!(tokens, vec![(TokenKind::Keyword, "let".to_string()), (TokenKind::Number, "3".to_string())]);
+    }
+}
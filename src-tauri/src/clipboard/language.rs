@@ -0,0 +1,527 @@
+use serde::{Deserialize, Serialize};
+
+/// Programming or data language of a code snippet. Stored in the `language` column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    Java,
+    C,
+    Cpp,
+    Shell,
+    Sql,
+    Json,
+    Yaml,
+    Toml,
+    Html,
+    Css,
+}
+
+impl Language {
+    pub const ALL: [Language; 15] = [
+        Language::Rust,
+        Language::Python,
+        Language::JavaScript,
+        Language::TypeScript,
+        Language::Go,
+        Language::Java,
+        Language::C,
+        Language::Cpp,
+        Language::Shell,
+        Language::Sql,
+        Language::Json,
+        Language::Yaml,
+        Language::Toml,
+        Language::Html,
+        Language::Css,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Go => "go",
+            Language::Java => "java",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Shell => "shell",
+            Language::Sql => "sql",
+            Language::Json => "json",
+            Language::Yaml => "yaml",
+            Language::Toml => "toml",
+            Language::Html => "html",
+            Language::Css => "css",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|language| language.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Something that hints at a language, checked against each line
+enum Marker {
+    /// The trimmed line starts with this
+    Prefix(&'static str),
+    /// The line contains this anywhere
+    Contains(&'static str),
+    /// The trimmed line is exactly this
+    Line(&'static str),
+}
+
+use Marker::{Contains, Line, Prefix};
+
+const RUST: &[(Marker, u32)] = &[
+    (Prefix("fn "), 3),
+    (Prefix("pub fn "), 5),
+    (Prefix("pub struct "), 5),
+    (Prefix("impl "), 4),
+    (Prefix("impl<"), 5),
+    (Prefix("use std::"), 5),
+    (Prefix("#[derive("), 6),
+    (Prefix("mod "), 2),
+    (Contains("let mut "), 4),
+    (Contains("println!("), 5),
+    (Contains("&str"), 3),
+    (Contains("&mut "), 3),
+    (Contains(".unwrap()"), 3),
+    (Contains("Vec<"), 2),
+    (Contains("Option<"), 2),
+    (Contains("Result<"), 2),
+    (Contains(") -> "), 1),
+    (Contains("::"), 1),
+    (Prefix("match "), 1),
+];
+
+const PYTHON: &[(Marker, u32)] = &[
+    (Prefix("def "), 4),
+    (Prefix("elif "), 5),
+    (Prefix("from "), 1),
+    (Prefix("import "), 1),
+    (Contains(" import "), 2),
+    (Contains("self."), 2),
+    (Contains("__init__"), 5),
+    (Contains("__name__"), 5),
+    (Prefix("print("), 2),
+    (Prefix("class "), 1),
+    (Contains("lambda "), 2),
+    (Contains(" None"), 1),
+    (Contains("True"), 1),
+    (Contains("False"), 1),
+    (Prefix("except"), 4),
+    (Prefix("with open("), 5),
+    (Contains("):"), 1),
+];
+
+const JAVASCRIPT: &[(Marker, u32)] = &[
+    (Prefix("function "), 3),
+    (Prefix("const "), 2),
+    (Prefix("let "), 1),
+    (Prefix("var "), 2),
+    (Contains("=> "), 2),
+    (Contains("console.log("), 5),
+    (Contains("==="), 3),
+    (Contains("!=="), 3),
+    (Contains("document."), 3),
+    (Contains("require("), 3),
+    (Prefix("export default "), 4),
+    (Prefix("export const "), 3),
+    (Contains("async "), 1),
+    (Contains("await "), 1),
+    (Prefix("import {"), 2),
+];
+
+/// Counted on top of the JavaScript markers
+const TYPESCRIPT: &[(Marker, u32)] = &[
+    (Contains(": string"), 3),
+    (Contains(": number"), 3),
+    (Contains(": boolean"), 3),
+    (Prefix("interface "), 4),
+    (Prefix("export interface "), 5),
+    (Prefix("type "), 2),
+    (Contains(" as const"), 3),
+    (Contains("): Promise<"), 4),
+    (Prefix("private readonly "), 4),
+];
+
+const GO: &[(Marker, u32)] = &[
+    (Prefix("package "), 4),
+    (Prefix("func "), 5),
+    (Contains(":= "), 2),
+    (Contains("fmt."), 4),
+    (Prefix("import ("), 5),
+    (Contains("err != nil"), 6),
+    (Prefix("defer "), 4),
+    (Contains("chan "), 2),
+];
+
+const JAVA: &[(Marker, u32)] = &[
+    (Prefix("public class "), 6),
+    (Contains("System.out."), 6),
+    (Prefix("private "), 2),
+    (Prefix("public "), 2),
+    (Contains("public static void main"), 6),
+    (Prefix("import java."), 6),
+    (Prefix("@Override"), 5),
+    (Contains("String[]"), 3),
+    (Contains("new "), 1),
+];
+
+const C: &[(Marker, u32)] = &[
+    (Prefix("#include <"), 4),
+    (Prefix("#include \""), 4),
+    (Prefix("#define "), 4),
+    (Contains("printf("), 3),
+    (Contains("int main("), 4),
+    (Contains("malloc("), 4),
+    (Contains("free("), 2),
+    (Contains("NULL"), 2),
+    (Contains("->"), 1),
+    (Prefix("struct "), 1),
+    (Prefix("void "), 2),
+];
+
+/// Counted on top of the C markers
+const CPP: &[(Marker, u32)] = &[
+    (Contains("std::"), 4),
+    (Prefix("#include <iostream>"), 6),
+    (Contains("cout <<"), 5),
+    (Prefix("template <"), 5),
+    (Prefix("template<"), 5),
+    (Contains("nullptr"), 4),
+    (Prefix("namespace "), 3),
+    (Prefix("using namespace "), 5),
+    (Prefix("class "), 1),
+];
+
+const SHELL: &[(Marker, u32)] = &[
+    (Prefix("#!/bin/bash"), 10),
+    (Prefix("#!/bin/sh"), 10),
+    (Prefix("#!/usr/bin/env bash"), 10),
+    (Prefix("$ "), 3),
+    (Prefix("sudo "), 5),
+    (Prefix("echo "), 3),
+    (Prefix("export "), 2),
+    (Prefix("cd "), 3),
+    (Prefix("ls "), 3),
+    (Prefix("mkdir "), 4),
+    (Prefix("rm "), 3),
+    (Prefix("chmod "), 4),
+    (Prefix("curl "), 4),
+    (Prefix("wget "), 4),
+    (Prefix("git "), 4),
+    (Prefix("cargo "), 4),
+    (Prefix("npm "), 4),
+    (Prefix("bun "), 4),
+    (Prefix("docker "), 4),
+    (Prefix("kubectl "), 4),
+    (Prefix("brew "), 4),
+    (Prefix("apt "), 4),
+    (Prefix("apt-get "), 4),
+    (Prefix("pip install "), 4),
+    (Prefix("ssh "), 4),
+    (Contains(" | grep "), 3),
+    (Contains(" | xargs "), 3),
+    (Contains("${"), 2),
+    (Contains(" && "), 1),
+    (Contains(" 2>&1"), 3),
+    (Line("fi"), 4),
+    (Line("done"), 3),
+    (Line("then"), 2),
+    (Line("esac"), 5),
+];
+
+/// Matched against the lowercased line
+const SQL: &[(Marker, u32)] = &[
+    (Prefix("select "), 3),
+    (Contains(" from "), 2),
+    (Prefix("from "), 2),
+    (Contains("where "), 2),
+    (Prefix("insert into "), 6),
+    (Prefix("create table "), 6),
+    (Prefix("create index "), 6),
+    (Prefix("alter table "), 6),
+    (Prefix("delete from "), 6),
+    (Prefix("drop table "), 6),
+    (Prefix("update "), 2),
+    (Contains(" set "), 1),
+    (Contains(" join "), 2),
+    (Contains("group by "), 4),
+    (Contains("order by "), 3),
+    (Contains(" values ("), 3),
+];
+
+const CSS: &[(Marker, u32)] = &[
+    (Prefix("@media "), 5),
+    (Prefix("@import "), 3),
+    (Contains("px;"), 2),
+    (Contains("em;"), 2),
+    (Prefix("color:"), 2),
+    (Prefix("display:"), 3),
+    (Prefix("margin"), 2),
+    (Prefix("padding"), 2),
+    (Prefix("font-"), 2),
+    (Prefix("background"), 2),
+    (Prefix("border"), 2),
+];
+
+/// Below this no language is assigned: one strong hint or a few weak ones
+const MIN_SCORE: u32 = 4;
+
+/// Texts longer than this are judged by their start
+const SAMPLE_LEN: usize = 16 * 1024;
+
+/// Guess the language of a snippet. None for prose or when the hints are too
+/// weak. Deterministic, like the kind classifier.
+pub fn detect_language(text: &str) -> Option<Language> {
+    let trimmed = text.trim();
+    let mut end = trimmed.len().min(SAMPLE_LEN);
+    while !trimmed.is_char_boundary(end) {
+        end -= 1;
+    }
+    let sample = &trimmed[..end];
+
+    let lines: Vec<&str> = sample.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.is_empty() {
+        return None;
+    }
+
+    // Formats that can be recognised outright
+    if (sample.starts_with('{') || sample.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok_and(|value| value.is_object() || value.is_array())
+    {
+        return Some(Language::Json);
+    }
+    if is_html(sample) {
+        return Some(Language::Html);
+    }
+    if let Some(language) = data_format(&lines) {
+        return Some(language);
+    }
+
+    let lowered: Vec<String> = lines.iter().map(|line| line.to_lowercase()).collect();
+    let lowered: Vec<&str> = lowered.iter().map(String::as_str).collect();
+
+    let javascript = score(&lines, JAVASCRIPT);
+    let typescript = score(&lines, TYPESCRIPT);
+    let c = score(&lines, C);
+    let cpp = score(&lines, CPP);
+
+    let scores = [
+        (Language::Rust, score(&lines, RUST)),
+        (Language::Python, score(&lines, PYTHON)),
+        (Language::JavaScript, javascript),
+        // TypeScript is JavaScript with types; it needs a type hint of its own
+        (Language::TypeScript, if typescript > 0 { javascript + typescript } else { 0 }),
+        (Language::Go, score(&lines, GO)),
+        (Language::Java, score(&lines, JAVA)),
+        (Language::C, c),
+        (Language::Cpp, if cpp > 0 { c + cpp } else { 0 }),
+        (Language::Shell, score(&lines, SHELL)),
+        (Language::Sql, score(&lowered, SQL)),
+        (Language::Css, score(&lines, CSS) + css_rules(&lines)),
+    ];
+
+    // The first of equal scores wins, so the result doesn't depend on anything but the text
+    let (language, best) = scores
+        .into_iter()
+        .fold((None, 0), |(best_language, best), (language, score)| {
+            if score > best { (Some(language), score) } else { (best_language, best) }
+        });
+    language.filter(|_| best >= MIN_SCORE)
+}
+
+fn score(lines: &[&str], markers: &[(Marker, u32)]) -> u32 {
+    lines
+        .iter()
+        .map(|line| {
+            let trimmed = line.trim();
+            markers
+                .iter()
+                .filter(|(marker, _)| match marker {
+                    Prefix(prefix) => trimmed.starts_with(prefix),
+                    Contains(needle) => line.contains(needle),
+                    Line(exact) => trimmed == *exact,
+                })
+                .map(|(_, weight)| weight)
+                .sum::<u32>()
+        })
+        .sum()
+}
+
+fn is_html(sample: &str) -> bool {
+    let lower = sample.get(..sample.len().min(256)).unwrap_or(sample).to_lowercase();
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        return true;
+    }
+    // A tag at the start and a closing tag somewhere
+    let opens_with_tag = sample.starts_with('<') && sample[1..].starts_with(|c: char| c.is_ascii_alphabetic());
+    opens_with_tag && sample.contains("</") && sample.trim_end().ends_with('>')
+}
+
+/// YAML and TOML are mostly `key: value` or `key = value` lines
+fn data_format(lines: &[&str]) -> Option<Language> {
+    if lines.len() < 2 {
+        return None;
+    }
+    let content: Vec<&str> = lines.iter().map(|line| line.trim()).filter(|line| !line.starts_with('#')).collect();
+    if content.is_empty() {
+        return None;
+    }
+
+    let is_key = |key: &str| {
+        let key = key.trim();
+        !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || "_-.\"'".contains(c))
+    };
+
+    let yaml = content
+        .iter()
+        .filter(|line| {
+            **line == "---"
+                || line.starts_with("- ")
+                || line
+                    .split_once(':')
+                    .is_some_and(|(key, rest)| is_key(key) && (rest.is_empty() || rest.starts_with(' ')))
+        })
+        .count();
+    let toml_sections = content
+        .iter()
+        .filter(|line| line.starts_with('[') && line.ends_with(']') && is_key(line.trim_matches(['[', ']'])))
+        .count();
+    let toml = toml_sections
+        + content
+            .iter()
+            .filter(|line| line.split_once(" = ").is_some_and(|(key, _)| is_key(key)))
+            .count();
+
+    // Nearly every line has to fit, otherwise it's code with a few assignments
+    let fits = |count: usize| count * 10 >= content.len() * 9;
+    if fits(toml) && (toml_sections > 0 || content.len() >= 3) {
+        Some(Language::Toml)
+    } else if fits(yaml) {
+        Some(Language::Yaml)
+    } else {
+        None
+    }
+}
+
+/// `selector {` followed by `property: value;` lines
+fn css_rules(lines: &[&str]) -> u32 {
+    let declarations = lines
+        .iter()
+        .filter(|line| {
+            let line = line.trim();
+            line.ends_with(';')
+                && line
+                    .split_once(':')
+                    .is_some_and(|(property, _)| !property.is_empty() && property.chars().all(|c| c.is_ascii_lowercase() || c == '-'))
+        })
+        .count() as u32;
+    let selectors = lines
+        .iter()
+        .filter(|line| {
+            let line = line.trim();
+            line.ends_with('{') && (line.starts_with('.') || line.starts_with('#') || line.starts_with(|c: char| c.is_ascii_lowercase()))
+                && !line.contains('(')
+        })
+        .count() as u32;
+    if selectors > 0 { declarations * 2 + selectors } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(text: &str) -> Option<Language> {
+        detect_language(text)
+    }
+
+    #[test]
+    fn test_rust() {
+        assert_eq!(detect("fn main() {\n    let mut v = Vec::new();\n    println!(\"{:?}\", v);\n}"), Some(Language::Rust));
+        assert_eq!(detect("#[derive(Debug, Clone)]\npub struct Point { x: f64, y: f64 }"), Some(Language::Rust));
+        assert_eq!(detect("use std::collections::HashMap;"), Some(Language::Rust));
+    }
+
+    #[test]
+    fn test_python() {
+        let code = "def greet(name):\n    if not name:\n        return None\n    print(f\"hello {name}\")";
+        assert_eq!(detect(code), Some(Language::Python));
+        let class = "class Cache:\n    def __init__(self):\n        self.items = {}";
+        assert_eq!(detect(class), Some(Language::Python));
+        assert_eq!(detect("from pathlib import Path"), None, "Too little to go on");
+    }
+
+    #[test]
+    fn test_javascript_and_typescript() {
+        let js = "const add = (a, b) => a + b;\nconsole.log(add(1, 2));";
+        assert_eq!(detect(js), Some(Language::JavaScript));
+        let ts = "interface User {\n  name: string;\n  age: number;\n}\nconst greet = (u: User) => console.log(u.name);";
+        assert_eq!(detect(ts), Some(Language::TypeScript));
+    }
+
+    #[test]
+    fn test_go_java_c_cpp() {
+        let go = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"hi\")\n}";
+        assert_eq!(detect(go), Some(Language::Go));
+        let java = "public class Main {\n    public static void main(String[] args) {\n        System.out.println(\"hi\");\n    }\n}";
+        assert_eq!(detect(java), Some(Language::Java));
+        let c = "#include <stdio.h>\n\nint main(void) {\n    printf(\"hi\\n\");\n    return 0;\n}";
+        assert_eq!(detect(c), Some(Language::C));
+        let cpp = "#include <iostream>\n\nint main() {\n    std::cout << \"hi\" << std::endl;\n}";
+        assert_eq!(detect(cpp), Some(Language::Cpp));
+    }
+
+    #[test]
+    fn test_shell() {
+        assert_eq!(detect("git push --force-with-lease origin main"), Some(Language::Shell));
+        assert_eq!(detect("sudo apt-get install -y ripgrep"), Some(Language::Shell));
+        assert_eq!(detect("#!/bin/bash\nset -e\nfor f in *.log; do\n  gzip \"$f\"\ndone"), Some(Language::Shell));
+        assert_eq!(detect("ps aux | grep superkbd 2>&1"), Some(Language::Shell));
+    }
+
+    #[test]
+    fn test_sql() {
+        assert_eq!(detect("SELECT id, name FROM users WHERE active = 1 ORDER BY name"), Some(Language::Sql));
+        assert_eq!(detect("insert into logs (level, message) values ('info', 'hi')"), Some(Language::Sql));
+        assert_eq!(detect("CREATE TABLE t (id INTEGER PRIMARY KEY)"), Some(Language::Sql));
+        assert_eq!(detect("Update the docs and set a reminder"), None);
+    }
+
+    #[test]
+    fn test_data_formats() {
+        assert_eq!(detect("{\"name\": \"superkbd\", \"tags\": [1, 2]}"), Some(Language::Json));
+        assert_eq!(detect("name: superkbd\nversion: 1.0\ndependencies:\n  - serde\n  - tokio"), Some(Language::Yaml));
+        assert_eq!(detect("[package]\nname = \"superkbd\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\""), Some(Language::Toml));
+        assert_eq!(detect("<div class=\"card\">\n  <p>Hello</p>\n</div>"), Some(Language::Html));
+        assert_eq!(detect("<!DOCTYPE html>\n<html></html>"), Some(Language::Html));
+        assert_eq!(detect(".card {\n  margin: 0 auto;\n  color: #333;\n}"), Some(Language::Css));
+    }
+
+    #[test]
+    fn test_prose_has_no_language() {
+        for text in [
+            "Hello world",
+            "Meeting notes: discuss the roadmap. Bring coffee.",
+            "Dear team,\nthe release is on Friday.\nThanks!",
+            "https://example.org",
+            "",
+        ] {
+            assert_eq!(detect(text), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_language_names_round_trip() {
+        for language in Language::ALL {
+            assert_eq!(Language::parse(language.as_str()), Some(language));
+            assert_eq!(serde_json::to_string(&language).unwrap(), format!("\"{}\"", language.as_str()));
+        }
+    }
+}
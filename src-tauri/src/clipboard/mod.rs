@@ -1,5 +1,13 @@
+pub mod classify;
+pub mod highlight;
+pub mod language;
 pub mod monitor;
+pub mod ocr;
+pub mod persist;
+pub mod selection;
+pub mod thumbnail;
 pub mod types;
 
-pub use monitor::ClipboardMonitor;
+pub use classify::{classify, Classification, ContentKind};
+pub use monitor::{ignore_self_write, is_recording_paused, set_recording_paused, ClipboardMonitor};
 pub use types::{ClipboardContent, ContentType};
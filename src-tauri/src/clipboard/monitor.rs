@@ -1,39 +1,137 @@
 use anyhow::Result;
 use arboard::Clipboard;
 use sqlx::SqlitePool;
-use std::sync::Arc;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::RwLock;
 use tokio::time::interval;
 
+#[cfg(target_os = "linux")]
+use super::{persist, selection};
 use super::types::ClipboardContent;
-use crate::database::{self, NewClipboardEntry};
+use crate::database::offload;
+use crate::database::NewClipboardEntry;
+use crate::settings;
+
+// How long a self-originated clipboard write stays suppressed. The monitor polls
+// every 500ms, so this leaves room for a couple of ticks.
+const SELF_WRITE_TTL: Duration = Duration::from_secs(2);
+
+// Clipboard contents written by SuperKBD itself (pastes and restores) that the
+// monitor should not record as new copies
+static SELF_WRITES: Mutex<Vec<(String, Instant)>> = Mutex::new(Vec::new());
+
+// While set, copies are still tracked but not saved to history
+static RECORDING_PAUSED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_recording_paused(paused: bool) {
+    RECORDING_PAUSED.store(paused, Ordering::SeqCst);
+}
+
+pub fn is_recording_paused() -> bool {
+    RECORDING_PAUSED.load(Ordering::SeqCst)
+}
+
+/// Tell the monitor that we are about to put `text` on the clipboard ourselves,
+/// so it is not recorded again and does not bump the entry's `created_at`
+pub fn ignore_self_write(text: &str) {
+    if let Ok(mut writes) = SELF_WRITES.lock() {
+        let now = Instant::now();
+        writes.retain(|(_, at)| now.duration_since(*at) < SELF_WRITE_TTL);
+        writes.push((text.to_string(), now));
+    }
+}
+
+/// Returns true (and consumes the marker) if `text` was written by us recently
+fn take_self_write(text: &str) -> bool {
+    match SELF_WRITES.lock() {
+        Ok(mut writes) => take_matching_write(&mut writes, text, Instant::now()),
+        Err(_) => false,
+    }
+}
+
+fn take_matching_write(writes: &mut Vec<(String, Instant)>, text: &str, now: Instant) -> bool {
+    writes.retain(|(_, at)| now.duration_since(*at) < SELF_WRITE_TTL);
+
+    if let Some(pos) = writes.iter().position(|(written, _)| written == text) {
+        writes.remove(pos);
+        true
+    } else {
+        false
+    }
+}
 
 pub struct ClipboardMonitor {
     last_content: Arc<RwLock<Option<String>>>,
     pool: Arc<SqlitePool>,
+    /// Where texts too large for the database are stored
+    blob_dir: PathBuf,
 }
 
 impl ClipboardMonitor {
-    pub fn new(pool: Arc<SqlitePool>) -> Self {
+    pub fn new(pool: Arc<SqlitePool>, blob_dir: PathBuf) -> Self {
         Self {
             last_content: Arc::new(RwLock::new(None)),
             pool,
+            blob_dir,
         }
     }
 
     pub fn start(&self, app_handle: AppHandle) {
         let last_content = Arc::clone(&self.last_content);
         let pool = Arc::clone(&self.pool);
+        let blob_dir = self.blob_dir.clone();
 
         tauri::async_runtime::spawn(async move {
-            let mut interval = interval(Duration::from_millis(500));
+            let mut settings_rx = settings::subscribe();
+            let mut poll_interval_ms = settings_rx.borrow_and_update().poll_interval_ms;
+            let mut interval = interval(Duration::from_millis(poll_interval_ms));
             let mut clipboard = Clipboard::new().expect("Failed to access clipboard");
+            #[cfg(target_os = "linux")]
+            let mut primary = selection::Debouncer::new();
+            #[cfg(target_os = "linux")]
+            let mut owner = persist::OwnerInspector::new();
 
             loop {
                 interval.tick().await;
 
+                // Pick up a changed poll interval without restarting
+                if settings_rx.has_changed().unwrap_or(false) {
+                    let new_interval_ms = settings_rx.borrow_and_update().poll_interval_ms;
+                    if new_interval_ms != poll_interval_ms {
+                        poll_interval_ms = new_interval_ms;
+                        interval = tokio::time::interval(Duration::from_millis(poll_interval_ms));
+                    }
+                }
+
+                // PRIMARY selection, once it stops changing
+                #[cfg(target_os = "linux")]
+                {
+                    let current = settings::current();
+                    if current.selection.watches_primary() {
+                        let delay = Duration::from_millis(current.selection.primary_debounce_ms);
+                        let selected = selection::read_primary(&mut clipboard)
+                            .and_then(|text| primary.observe(&text, Instant::now(), delay));
+
+                        if let Some(text) = selected {
+                            if current.selection.sync_selections {
+                                // Known to the CLIPBOARD check below, so it's only recorded
+                                // when PRIMARY capture is on
+                                *last_content.write().await = Some(text.clone());
+                                if let Err(e) = clipboard.set_text(text.clone()) {
+                                    println!("⚠️  [WARNING] Failed to copy the selection to the clipboard: {}", e);
+                                }
+                            }
+                            if current.selection.capture_primary && !is_recording_paused() {
+                                record_text(&app_handle, &pool, &blob_dir, text).await;
+                            }
+                        }
+                    }
+                }
+
                 // Try to get clipboard text
                 if let Ok(text) = clipboard.get_text() {
                     let should_process = {
@@ -51,20 +149,46 @@ impl ClipboardMonitor {
                             *last = Some(text.clone());
                         }
 
-                        // Create clipboard content
-                        let content = ClipboardContent::new_text(text.clone());
+                        // Skip our own writes (auto-paste, clipboard restore)
+                        if take_self_write(&text) {
+                            continue;
+                        }
 
-                        // Emit event to frontend
-                        let _ = app_handle.emit("clipboard-update", &content);
+                        #[cfg(target_os = "linux")]
+                        {
+                            // Passwords are neither kept alive nor recorded. Without an
+                            // X server arboard can't read the clipboard either, so a
+                            // missing answer only happens for owners that don't reply.
+                            let targets = owner.targets().await;
+                            if targets.as_deref().is_some_and(persist::is_concealed) {
+                                println!("🔧 [DEBUG] Skipping a copy marked as secret by a password manager");
+                                continue;
+                            }
 
-                        // Get the frontmost application name
-                        let source_app = get_frontmost_app();
+                            let current = settings::current();
+                            if current.selection.sync_selections {
+                                primary.mark_reported(&text);
+                                if let Err(e) = selection::write_primary(&mut clipboard, &text) {
+                                    println!("⚠️  [WARNING] Failed to copy the clipboard to the selection: {}", e);
+                                }
+                            }
+                            // Files, images and rich text stay with their source, serving
+                            // only the text would turn them into a path or alt text
+                            let servable = targets.as_deref().is_some_and(persist::is_text_only);
+                            if current.selection.persist_clipboard && servable {
+                                if let Err(e) = persist::take_ownership(&mut clipboard, &text) {
+                                    println!("⚠️  [WARNING] Failed to keep the clipboard alive: {}", e);
+                                }
+                            }
+                        }
 
-                        // Save to database (using upsert to avoid duplicates)
-                        let entry = NewClipboardEntry::new_text_with_source(text, source_app);
-                        if let Err(e) = database::upsert_entry(&pool, entry).await {
-                            eprintln!("Failed to save clipboard entry: {}", e);
+                        // Keep tracking while paused so resuming doesn't record
+                        // whatever was copied in the meantime
+                        if is_recording_paused() {
+                            continue;
                         }
+
+                        record_text(&app_handle, &pool, &blob_dir, text).await;
                     }
                 }
             }
@@ -72,6 +196,34 @@ impl ClipboardMonitor {
     }
 }
 
+/// Save a copied or selected text to history and tell the frontend
+async fn record_text(app_handle: &AppHandle, pool: &SqlitePool, blob_dir: &Path, text: String) {
+    let current = settings::current();
+    if text.len() as u64 > current.max_capture_bytes {
+        println!(
+            "⚠️  [WARNING] Not recording a {} byte copy, the limit is {} bytes",
+            text.len(),
+            current.max_capture_bytes
+        );
+        return;
+    }
+
+    // Create clipboard content; listeners only need a preview
+    let content = ClipboardContent::new_text(offload::preview(&text).to_string());
+
+    // Emit event to frontend
+    let _ = app_handle.emit("clipboard-update", &content);
+
+    // Get the frontmost application name
+    let source_app = get_frontmost_app();
+
+    // Save to database (using upsert to avoid duplicates)
+    let entry = NewClipboardEntry::new_text_with_source(text, source_app);
+    if let Err(e) = offload::upsert_text(pool, blob_dir, entry, current.offload_threshold_bytes).await {
+        eprintln!("Failed to save clipboard entry: {}", e);
+    }
+}
+
 /// Get the frontmost application name on macOS
 fn get_frontmost_app() -> Option<String> {
     #[cfg(target_os = "macos")]
@@ -135,6 +287,26 @@ mod tests {
         assert!(!should_save_content("Hello", Some("Hello")));
     }
 
+    #[test]
+    fn test_self_write_is_consumed_once() {
+        let now = Instant::now();
+        let mut writes = vec![("pasted".to_string(), now)];
+
+        assert!(!take_matching_write(&mut writes, "other", now));
+        assert!(take_matching_write(&mut writes, "pasted", now));
+        assert!(!take_matching_write(&mut writes, "pasted", now), "Marker should only suppress one change");
+    }
+
+    #[test]
+    fn test_self_write_expires() {
+        let written_at = Instant::now();
+        let mut writes = vec![("pasted".to_string(), written_at)];
+
+        let later = written_at + SELF_WRITE_TTL + Duration::from_millis(1);
+        assert!(!take_matching_write(&mut writes, "pasted", later));
+        assert!(writes.is_empty(), "Expired markers should be pruned");
+    }
+
     #[test]
     fn test_clipboard_content_deduplication() {
         let content1 = "Hello World";
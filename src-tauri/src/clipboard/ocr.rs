@@ -0,0 +1,313 @@
+// Text recognition for image entries with a locally installed Tesseract,
+// run as a subprocess so nothing has to be linked in. Entries are processed
+// one at a time in the background after their thumbnails; without Tesseract
+// the queue is skipped and images simply aren't searchable by their text.
+// An image Tesseract fails on gets an "ocr_error" in its metadata, together
+// with the setup it failed with, and is only tried again once Tesseract or
+// the languages change.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+use crate::database;
+use crate::settings;
+
+/// A single image taking longer than this is given up on
+const RECOGNIZE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[cfg(windows)]
+const EXECUTABLE: &str = "tesseract.exe";
+#[cfg(not(windows))]
+const EXECUTABLE: &str = "tesseract";
+
+/// Install locations that aren't on the PATH of apps started from the
+/// desktop, e.g. Homebrew's on macOS
+const EXTRA_DIRS: &[&str] = &[
+    "/opt/homebrew/bin",
+    "/usr/local/bin",
+    "/usr/bin",
+    "C:\\Program Files\\Tesseract-OCR",
+];
+
+// Only one queue runs at a time, so an entry is never recognized twice
+static QUEUE: Mutex<()> = Mutex::const_new(());
+static WARNED_MISSING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrStatus {
+    pub enabled: bool,
+    /// Tesseract that will be used, None when it isn't installed
+    pub tesseract: Option<String>,
+}
+
+pub fn status() -> OcrStatus {
+    OcrStatus {
+        enabled: settings::current().ocr_enabled,
+        tesseract: find_tesseract().map(|path| path.to_string_lossy().into_owned()),
+    }
+}
+
+/// Tesseract on the PATH or in one of the usual install locations
+pub fn find_tesseract() -> Option<PathBuf> {
+    let path_dirs = std::env::var_os("PATH").map(|path| std::env::split_paths(&path).collect::<Vec<_>>()).unwrap_or_default();
+    find_in(path_dirs.into_iter().chain(EXTRA_DIRS.iter().map(PathBuf::from)))
+}
+
+fn find_in(dirs: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    dirs.into_iter().map(|dir| dir.join(EXECUTABLE)).find(|candidate| candidate.is_file())
+}
+
+/// Languages are passed to Tesseract as an argument, so only names like
+/// "eng", "chi_sim" or "eng+deu" are accepted
+pub fn check_languages(languages: &str) -> Result<()> {
+    let valid = |name: &str| {
+        !name.is_empty()
+            && !name.starts_with('-')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '/'))
+    };
+    if !languages.split('+').all(valid) {
+        bail!("ocr_languages must be Tesseract language names joined with '+', e.g. \"eng+deu\"");
+    }
+    Ok(())
+}
+
+/// Run Tesseract on one image and return the text it found
+pub async fn recognize(tesseract: &Path, image: &Path, languages: &str) -> Result<String> {
+    let mut command = Command::new(tesseract);
+    command
+        .arg(image)
+        .arg("stdout")
+        .arg("-l")
+        .arg(languages)
+        // One thread keeps a large screenshot from taking over the CPU
+        .env("OMP_THREAD_LIMIT", "1")
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(RECOGNIZE_TIMEOUT, command.output())
+        .await
+        .context("Tesseract timed out")?
+        .with_context(|| format!("Failed to run {}", tesseract.display()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or("no error output");
+        bail!("Tesseract failed ({}): {}", output.status, reason);
+    }
+    Ok(normalize(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Drop the page break Tesseract ends with, trailing spaces and runs of
+/// blank lines
+fn normalize(raw: &str) -> String {
+    let mut text = String::new();
+    let mut blank = false;
+    for line in raw.replace('\u{c}', "\n").lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank = !text.is_empty();
+            continue;
+        }
+        if blank {
+            text.push('\n');
+            blank = false;
+        }
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(line);
+    }
+    text
+}
+
+/// Identifies a Tesseract install and language selection. Replacing the
+/// binary (e.g. an upgrade) changes its modification time and so the key.
+pub fn setup_key(tesseract: &Path, languages: &str) -> String {
+    let modified = std::fs::metadata(tesseract)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    format!("{}@{} -l {}", tesseract.display(), modified, languages)
+}
+
+/// Recognize the text of every image entry that hasn't been through OCR.
+/// Failures are recorded on the entry so it isn't retried with the same setup.
+pub async fn process_pending(pool: &SqlitePool, tesseract: &Path, languages: &str) -> Result<usize> {
+    let setup = setup_key(tesseract, languages);
+    let mut processed = 0;
+    for entry in database::get_images_without_ocr(pool, &setup).await? {
+        let Some(ref file_path) = entry.file_path else {
+            continue;
+        };
+        match recognize(tesseract, Path::new(file_path), languages).await {
+            Ok(text) => {
+                database::set_ocr_text(pool, entry.id, &text).await?;
+                processed += 1;
+            }
+            Err(e) => {
+                println!("⚠️  [WARNING] No OCR for entry {}: {:#}", entry.id, e);
+                let mut fields = serde_json::Map::new();
+                fields.insert("ocr_error".to_string(), format!("{:#}", e).into());
+                fields.insert("ocr_setup".to_string(), setup.clone().into());
+                database::merge_metadata(pool, entry.id, fields).await?;
+            }
+        }
+    }
+    Ok(processed)
+}
+
+/// Process pending images if OCR is enabled and Tesseract is installed
+pub async fn run_queue(pool: &SqlitePool) {
+    let current = settings::current();
+    if !current.ocr_enabled {
+        return;
+    }
+    let Some(tesseract) = find_tesseract() else {
+        if !WARNED_MISSING.swap(true, Ordering::Relaxed) {
+            println!("⚠️  [WARNING] Tesseract not found, text in images won't be searchable");
+        }
+        return;
+    };
+
+    let _running = QUEUE.lock().await;
+    match process_pending(pool, &tesseract, &current.ocr_languages).await {
+        Ok(processed) if processed > 0 => println!("🔧 [DEBUG] Recognized text in {} images", processed),
+        Ok(_) => {}
+        Err(e) => eprintln!("❌ [ERROR] OCR failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{ClipboardSearchParams, Database, NewClipboardEntry};
+    use tempfile::TempDir;
+
+    fn image_entry(path: &Path) -> NewClipboardEntry {
+        NewClipboardEntry {
+            content_type: "image".to_string(),
+            text_content: None,
+            file_path: Some(path.to_string_lossy().into_owned()),
+            metadata: None,
+            source_app: None,
+        }
+    }
+
+    /// Shell script standing in for Tesseract
+    #[cfg(unix)]
+    fn fake_tesseract(dir: &Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join(EXECUTABLE);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Error: refused  \n\n\n\n  at db.rs:42\n\u{c}"), "Error: refused\n\n  at db.rs:42");
+        assert_eq!(normalize("\n\n first\nsecond\n"), " first\nsecond");
+        assert_eq!(normalize(" \n\u{c}"), "");
+    }
+
+    #[test]
+    fn test_check_languages() {
+        assert!(check_languages("eng").is_ok());
+        assert!(check_languages("eng+chi_sim").is_ok());
+        assert!(check_languages("script/Latin").is_ok());
+        assert!(check_languages("").is_err());
+        assert!(check_languages("eng+").is_err());
+        assert!(check_languages("--psm").is_err());
+        assert!(check_languages("eng deu").is_err());
+    }
+
+    #[test]
+    fn test_find_in() {
+        let dir = TempDir::new().unwrap();
+        let empty = dir.path().join("empty");
+        let bin = dir.path().join("bin");
+        std::fs::create_dir_all(&empty).unwrap();
+        std::fs::create_dir_all(&bin).unwrap();
+        assert_eq!(find_in([empty.clone(), bin.clone()]), None);
+
+        std::fs::write(bin.join(EXECUTABLE), "").unwrap();
+        assert_eq!(find_in([empty, bin.clone()]), Some(bin.join(EXECUTABLE)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_recognized_text_is_searchable() -> Result<()> {
+        let dir = TempDir::new()?;
+        let tesseract = fake_tesseract(dir.path(), r#"printf 'Error: connection refused  \n\n\n%s %s\n\f' "$3" "$4""#);
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+        let id = database::insert_entry(pool, image_entry(&dir.path().join("shot.png"))).await?;
+
+        assert_eq!(process_pending(pool, &tesseract, "eng+deu").await?, 1);
+        let entry = database::get_entry(pool, id).await?.unwrap();
+        assert_eq!(entry.ocr_text.as_deref(), Some("Error: connection refused\n\n-l eng+deu"));
+
+        let params = ClipboardSearchParams { query: Some("connection refused".to_string()), ..Default::default() };
+        let found = database::search_entries(pool, params).await?;
+        assert_eq!(found.iter().map(|entry| entry.id).collect::<Vec<_>>(), vec![id]);
+
+        // Processed entries aren't picked up again
+        assert_eq!(process_pending(pool, &tesseract, "eng").await?, 0);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failures_are_only_retried_with_another_setup() -> Result<()> {
+        let dir = TempDir::new()?;
+        let tesseract = fake_tesseract(dir.path(), "echo 'Failed loading language xyz' >&2; exit 1");
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+        let id = database::insert_entry(pool, image_entry(&dir.path().join("shot.png"))).await?;
+
+        let error = recognize(&tesseract, &dir.path().join("shot.png"), "xyz").await.unwrap_err();
+        assert!(error.to_string().contains("Failed loading language xyz"));
+
+        assert_eq!(process_pending(pool, &tesseract, "xyz").await?, 0);
+        let entry = database::get_entry(pool, id).await?.unwrap();
+        assert_eq!(entry.ocr_text, None);
+        let metadata: serde_json::Value = serde_json::from_str(entry.metadata.as_deref().unwrap())?;
+        assert!(metadata["ocr_error"].as_str().unwrap().contains("Failed loading language xyz"));
+
+        // Not tried again with the same Tesseract and languages
+        assert!(database::get_images_without_ocr(pool, &setup_key(&tesseract, "xyz")).await?.is_empty());
+        assert_eq!(database::get_images_without_ocr(pool, &setup_key(&tesseract, "eng")).await?.len(), 1);
+
+        // A new Tesseract picks it up again
+        let other = dir.path().join("other");
+        std::fs::create_dir_all(&other)?;
+        let fixed = fake_tesseract(&other, "echo found it");
+        assert_eq!(process_pending(pool, &fixed, "xyz").await?, 1);
+        assert_eq!(database::get_entry(pool, id).await?.unwrap().ocr_text.as_deref(), Some("found it"));
+
+        // Not installed at all
+        assert!(recognize(&dir.path().join("missing"), &dir.path().join("shot.png"), "eng").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reads_fixture_image() -> Result<()> {
+        let Some(tesseract) = find_tesseract() else {
+            println!("Tesseract is not installed, skipping");
+            return Ok(());
+        };
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ocr/hello-ocr-42.png");
+        let text = recognize(&tesseract, &fixture, "eng").await?;
+        assert!(text.contains("HELLO OCR 42"), "Recognized {:?}", text);
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+// Keeping copies alive on Linux. On X11 (and Wayland through XWayland) the
+// app that copied something serves it until it exits, so after each new copy
+// the monitor can take over the CLIPBOARD selection and serve it itself. Only
+// plain text and HTML can be served again, so copies offering anything else
+// (files, images, rich text) are left with their source. Copies a password
+// manager marks as secret are neither taken over nor recorded.
+
+use anyhow::Result;
+#[cfg(target_os = "linux")]
+use anyhow::bail;
+#[cfg(target_os = "linux")]
+use arboard::Clipboard;
+#[cfg(target_os = "linux")]
+use std::time::{Duration, Instant};
+
+/// Selection targets password managers offer alongside a secret, e.g.
+/// KeePassXC's KDE hint
+const CONCEALED_TARGETS: &[&str] = &["x-kde-passwordManagerHint", "application/x-nspasteboard-concealed-type"];
+
+/// Targets every owner answers, which say nothing about the content
+const META_TARGETS: &[&str] = &["TARGETS", "TIMESTAMP", "MULTIPLE", "SAVE_TARGETS", "DELETE"];
+
+/// Flavors `take_ownership` serves again
+const TEXT_TARGETS: &[&str] = &["UTF8_STRING", "STRING", "TEXT", "COMPOUND_TEXT", "text/html"];
+
+/// How long the clipboard owner gets to list its targets
+#[cfg(target_os = "linux")]
+const TARGETS_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Whether the owner's targets mark the content as a password
+pub fn is_concealed(targets: &[String]) -> bool {
+    targets.iter().any(|target| CONCEALED_TARGETS.contains(&target.as_str()))
+}
+
+/// Whether serving plain text and HTML ourselves keeps everything the owner
+/// offers. False for copied files, images, rich text and the like.
+pub fn is_text_only(targets: &[String]) -> bool {
+    targets.iter().all(|target| {
+        META_TARGETS.contains(&target.as_str())
+            || TEXT_TARGETS.contains(&target.as_str())
+            || target.starts_with("text/plain")
+    })
+}
+
+/// Asks the CLIPBOARD owner for its targets over one X connection, which is
+/// kept for as long as it works
+#[cfg(target_os = "linux")]
+pub struct TargetReader {
+    conn: x11rb::rust_connection::RustConnection,
+    window: u32,
+    clipboard: u32,
+    targets: u32,
+    property: u32,
+}
+
+#[cfg(target_os = "linux")]
+impl TargetReader {
+    pub fn connect() -> Result<Self> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{ConnectionExt, CreateWindowAux, WindowClass};
+
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+        let window = conn.generate_id()?;
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )?;
+
+        let clipboard = conn.intern_atom(false, b"CLIPBOARD")?.reply()?.atom;
+        let targets = conn.intern_atom(false, b"TARGETS")?.reply()?.atom;
+        let property = conn.intern_atom(false, b"SUPERKBD_TARGETS")?.reply()?.atom;
+        Ok(Self { conn, window, clipboard, targets, property })
+    }
+
+    /// Target names the current owner offers, empty when nobody owns the
+    /// clipboard. Blocks for up to `TARGETS_TIMEOUT`.
+    pub fn clipboard_targets(&self) -> Result<Vec<String>> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+        use x11rb::protocol::Event;
+
+        let conn = &self.conn;
+        conn.convert_selection(self.window, self.clipboard, self.targets, self.property, x11rb::CURRENT_TIME)?;
+        conn.flush()?;
+
+        let deadline = Instant::now() + TARGETS_TIMEOUT;
+        loop {
+            match conn.poll_for_event()? {
+                Some(Event::SelectionNotify(event)) if event.requestor == self.window => {
+                    // No owner, or one that can't list its targets
+                    if event.property == x11rb::NONE {
+                        return Ok(Vec::new());
+                    }
+                    break;
+                }
+                Some(_) => {}
+                None if Instant::now() >= deadline => bail!("The clipboard owner didn't answer"),
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+
+        let reply = conn.get_property(true, self.window, self.property, AtomEnum::ATOM, 0, 1024)?.reply()?;
+        let atoms: Vec<u32> = reply.value32().map(Iterator::collect).unwrap_or_default();
+        let mut names = Vec::with_capacity(atoms.len());
+        for atom in atoms {
+            names.push(String::from_utf8_lossy(&conn.get_atom_name(atom)?.reply()?.name).into_owned());
+        }
+        Ok(names)
+    }
+}
+
+/// The monitor's view of the clipboard owner. The X round trips run on the
+/// blocking pool so a slow owner never stalls the runtime.
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+pub struct OwnerInspector {
+    reader: Option<TargetReader>,
+    /// No X server to ask, e.g. a Wayland session without XWayland
+    unavailable: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl OwnerInspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Targets of the current owner, None when they can't be read. The
+    /// connection is opened on first use and again after a failure.
+    pub async fn targets(&mut self) -> Option<Vec<String>> {
+        if self.unavailable {
+            return None;
+        }
+        let reader = match self.reader.take() {
+            Some(reader) => reader,
+            None => match tokio::task::spawn_blocking(TargetReader::connect).await {
+                Ok(Ok(reader)) => reader,
+                Ok(Err(e)) => {
+                    // Not retried: every copy would fail the same way
+                    println!("⚠️  [WARNING] No X server to ask about clipboard owners ({}), copies won't be kept alive", e);
+                    self.unavailable = true;
+                    return None;
+                }
+                Err(_) => return None,
+            },
+        };
+
+        let (reader, result) = tokio::task::spawn_blocking(move || {
+            let result = reader.clipboard_targets();
+            (reader, result)
+        })
+        .await
+        .ok()?;
+        match result {
+            Ok(targets) => {
+                self.reader = Some(reader);
+                Some(targets)
+            }
+            Err(e) => {
+                // A late answer would confuse the next request, so start over
+                println!("⚠️  [WARNING] Could not read clipboard targets: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Serve the current copy from `clipboard` so it outlives the app it came
+/// from. `text` is what was just read; HTML is kept when the source offered it.
+/// Only call this when the owner's targets pass `is_text_only`.
+#[cfg(target_os = "linux")]
+pub fn take_ownership(clipboard: &mut Clipboard, text: &str) -> Result<()> {
+    match clipboard.get().html() {
+        Ok(html) => clipboard.set_html(html.as_str(), Some(text))?,
+        Err(_) => clipboard.set_text(text)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_concealed() {
+        let targets = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert!(!is_concealed(&targets(&["TARGETS", "UTF8_STRING", "text/plain"])));
+        assert!(is_concealed(&targets(&["TARGETS", "UTF8_STRING", "x-kde-passwordManagerHint"])));
+        assert!(is_concealed(&targets(&["application/x-nspasteboard-concealed-type"])));
+        assert!(!is_concealed(&[]));
+    }
+
+    #[test]
+    fn test_is_text_only() {
+        let targets = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert!(is_text_only(&targets(&["TARGETS", "TIMESTAMP", "UTF8_STRING", "STRING", "text/plain;charset=utf-8"])));
+        assert!(is_text_only(&targets(&["TARGETS", "text/html", "text/plain"])));
+        assert!(!is_text_only(&targets(&["TARGETS", "text/uri-list", "x-special/gnome-copied-files", "UTF8_STRING"])));
+        assert!(!is_text_only(&targets(&["TARGETS", "image/png", "text/html"])));
+        assert!(!is_text_only(&targets(&["text/rtf", "text/plain"])));
+    }
+
+    // Run by test_copy_survives_its_source_exiting in a child process: copy
+    // the text in COPY_AND_EXIT_TEXT, serve it briefly, then exit
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "helper for the X11 test"]
+    fn copy_and_exit_helper() -> Result<()> {
+        let Ok(text) = std::env::var("COPY_AND_EXIT_TEXT") else {
+            return Ok(());
+        };
+        let mut clipboard = Clipboard::new()?;
+        clipboard.set_text(text)?;
+        std::thread::sleep(Duration::from_millis(600));
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn copy_in_short_lived_process(text: &str) -> Result<std::process::Child> {
+        Ok(std::process::Command::new(std::env::current_exe()?)
+            .args(["--ignored", "--exact", "clipboard::persist::tests::copy_and_exit_helper", "--test-threads=1"])
+            .env("COPY_AND_EXIT_TEXT", text)
+            .stdout(std::process::Stdio::null())
+            .spawn()?)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn wait_for_text(clipboard: &mut Clipboard, text: &str) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if clipboard.get_text().is_ok_and(|current| current == text) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    // Needs an X server: xvfb-run cargo test --lib persist -- --ignored
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "needs an X server"]
+    fn test_copy_survives_its_source_exiting() -> Result<()> {
+        let mut monitor = Clipboard::new()?;
+
+        // Without persistence the copy is gone with its source
+        let mut source = copy_in_short_lived_process("lost with the terminal")?;
+        assert!(wait_for_text(&mut monitor, "lost with the terminal"));
+        let targets = TargetReader::connect()?.clipboard_targets()?;
+        assert!(!is_concealed(&targets) && is_text_only(&targets));
+        source.wait()?;
+        assert!(Clipboard::new()?.get_text().is_err());
+
+        let mut source = copy_in_short_lived_process("kept after exit")?;
+        assert!(wait_for_text(&mut monitor, "kept after exit"));
+        take_ownership(&mut monitor, "kept after exit")?;
+        source.wait()?;
+        assert_eq!(Clipboard::new()?.get_text()?, "kept after exit");
+        Ok(())
+    }
+}
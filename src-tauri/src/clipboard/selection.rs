@@ -0,0 +1,178 @@
+// The X11 PRIMARY selection: select to copy, middle-click to paste. A drag
+// changes it on every mouse move, so a selection only counts once it has
+// stopped changing for a while. Optionally PRIMARY and CLIPBOARD are kept in
+// sync both ways, like Klipper does.
+
+use anyhow::{bail, Result};
+#[cfg(target_os = "linux")]
+use arboard::{Clipboard, GetExtLinux, LinuxClipboardKind, SetExtLinux};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelectionSettings {
+    /// Record the PRIMARY selection in history (Linux only)
+    pub capture_primary: bool,
+    /// How long PRIMARY has to stay the same before it counts
+    pub primary_debounce_ms: u64,
+    /// Copy a new selection to the clipboard and a new copy to the selection
+    pub sync_selections: bool,
+    /// Serve each new text copy ourselves so it survives the app it came from
+    pub persist_clipboard: bool,
+}
+
+impl Default for SelectionSettings {
+    fn default() -> Self {
+        Self {
+            capture_primary: false,
+            primary_debounce_ms: 700,
+            sync_selections: false,
+            persist_clipboard: false,
+        }
+    }
+}
+
+impl SelectionSettings {
+    pub fn validate(&self) -> Result<()> {
+        if !(100..=10_000).contains(&self.primary_debounce_ms) {
+            bail!("primary_debounce_ms must be between 100 and 10000");
+        }
+        Ok(())
+    }
+
+    /// Whether the monitor has to poll PRIMARY at all
+    pub fn watches_primary(&self) -> bool {
+        self.capture_primary || self.sync_selections
+    }
+}
+
+/// Reports a value once it has stayed the same for the debounce delay, and
+/// only if it differs from the one reported last
+#[derive(Debug, Default)]
+pub struct Debouncer {
+    pending: Option<(String, Instant)>,
+    reported: Option<String>,
+}
+
+impl Debouncer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, text: &str, now: Instant, delay: Duration) -> Option<String> {
+        match &self.pending {
+            Some((pending, since)) if pending == text => {
+                if now.duration_since(*since) < delay || self.reported.as_deref() == Some(text) {
+                    return None;
+                }
+                self.reported = Some(text.to_string());
+                self.reported.clone()
+            }
+            _ => {
+                self.pending = Some((text.to_string(), now));
+                None
+            }
+        }
+    }
+
+    /// Treat `text` as reported, e.g. after writing it to PRIMARY ourselves
+    pub fn mark_reported(&mut self, text: &str) {
+        self.reported = Some(text.to_string());
+    }
+}
+
+/// Current PRIMARY text, None when nothing is selected
+#[cfg(target_os = "linux")]
+pub fn read_primary(clipboard: &mut Clipboard) -> Option<String> {
+    clipboard
+        .get()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text()
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
+#[cfg(target_os = "linux")]
+pub fn write_primary(clipboard: &mut Clipboard, text: &str) -> Result<()> {
+    clipboard.set().clipboard(LinuxClipboardKind::Primary).text(text)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn test_drag_is_reported_once_it_settles() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut debouncer = Debouncer::new();
+
+        // Growing selection while the mouse moves
+        assert_eq!(debouncer.observe("q", at(0), DELAY), None);
+        assert_eq!(debouncer.observe("quick", at(100), DELAY), None);
+        assert_eq!(debouncer.observe("quick brown", at(200), DELAY), None);
+        assert_eq!(debouncer.observe("quick brown", at(600), DELAY), None, "Not stable long enough");
+        assert_eq!(debouncer.observe("quick brown", at(700), DELAY).as_deref(), Some("quick brown"));
+
+        // Still selected: not reported again
+        assert_eq!(debouncer.observe("quick brown", at(1500), DELAY), None);
+
+        assert_eq!(debouncer.observe("fox", at(2000), DELAY), None);
+        assert_eq!(debouncer.observe("fox", at(2500), DELAY).as_deref(), Some("fox"));
+    }
+
+    #[test]
+    fn test_reselecting_an_older_text_is_reported() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new();
+        debouncer.observe("a", start, DELAY);
+        assert!(debouncer.observe("a", start + DELAY, DELAY).is_some());
+        debouncer.observe("b", start + DELAY * 2, DELAY);
+        assert!(debouncer.observe("b", start + DELAY * 3, DELAY).is_some());
+        debouncer.observe("a", start + DELAY * 4, DELAY);
+        assert_eq!(debouncer.observe("a", start + DELAY * 5, DELAY).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_own_writes_are_not_reported() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new();
+        debouncer.mark_reported("synced from the clipboard");
+        debouncer.observe("synced from the clipboard", start, DELAY);
+        assert_eq!(debouncer.observe("synced from the clipboard", start + DELAY, DELAY), None);
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings = SelectionSettings::default();
+        assert!(settings.validate().is_ok());
+        assert!(!settings.watches_primary(), "Off unless the user opts in");
+        assert!(!settings.persist_clipboard);
+        assert!(SelectionSettings { sync_selections: true, ..settings.clone() }.watches_primary());
+        assert!(SelectionSettings { primary_debounce_ms: 10, ..settings }.validate().is_err());
+    }
+
+    // Needs an X server: xvfb-run cargo test --lib selection -- --ignored
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "needs an X server"]
+    fn test_primary_round_trip_on_x11() -> Result<()> {
+        let mut owner = Clipboard::new()?;
+        let mut reader = Clipboard::new()?;
+
+        write_primary(&mut owner, "middle-click me")?;
+        assert_eq!(read_primary(&mut reader).as_deref(), Some("middle-click me"));
+
+        // Syncing a selection to the clipboard leaves both with the text
+        owner.set_text("middle-click me")?;
+        assert_eq!(reader.get_text()?, "middle-click me");
+
+        write_primary(&mut owner, " ")?;
+        assert_eq!(read_primary(&mut reader), None, "Blank selections are ignored");
+        Ok(())
+    }
+}
@@ -0,0 +1,340 @@
+// Thumbnails for image entries, written next to the original blob as
+// "<name>.thumb<size>.png". Generating them also records the image's width,
+// height, format and file size in the entry's metadata, which doubles as the
+// marker that an entry has been processed, and adds the text of any QR code
+// or barcode in the image as a child entry. Images that can't be decoded get
+// a "thumbnail_error" instead and aren't tried again.
+
+use anyhow::{Context, Result};
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::ocr;
+use crate::database::{self, ClipboardEntry, NewClipboardEntry};
+use crate::qr;
+
+/// Longest side in pixels: list rows and the selected preview
+pub const THUMBNAIL_SIZES: [u32; 2] = [64, 256];
+
+// Only one pass runs at a time, so an image's codes are never added twice
+static QUEUE: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Lowercase extension of the detected format, e.g. "png"
+    pub format: String,
+    pub file_size: u64,
+}
+
+/// Where the thumbnail of `original` with the given longest side lives
+pub fn thumbnail_path(original: &Path, size: u32) -> PathBuf {
+    let stem = original.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    original.with_file_name(format!("{}.thumb{}.png", stem, size))
+}
+
+/// Smallest thumbnail size that covers `requested`, or the largest one
+pub fn thumbnail_size_for(requested: u32) -> u32 {
+    THUMBNAIL_SIZES
+        .iter()
+        .copied()
+        .find(|size| *size >= requested)
+        .unwrap_or(THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1])
+}
+
+/// Read and decode the image at `original`
+pub fn decode(original: &Path) -> Result<(ImageInfo, DynamicImage)> {
+    let data = std::fs::read(original).with_context(|| format!("Failed to read {}", original.display()))?;
+    decode_data(&data).with_context(|| format!("Failed to decode {}", original.display()))
+}
+
+/// Decode an image file's contents
+pub fn decode_data(data: &[u8]) -> Result<(ImageInfo, DynamicImage)> {
+    let format = image::guess_format(data).context("Unrecognized image format")?;
+    let image = image::load_from_memory_with_format(data, format)?;
+    let (width, height) = image.dimensions();
+
+    let info = ImageInfo {
+        width,
+        height,
+        format: format.extensions_str().first().copied().unwrap_or("unknown").to_string(),
+        file_size: data.len() as u64,
+    };
+    Ok((info, image))
+}
+
+/// Write all thumbnail sizes of `image` next to `original`. Images smaller
+/// than a thumbnail size are stored as is, never upscaled.
+pub fn write_thumbnails(original: &Path, image: &DynamicImage) -> Result<()> {
+    let (width, height) = image.dimensions();
+    for size in THUMBNAIL_SIZES {
+        let thumbnail = if width <= size && height <= size { image.clone() } else { image.thumbnail(size, size) };
+        thumbnail
+            .save_with_format(thumbnail_path(original, size), ImageFormat::Png)
+            .with_context(|| format!("Failed to write {}px thumbnail", size))?;
+    }
+    Ok(())
+}
+
+/// Decode the image at `original` and write all thumbnail sizes for it
+pub fn generate(original: &Path) -> Result<(ImageInfo, DynamicImage)> {
+    let (info, image) = decode(original)?;
+    write_thumbnails(original, &image)?;
+    Ok((info, image))
+}
+
+/// Whether processing gave up on the entry's image
+pub fn has_failed(entry: &ClipboardEntry) -> bool {
+    entry
+        .metadata
+        .as_deref()
+        .and_then(|metadata| serde_json::from_str::<serde_json::Value>(metadata).ok())
+        .is_some_and(|metadata| metadata.get("thumbnail_error").is_some())
+}
+
+/// Generate thumbnails for one image entry, store its dimensions and add
+/// the codes found in it as child entries. An image that can't be decoded is
+/// marked as failed; failing to read the file or to write thumbnails leaves
+/// it for the next run.
+pub async fn process_entry(pool: &SqlitePool, entry: &ClipboardEntry) -> Result<ImageInfo> {
+    let path = PathBuf::from(entry.file_path.as_deref().context("Image entry has no file")?);
+    let (path, decoded) = tokio::task::spawn_blocking(move || {
+        let data = std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        anyhow::Ok((path, decode_data(&data)))
+    })
+    .await??;
+    let (info, image) = match decoded {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            let mut fields = serde_json::Map::new();
+            fields.insert("thumbnail_error".to_string(), format!("{:#}", e).into());
+            database::merge_metadata(pool, entry.id, fields).await?;
+            return Err(e);
+        }
+    };
+
+    let codes = tokio::task::spawn_blocking(move || {
+        write_thumbnails(&path, &image)?;
+        anyhow::Ok(qr::scan(&image))
+    })
+    .await??;
+
+    for code in codes {
+        let child = NewClipboardEntry::new_text_with_source(code, entry.source_app.clone());
+        database::insert_child_entry(pool, entry.id, &child).await?;
+    }
+
+    let mut fields = serde_json::Map::new();
+    fields.insert("width".to_string(), info.width.into());
+    fields.insert("height".to_string(), info.height.into());
+    fields.insert("format".to_string(), info.format.clone().into());
+    fields.insert("file_size".to_string(), info.file_size.into());
+    database::merge_metadata(pool, entry.id, fields).await?;
+    Ok(info)
+}
+
+/// Process every image entry that has no dimensions yet. Failures are
+/// logged; see `process_entry` for which ones are retried.
+pub async fn process_pending(pool: &SqlitePool) -> Result<usize> {
+    let _running = QUEUE.lock().await;
+    let mut processed = 0;
+    for entry in database::get_images_without_dimensions(pool).await? {
+        match process_entry(pool, &entry).await {
+            Ok(_) => processed += 1,
+            Err(e) => println!("⚠️  [WARNING] No thumbnails for entry {}: {:#}", entry.id, e),
+        }
+    }
+    Ok(processed)
+}
+
+/// Run `process_pending` in the background, e.g. right after images were
+/// inserted, followed by OCR of the images
+pub fn spawn_pending(pool: Arc<SqlitePool>) {
+    tauri::async_runtime::spawn(async move {
+        match process_pending(&pool).await {
+            Ok(processed) if processed > 0 => println!("🔧 [DEBUG] Generated thumbnails for {} images", processed),
+            Ok(_) => {}
+            Err(e) => eprintln!("❌ [ERROR] Thumbnail generation failed: {}", e),
+        }
+        ocr::run_queue(&pool).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{ClipboardSearchParams, Database};
+    use image::{ImageBuffer, Rgba};
+    use tempfile::TempDir;
+
+    fn write_png(path: &Path, width: u32, height: u32) {
+        ImageBuffer::from_pixel(width, height, Rgba([200u8, 40, 40, 255])).save(path).unwrap();
+    }
+
+    #[test]
+    fn test_generate_writes_thumbnails() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("abc.png");
+        write_png(&original, 1000, 500);
+
+        let (info, _) = generate(&original).unwrap();
+        assert_eq!((info.width, info.height, info.format.as_str()), (1000, 500, "png"));
+        assert_eq!(info.file_size, std::fs::metadata(&original).unwrap().len());
+
+        let small = image::open(dir.path().join("abc.thumb64.png")).unwrap();
+        assert_eq!(small.dimensions(), (64, 32), "Aspect ratio is kept");
+        let large = image::open(thumbnail_path(&original, 256)).unwrap();
+        assert_eq!(large.dimensions(), (256, 128));
+    }
+
+    #[test]
+    fn test_small_images_are_not_upscaled() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("icon.png");
+        write_png(&original, 100, 20);
+
+        generate(&original).unwrap();
+        assert_eq!(image::open(thumbnail_path(&original, 64)).unwrap().dimensions(), (64, 13));
+        assert_eq!(image::open(thumbnail_path(&original, 256)).unwrap().dimensions(), (100, 20));
+    }
+
+    #[test]
+    fn test_other_formats_are_decoded() {
+        let dir = TempDir::new().unwrap();
+        let image = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(40, 30, image::Rgb([200u8, 40, 40])));
+        for (name, format) in [("photo.jpg", ImageFormat::Jpeg), ("anim.gif", ImageFormat::Gif), ("icon.bmp", ImageFormat::Bmp)] {
+            let original = dir.path().join(name);
+            image.save_with_format(&original, format).unwrap();
+            let (info, _) = generate(&original).unwrap();
+            assert_eq!((info.width, info.height), (40, 30), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_images_fail() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("broken.png");
+        std::fs::write(&original, b"\x89PNG not really").unwrap();
+        assert!(generate(&original).is_err());
+        assert!(generate(&dir.path().join("missing.png")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_images_are_not_retried() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+
+        let original = dir.path().join("broken.png");
+        std::fs::write(&original, b"\x89PNG not really")?;
+        let image = NewClipboardEntry {
+            content_type: "image".to_string(),
+            text_content: None,
+            file_path: Some(original.to_string_lossy().into_owned()),
+            metadata: None,
+            source_app: None,
+        };
+        let id = database::insert_entry(pool, image).await?;
+
+        assert_eq!(process_pending(pool).await?, 0);
+        let entry = database::get_entry(pool, id).await?.unwrap();
+        assert!(has_failed(&entry));
+        assert!(database::get_images_without_dimensions(pool).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_images_are_retried() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+
+        // Not written yet, e.g. the blob store is on a drive that isn't mounted
+        let original = dir.path().join("later.png");
+        let image = NewClipboardEntry {
+            content_type: "image".to_string(),
+            text_content: None,
+            file_path: Some(original.to_string_lossy().into_owned()),
+            metadata: None,
+            source_app: None,
+        };
+        let id = database::insert_entry(pool, image).await?;
+
+        assert_eq!(process_pending(pool).await?, 0);
+        assert!(!has_failed(&database::get_entry(pool, id).await?.unwrap()));
+
+        write_png(&original, 30, 20);
+        assert_eq!(process_pending(pool).await?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumbnail_size_for() {
+        assert_eq!(thumbnail_size_for(32), 64);
+        assert_eq!(thumbnail_size_for(64), 64);
+        assert_eq!(thumbnail_size_for(100), 256);
+        assert_eq!(thumbnail_size_for(4000), 256);
+    }
+
+    #[tokio::test]
+    async fn test_process_pending_records_metadata() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+
+        let original = dir.path().join("shot.png");
+        write_png(&original, 300, 200);
+        let image = NewClipboardEntry {
+            content_type: "image".to_string(),
+            text_content: None,
+            file_path: Some(original.to_string_lossy().into_owned()),
+            metadata: Some(r#"{"tags":["work"]}"#.to_string()),
+            source_app: None,
+        };
+        let id = database::insert_entry(pool, image).await?;
+
+        assert_eq!(process_pending(pool).await?, 1);
+        let metadata: serde_json::Value = serde_json::from_str(&database::get_entry(pool, id).await?.unwrap().metadata.unwrap())?;
+        assert_eq!(metadata["width"], 300);
+        assert_eq!(metadata["height"], 200);
+        assert_eq!(metadata["format"], "png");
+        assert_eq!(metadata["tags"][0], "work", "Existing metadata is kept");
+        assert!(thumbnail_path(&original, 64).exists());
+
+        // Processed entries aren't picked up again
+        assert_eq!(process_pending(pool).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_qr_codes_become_child_entries() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+
+        let original = dir.path().join("ticket.png");
+        std::fs::write(&original, qr::render_png("https://example.com/boarding-pass", 200)?)?;
+        let image = NewClipboardEntry {
+            content_type: "image".to_string(),
+            text_content: None,
+            file_path: Some(original.to_string_lossy().into_owned()),
+            metadata: None,
+            source_app: Some("Screenshot".to_string()),
+        };
+        let id = database::insert_entry(pool, image).await?;
+
+        process_pending(pool).await?;
+        let params = ClipboardSearchParams { query: Some("boarding".to_string()), ..Default::default() };
+        let children = database::search_entries(pool, params).await?;
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].parent_id, Some(id));
+        assert_eq!(children[0].text_content.as_deref(), Some("https://example.com/boarding-pass"));
+        assert_eq!(children[0].source_app.as_deref(), Some("Screenshot"));
+        Ok(())
+    }
+}
@@ -1,10 +1,25 @@
-use crate::database::{self, ClipboardEntry, ClipboardSearchParams};
+use crate::actions::{self, ActionInfo, ActionOutput};
+use crate::calculator;
+use crate::clipboard::ocr::{self, OcrStatus};
+use crate::clipboard::thumbnail;
+use crate::clipboard::highlight::{self, Highlighted};
+use crate::clipboard::language::Language;
+use crate::clipboard::ContentKind;
+use crate::database::backup::{self, BackupInfo};
+use crate::database::offload;
+use crate::database::recovery::{self, OpenFailure, OpenFailureKind, RecoveryAction};
+use crate::database::{self, ClipboardEntry, Database, ClipboardSearchParams, ExportFilter, PasteProfile};
 use crate::paste;
+use crate::qr;
+use crate::settings::{self, Settings};
+use crate::shortcuts::{self, ShortcutAction, ShortcutStatus};
+use crate::sync::{self, SyncReport};
+use crate::transfer::{self, ExportFormat, ExportSummary, ForeignSource, ImportReport};
 use crate::window;
 use anyhow::Result;
 use sqlx::SqlitePool;
 use std::sync::Arc;
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 pub struct AppState {
     pub pool: Arc<SqlitePool>,
@@ -15,18 +30,57 @@ pub async fn get_clipboard_history(
     search_query: Option<String>,
     limit: Option<i64>,
     favorites_only: Option<bool>,
+    kind: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<Vec<ClipboardEntry>, String> {
+    let kind = kind
+        .map(|name| ContentKind::parse(&name).ok_or_else(|| format!("Unknown content kind: {}", name)))
+        .transpose()?;
+
+    let favorites_only = favorites_only.unwrap_or(false);
+    // "42*1.19" or "3 km to mi" shows its result ahead of the history
+    let calculation = match (&search_query, &kind, favorites_only) {
+        (Some(query), None, false) => calculator::evaluate(query).map(calculation_entry),
+        _ => None,
+    };
+
     let params = ClipboardSearchParams {
         query: search_query,
-        favorites_only: favorites_only.unwrap_or(false),
-        limit: limit.unwrap_or(100),
+        kind: kind.map(|kind| kind.as_str().to_string()),
+        favorites_only,
+        limit: limit.unwrap_or_else(|| settings::current().history_limit),
         ..Default::default()
     };
 
-    database::search_entries(&state.pool, params)
+    let mut entries = database::search_entries(&state.pool, params)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    if let Some(calculation) = calculation {
+        entries.insert(0, calculation);
+    }
+    Ok(entries)
+}
+
+/// Synthetic search result for an evaluated query. It is never stored, so
+/// it has id 0 and is pasted through `paste_and_close` like any entry.
+fn calculation_entry(evaluation: calculator::Evaluation) -> ClipboardEntry {
+    ClipboardEntry {
+        id: 0,
+        content_type: "calculation".to_string(),
+        text_content: Some(evaluation.result),
+        file_path: None,
+        metadata: Some(serde_json::json!({ "expression": evaluation.expression }).to_string()),
+        created_at: chrono::Utc::now().timestamp(),
+        favorite: false,
+        source_app: None,
+        kind: None,
+        kind_confidence: None,
+        language: None,
+        parent_id: None,
+        ocr_text: None,
+        content_size: None,
+        truncated: false,
+    }
 }
 
 #[tauri::command]
@@ -39,6 +93,147 @@ pub async fn toggle_favorite(
         .map_err(|e| e.to_string())
 }
 
+/// Replace an entry's tags
+#[tauri::command]
+pub async fn set_entry_tags(
+    id: i64,
+    tags: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    database::set_tags(&state.pool, id, &tags)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// An entry's text split into syntax-highlighted spans for the preview
+#[tauri::command]
+pub async fn get_highlighted(
+    id: i64,
+    state: State<'_, AppState>,
+) -> Result<Highlighted, String> {
+    let entry = database::get_entry(&state.pool, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Entry not found".to_string())?;
+
+    let text = offload::load_text(&entry).await.map_err(|e| e.to_string())?.unwrap_or_default();
+    let language = entry.language.as_deref().and_then(Language::parse);
+    Ok(Highlighted { language, spans: highlight::highlight(&text, language) })
+}
+
+/// Entries on the paste stack, next to be pasted first. Ids of entries that
+/// were deleted in the meantime are dropped.
+#[tauri::command]
+pub async fn get_paste_stack(state: State<'_, AppState>) -> Result<Vec<ClipboardEntry>, String> {
+    let mut entries = Vec::new();
+    for id in paste::stack::ids() {
+        match database::get_entry(&state.pool, id).await.map_err(|e| e.to_string())? {
+            Some(entry) => entries.push(entry),
+            None => {
+                paste::stack::remove(id);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Queue an entry on the paste stack, returning the stack's ids
+#[tauri::command]
+pub fn add_to_paste_stack(id: i64) -> Vec<i64> {
+    paste::stack::push(id)
+}
+
+/// Take an entry off the paste stack, e.g. right before pasting it
+#[tauri::command]
+pub fn remove_from_paste_stack(id: i64) -> Vec<i64> {
+    paste::stack::remove(id)
+}
+
+#[tauri::command]
+pub fn clear_paste_stack() {
+    paste::stack::clear()
+}
+
+/// Complete text of an entry. Lists only carry a preview of long texts
+/// (`truncated` is set), so pasting or viewing one loads it from here.
+#[tauri::command]
+pub async fn get_entry_content(
+    id: i64,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    database::get_entry_content(&state.pool, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Entry has no text".to_string())
+}
+
+/// PNG of a text entry as a QR code, for scanning it with a phone
+#[tauri::command]
+pub async fn render_qr(
+    id: i64,
+    state: State<'_, AppState>,
+) -> Result<tauri::ipc::Response, String> {
+    let entry = database::get_entry(&state.pool, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Entry not found".to_string())?;
+    let text = offload::load_text(&entry)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Only text entries can be shown as a QR code".to_string())?;
+
+    let png = qr::render_png(&text, 320).map_err(|e| e.to_string())?;
+    Ok(tauri::ipc::Response::new(png))
+}
+
+/// Text and detected kind of an entry, for the quick actions
+async fn action_target(pool: &SqlitePool, id: i64) -> Result<Option<(ContentKind, String)>, String> {
+    let entry = database::get_entry(pool, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Entry not found".to_string())?;
+
+    // Offloaded texts only carry a preview; actions work on the whole text
+    let kind = entry.kind.as_deref().and_then(ContentKind::parse);
+    let text = offload::load_text(&entry).await.map_err(|e| e.to_string())?;
+    Ok(kind.zip(text))
+}
+
+/// Quick actions for an entry's kind: open a URL, convert a color, ...
+#[tauri::command]
+pub async fn list_actions(
+    id: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<ActionInfo>, String> {
+    Ok(match action_target(&state.pool, id).await? {
+        Some((kind, text)) => actions::actions_for(kind, &text),
+        None => Vec::new(),
+    })
+}
+
+/// Run a quick action. Converted text is returned for the picker to show or
+/// paste; URLs and files are opened right away.
+#[tauri::command]
+pub async fn run_action(
+    id: i64,
+    action: String,
+    state: State<'_, AppState>,
+) -> Result<ActionOutput, String> {
+    let (kind, text) = action_target(&state.pool, id)
+        .await?
+        .ok_or_else(|| "This entry has no quick actions".to_string())?;
+
+    let output = actions::run(&action, kind, &text).map_err(|e| e.to_string())?;
+    match &output {
+        ActionOutput::Text { .. } => {}
+        ActionOutput::OpenUrl { url } => tauri_plugin_opener::open_url(url, None::<&str>).map_err(|e| e.to_string())?,
+        ActionOutput::RevealPath { path } => tauri_plugin_opener::reveal_item_in_dir(path).map_err(|e| e.to_string())?,
+        ActionOutput::OpenPath { path } => tauri_plugin_opener::open_path(path, None::<&str>).map_err(|e| e.to_string())?,
+    }
+    println!("🔧 [DEBUG] Ran action {} on entry {}", action, id);
+    Ok(output)
+}
+
 #[tauri::command]
 pub async fn delete_clipboard_entry(
     id: i64,
@@ -52,8 +247,11 @@ pub async fn delete_clipboard_entry(
 #[tauri::command]
 pub async fn paste_and_close(
     content: String,
+    restore_clipboard: Option<bool>,
+    strategy: Option<paste::PasteStrategy>,
+    type_rate: Option<u32>,
     app: AppHandle,
-    _state: State<'_, AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
     use std::time::Instant;
 
@@ -71,25 +269,358 @@ pub async fn paste_and_close(
     }
     println!("🔧 [PERF] T+{}ms: Window hidden", start.elapsed().as_millis());
 
-    // Immediately paste - don't wait for natural focus switch
-    paste::simulate_paste_with_app_switch(content, target_app, start)
+    // Start from the target app's profile; explicit arguments win over it
+    let user_profiles = database::get_paste_profiles(&state.pool)
         .await
         .map_err(|e| e.to_string())?;
+    let mut options = match paste::profiles::find_profile(target_app.as_deref(), &user_profiles) {
+        Some(profile) => paste::profiles::options_for_profile(&profile).map_err(|e| e.to_string())?,
+        None => settings::current().paste_options(),
+    };
+
+    if let Some(restore_clipboard) = restore_clipboard {
+        options.restore_clipboard = restore_clipboard;
+    }
+    if let Some(strategy) = strategy {
+        options.strategy = strategy;
+    }
+    if let Some(type_rate) = type_rate {
+        options.type_rate = type_rate;
+    }
+
+    // Immediately paste - don't wait for natural focus switch
+    let outcome = paste::simulate_paste_with_app_switch(content, target_app, &options, start)
+        .await
+        .map_err(|e| {
+            paste::diagnostics::record_paste_error(&e.to_string());
+            e.to_string()
+        })?;
+
+    paste::diagnostics::record_paste_latency(start.elapsed().as_millis() as u64);
+
+    if outcome == paste::PasteOutcome::CopiedOnly {
+        let message = "Auto-paste isn't available in this session. The entry was copied - paste it with Ctrl+V.";
+        let _ = app.emit("paste-copied-only", message);
+
+        #[cfg(target_os = "linux")]
+        paste::wayland::notify_copy_only(message);
+    }
 
     println!("✅ [PERF] T+{}ms: ========== PASTE COMPLETE ==========\n", start.elapsed().as_millis());
     Ok(())
 }
 
+/// All paste profiles in effect: built-in defaults plus user overrides
+#[tauri::command]
+pub async fn get_paste_profiles(
+    state: State<'_, AppState>,
+) -> Result<Vec<PasteProfile>, String> {
+    let user_profiles = database::get_paste_profiles(&state.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(paste::profiles::merged_profiles(&user_profiles))
+}
+
+#[tauri::command]
+pub async fn save_paste_profile(
+    profile: PasteProfile,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    paste::profiles::validate_profile(&profile).map_err(|e| e.to_string())?;
+
+    database::upsert_paste_profile(&state.pool, &profile)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a user profile; built-in defaults for the app apply again afterwards
+#[tauri::command]
+pub async fn delete_paste_profile(
+    app_name: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    database::delete_paste_profile(&state.pool, &app_name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Delete non-favorite entries older than `days`, or the configured retention
 #[tauri::command]
 pub async fn cleanup_old_entries(
-    days: i64,
+    days: Option<i64>,
     state: State<'_, AppState>,
 ) -> Result<u64, String> {
+    let days = days.unwrap_or_else(|| settings::current().retention_days);
+    if days <= 0 {
+        return Ok(0);
+    }
+
     database::delete_old_entries(&state.pool, days)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Export history (all of it, or what matches `filter`) to `path`
+#[tauri::command]
+pub async fn export_history(
+    path: String,
+    format: Option<ExportFormat>,
+    filter: Option<ExportFilter>,
+    state: State<'_, AppState>,
+) -> Result<ExportSummary, String> {
+    transfer::export_history(
+        &state.pool,
+        std::path::Path::new(&path),
+        format.unwrap_or_default(),
+        &filter.unwrap_or_default(),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Merge an export into the history. With `dry_run` only the report is produced.
+#[tauri::command]
+pub async fn import_history(
+    path: String,
+    dry_run: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, String> {
+    let blob_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("blobs");
+
+    let report = transfer::import_history(&state.pool, std::path::Path::new(&path), &blob_dir, dry_run.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !report.dry_run && report.imported > 0 {
+        thumbnail::spawn_pending(Arc::clone(&state.pool));
+    }
+    if !report.dry_run && (report.imported > 0 || report.favorites_updated > 0) {
+        let _ = app.emit("history-imported", &report);
+    }
+    Ok(report)
+}
+
+/// Merge another clipboard manager's history. Without `path` the manager's
+/// usual location is read.
+#[tauri::command]
+pub async fn import_from_manager(
+    source: ForeignSource,
+    path: Option<String>,
+    dry_run: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, String> {
+    let blob_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("blobs");
+
+    let path = path.map(std::path::PathBuf::from);
+    let report = transfer::import_from_manager(&state.pool, source, path.as_deref(), &blob_dir, dry_run.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !report.dry_run && report.imported > 0 {
+        thumbnail::spawn_pending(Arc::clone(&state.pool));
+    }
+    if !report.dry_run && (report.imported > 0 || report.favorites_updated > 0) {
+        let _ = app.emit("history-imported", &report);
+    }
+    Ok(report)
+}
+
+/// Whether image text is recognized, and with which Tesseract
+#[tauri::command]
+pub fn get_ocr_status() -> OcrStatus {
+    ocr::status()
+}
+
+/// Current settings, without the sync passphrase
+#[tauri::command]
+pub fn get_settings() -> Settings {
+    settings::current().without_secrets()
+}
+
+/// Validate, store and apply new settings. Running parts of the app (monitor,
+/// shortcuts, paste) pick them up right away; the frontend gets `settings-changed`.
+/// An empty sync passphrase keeps the stored one, since the frontend never sees it.
+#[tauri::command]
+pub async fn update_settings(
+    mut new_settings: Settings,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Settings, String> {
+    let old_settings = settings::current();
+    if new_settings.sync.passphrase.is_empty() {
+        new_settings.sync.passphrase = old_settings.sync.passphrase.clone();
+    }
+    new_settings.validate().map_err(|e| e.to_string())?;
+
+    settings::save(&state.pool, &new_settings)
+        .await
+        .map_err(|e| e.to_string())?;
+    settings::publish(new_settings.clone());
+
+    if new_settings.shortcuts != old_settings.shortcuts {
+        // Registration failures are reported per action through get_shortcuts
+        shortcuts::register_all(&app, &new_settings.shortcuts);
+        let _ = app.emit("shortcuts-changed", shortcuts::statuses());
+    }
+
+    if new_settings.sync != old_settings.sync {
+        sync::configure(&state.pool, &new_settings.sync)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    if new_settings.ocr_enabled && (!old_settings.ocr_enabled || new_settings.ocr_languages != old_settings.ocr_languages) {
+        thumbnail::spawn_pending(Arc::clone(&state.pool));
+    }
+
+    if new_settings.retention_days > 0 && new_settings.retention_days != old_settings.retention_days {
+        if let Err(e) = database::delete_old_entries(&state.pool, new_settings.retention_days).await {
+            eprintln!("❌ [ERROR] Retention cleanup failed: {}", e);
+        }
+    }
+
+    let public = new_settings.without_secrets();
+    let _ = app.emit("settings-changed", &public);
+    Ok(public)
+}
+
+/// Exchange changes with the other devices now instead of waiting for the
+/// next scheduled sync
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, state: State<'_, AppState>) -> Result<SyncReport, String> {
+    if recovery::is_memory_only() {
+        return Err("Sync is unavailable while history is kept in memory".to_string());
+    }
+
+    let report = sync::sync_now(&state.pool, &settings::current().sync)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if report.applied > 0 {
+        let _ = app.emit("history-synced", &report);
+    }
+    Ok(report)
+}
+
+fn backup_dir(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join("backups"))
+}
+
+fn db_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join("clipboard.db"))
+}
+
+/// Database backups, newest first
+#[tauri::command]
+pub fn list_backups(app: AppHandle) -> Result<Vec<BackupInfo>, String> {
+    backup::list_backups(&backup_dir(&app)?).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_backup(app: AppHandle, state: State<'_, AppState>) -> Result<BackupInfo, String> {
+    if recovery::is_memory_only() {
+        return Err("History is in memory only this session, there is nothing to back up".to_string());
+    }
+    backup::create_backup(&state.pool, &backup_dir(&app)?, settings::current().backup_keep)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Replace the history with a backup. The current database is backed up
+/// first, then the app restarts to swap the files.
+#[tauri::command]
+pub async fn restore_backup(
+    file_name: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let backup_dir = backup_dir(&app)?;
+    let backup_path = backup::find_backup(&backup_dir, &file_name).map_err(|e| e.to_string())?;
+    backup::stage_restore(&db_path(&app)?, &backup_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Staged before this backup runs, so rotation can't delete the chosen file first
+    if !recovery::is_memory_only() {
+        backup::create_backup(&state.pool, &backup_dir, settings::current().backup_keep)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    println!("🔧 [DEBUG] Restoring {}, restarting", file_name);
+    app.request_restart();
+    Ok(())
+}
+
+/// Why the database failed to open, if this session runs without it
+#[tauri::command]
+pub fn get_startup_failure() -> Option<OpenFailure> {
+    recovery::startup_failure()
+}
+
+/// Act on the choice made in the recovery window. Everything except
+/// `memory_only` restarts the app to open the database again.
+#[tauri::command]
+pub async fn recover_database(
+    action: RecoveryAction,
+    backup_name: Option<String>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let db_path = db_path(&app)?;
+
+    match action {
+        RecoveryAction::Retry => {
+            // The same check as at startup, or the restart would land back here
+            let problems = backup::startup_check(&db_path).await;
+            if !problems.is_empty() {
+                let message = problems.join("; ");
+                recovery::set_startup_failure(OpenFailure { kind: OpenFailureKind::Corrupt, message: message.clone(), db_path });
+                return Err(message);
+            }
+            let db = Database::new(db_path.clone()).await.map_err(|e| {
+                let failure = OpenFailure { kind: recovery::classify(&e), message: format!("{:#}", e), db_path };
+                recovery::set_startup_failure(failure);
+                format!("{:#}", e)
+            })?;
+            db.pool().close().await;
+        }
+        RecoveryAction::RestoreBackup => {
+            let backup_name = backup_name.ok_or("Pick a backup to restore")?;
+            let backup_path = backup::find_backup(&backup_dir(&app)?, &backup_name).map_err(|e| e.to_string())?;
+            backup::stage_restore(&db_path, &backup_path)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        RecoveryAction::StartFresh => {
+            if db_path.exists() {
+                let moved = backup::move_aside(&db_path, "broken").map_err(|e| e.to_string())?;
+                println!("🔧 [DEBUG] Moved unusable database to {}", moved.display());
+            }
+        }
+        RecoveryAction::MemoryOnly => {
+            println!("⚠️  [WARNING] Running without saving history this session");
+            if let Some(window) = app.get_webview_window("recovery") {
+                let _ = window.close();
+            }
+            return window::show_window(&app).map_err(|e| e.to_string());
+        }
+    }
+
+    app.request_restart();
+    Ok(())
+}
+
 #[tauri::command]
 pub fn hide_window_command(app: AppHandle) -> Result<(), String> {
     window::hide_window(&app).map_err(|e| e.to_string())
@@ -109,6 +640,7 @@ pub async fn copy_to_clipboard_only(
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
     // Only copy to clipboard, don't simulate paste
+    crate::clipboard::ignore_self_write(&content);
     let mut clipboard = arboard::Clipboard::new()
         .map_err(|e| format!("Failed to access clipboard: {}", e))?;
     clipboard.set_text(content)
@@ -117,12 +649,62 @@ pub async fn copy_to_clipboard_only(
     Ok(())
 }
 
+/// Every bindable action with its shortcut, registration state and warnings
+#[tauri::command]
+pub fn get_shortcuts() -> Vec<ShortcutStatus> {
+    shortcuts::statuses()
+}
+
+/// Bind `action` to `accelerator` (e.g. "Ctrl+Shift+V"), or unbind it with `None`.
+/// Fails without changing anything on invalid combos, conflicts with another
+/// action, or when the OS refuses the registration.
+#[tauri::command]
+pub async fn set_shortcut(
+    action: ShortcutAction,
+    accelerator: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<ShortcutStatus>, String> {
+    let bindings = shortcuts::rebind(&app, action, accelerator.as_deref()).map_err(|e| e.to_string())?;
+
+    let mut new_settings = settings::current();
+    new_settings.shortcuts = bindings;
+    settings::save(&state.pool, &new_settings)
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("settings-changed", &new_settings.without_secrets());
+    settings::publish(new_settings);
+
+    Ok(shortcuts::statuses())
+}
+
+/// Pause or resume saving new copies to history
+#[tauri::command]
+pub fn set_recording_paused(paused: bool, app: AppHandle) -> bool {
+    crate::clipboard::set_recording_paused(paused);
+    let _ = app.emit("recording-paused", paused);
+    paused
+}
+
+#[tauri::command]
+pub fn is_recording_paused() -> bool {
+    crate::clipboard::is_recording_paused()
+}
+
 /// Check if we have accessibility permissions for auto-paste
 #[tauri::command]
 pub fn check_accessibility_permission() -> bool {
     paste::check_accessibility_permission()
 }
 
+/// Detailed report of what auto-paste can and can't do in this session
+#[tauri::command]
+pub async fn diagnose_paste() -> Result<paste::diagnostics::PasteDiagnostics, String> {
+    tokio::task::spawn_blocking(paste::diagnostics::diagnose)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Open system preferences to grant accessibility permissions
 #[tauri::command]
 pub fn open_accessibility_settings() -> Result<(), String> {
@@ -0,0 +1,369 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Row, SqlitePool};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::recovery::{self, OpenFailureKind};
+
+const BACKUP_PREFIX: &str = "clipboard-";
+const BACKUP_EXTENSION: &str = ".db";
+// Written by `stage_restore`, swapped in at the next start
+const PENDING_RESTORE_SUFFIX: &str = ".restore";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: PathBuf,
+    /// Unix timestamp (seconds) of the file's last modification
+    pub created_at: i64,
+    pub size: u64,
+}
+
+/// Write a consistent copy of the live database with `VACUUM INTO`, then
+/// delete all but the newest `keep` backups
+pub async fn create_backup(pool: &SqlitePool, backup_dir: &Path, keep: usize) -> Result<BackupInfo> {
+    std::fs::create_dir_all(backup_dir)?;
+
+    let file_name = format!(
+        "{}{}{}",
+        BACKUP_PREFIX,
+        chrono::Utc::now().format("%Y%m%d-%H%M%S-%3f"),
+        BACKUP_EXTENSION
+    );
+    let path = backup_dir.join(&file_name);
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().into_owned())
+        .execute(pool)
+        .await?;
+    remove_secrets(&path).await?;
+
+    rotate(backup_dir, keep)?;
+    backup_info(&path)
+}
+
+/// Blank the sync passphrase in a backup copy. After restoring one, sync
+/// stops until the passphrase is entered again.
+async fn remove_secrets(path: &Path) -> Result<()> {
+    let options = SqliteConnectOptions::new().filename(path);
+    let pool = SqlitePoolOptions::new().max_connections(1).connect_with(options).await?;
+    // Overwrite the old value on disk instead of just freeing its space
+    sqlx::query("PRAGMA secure_delete = ON").execute(&pool).await?;
+    let result = sqlx::query("UPDATE settings SET value = json_set(value, '$.passphrase', '') WHERE key = 'sync'")
+        .execute(&pool)
+        .await;
+    pool.close().await;
+    result?;
+    Ok(())
+}
+
+/// Backups in `backup_dir`, newest first
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<BackupInfo>> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for file in std::fs::read_dir(backup_dir)? {
+        let path = file?.path();
+        if is_backup_name(&path) {
+            backups.push(backup_info(&path)?);
+        }
+    }
+
+    // Names embed the UTC time, so they sort chronologically
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
+
+/// Delete the oldest backups so at most `keep` remain. Returns how many were deleted.
+pub fn rotate(backup_dir: &Path, keep: usize) -> Result<usize> {
+    let backups = list_backups(backup_dir)?;
+    let mut deleted = 0;
+
+    for backup in backups.iter().skip(keep.max(1)) {
+        std::fs::remove_file(&backup.path)?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// True when there is no backup yet or the newest is older than `interval`
+pub fn backup_due(backup_dir: &Path, interval: Duration) -> bool {
+    let newest = list_backups(backup_dir).ok().and_then(|backups| backups.into_iter().next());
+    match newest {
+        Some(backup) => {
+            let age = chrono::Utc::now().timestamp() - backup.created_at;
+            age < 0 || age as u64 >= interval.as_secs()
+        }
+        None => true,
+    }
+}
+
+/// Problems reported by `PRAGMA integrity_check`; empty when the database is fine
+pub async fn integrity_check(pool: &SqlitePool) -> Result<Vec<String>> {
+    run_check(pool, "PRAGMA integrity_check").await
+}
+
+/// Like `integrity_check`, but indexes aren't compared with their tables, so
+/// it stays fast on a large history
+pub async fn quick_check(pool: &SqlitePool) -> Result<Vec<String>> {
+    run_check(pool, "PRAGMA quick_check").await
+}
+
+async fn run_check(pool: &SqlitePool, pragma: &str) -> Result<Vec<String>> {
+    let rows = sqlx::query(pragma).fetch_all(pool).await?;
+
+    let messages: Vec<String> = rows.iter().filter_map(|row| row.try_get::<String, _>(0).ok()).collect();
+    if messages.len() == 1 && messages[0] == "ok" {
+        return Ok(Vec::new());
+    }
+    Ok(messages)
+}
+
+/// Full integrity check of a database file that isn't open
+pub async fn check_file(path: &Path) -> Result<Vec<String>> {
+    let pool = open_for_check(path).await?;
+    let problems = integrity_check(&pool).await;
+    pool.close().await;
+    problems
+}
+
+/// The file is opened read-write so SQLite can take in and then remove a
+/// leftover WAL; a read-only connection would leave `-wal`/`-shm` files behind.
+async fn open_for_check(path: &Path) -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::new().filename(path);
+    Ok(SqlitePoolOptions::new().max_connections(1).connect_with(options).await?)
+}
+
+/// Check `backup_path` and put it next to the database, to replace it the next
+/// time the app starts (the live database can't be swapped under open connections)
+pub async fn stage_restore(db_path: &Path, backup_path: &Path) -> Result<()> {
+    let problems = check_file(backup_path).await?;
+    if !problems.is_empty() {
+        bail!("{} is damaged: {}", backup_path.display(), problems.join("; "));
+    }
+
+    std::fs::copy(backup_path, pending_restore_path(db_path))?;
+    Ok(())
+}
+
+/// Swap in a backup staged by `stage_restore`. The replaced database is kept
+/// as `<db>.before-restore`. Must run before the database is opened.
+pub fn apply_pending_restore(db_path: &Path) -> Result<bool> {
+    let pending = pending_restore_path(db_path);
+    if !pending.exists() {
+        return Ok(false);
+    }
+
+    if db_path.exists() {
+        std::fs::rename(db_path, sibling(db_path, ".before-restore"))?;
+    }
+    remove_sidecars(db_path)?;
+    std::fs::rename(&pending, db_path)?;
+    Ok(true)
+}
+
+/// Quick check of the database file before it's opened at startup. Returns
+/// what's wrong with it, empty when it's fine or doesn't exist yet. Nothing is
+/// replaced here: a damaged file is left for the user to deal with in the
+/// recovery window.
+pub async fn startup_check(db_path: &Path) -> Vec<String> {
+    if !db_path.exists() {
+        return Vec::new();
+    }
+
+    let problems = match open_for_check(db_path).await {
+        Ok(pool) => {
+            let problems = quick_check(&pool).await;
+            pool.close().await;
+            problems
+        }
+        Err(e) => Err(e),
+    };
+    match problems {
+        Ok(problems) => problems,
+        Err(e) if recovery::classify(&e) == OpenFailureKind::Corrupt => vec![e.to_string()],
+        // Locked or unreadable: a backup won't help, opening it reports the reason
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Rename the database to `<db>.<label>-<time>` and drop its journal files,
+/// leaving the path free for a new database
+pub fn move_aside(db_path: &Path, label: &str) -> Result<PathBuf> {
+    let target = sibling(db_path, &format!(".{}-{}", label, chrono::Utc::now().format("%Y%m%d-%H%M%S")));
+    std::fs::rename(db_path, &target)?;
+    remove_sidecars(db_path)?;
+    Ok(target)
+}
+
+/// Resolve a name from `list_backups`, refusing anything outside `backup_dir`
+pub fn find_backup(backup_dir: &Path, file_name: &str) -> Result<PathBuf> {
+    let path = backup_dir.join(file_name);
+    if file_name.contains(['/', '\\']) || !is_backup_name(&path) || !path.is_file() {
+        bail!("No backup named {}", file_name);
+    }
+    Ok(path)
+}
+
+fn is_backup_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_EXTENSION))
+}
+
+fn backup_info(path: &Path) -> Result<BackupInfo> {
+    let metadata = std::fs::metadata(path)?;
+    let created_at = metadata
+        .modified()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|age| age.as_secs() as i64)
+        .unwrap_or(0);
+
+    Ok(BackupInfo {
+        file_name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        path: path.to_path_buf(),
+        created_at,
+        size: metadata.len(),
+    })
+}
+
+fn pending_restore_path(db_path: &Path) -> PathBuf {
+    sibling(db_path, PENDING_RESTORE_SUFFIX)
+}
+
+/// `db_path` with `suffix` appended to the file name
+fn sibling(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    db_path.with_file_name(name)
+}
+
+/// Journal files belong to the database they were written for; left next to
+/// a swapped-in file SQLite would try to apply them to it
+fn remove_sidecars(db_path: &Path) -> Result<()> {
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let path = sibling(db_path, suffix);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{self, Database, NewClipboardEntry};
+    use tempfile::tempdir;
+
+    async fn texts(pool: &SqlitePool) -> Result<Vec<String>> {
+        Ok(database::get_recent_entries(pool, 100)
+            .await?
+            .into_iter()
+            .filter_map(|entry| entry.text_content)
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_rotation() -> Result<()> {
+        let dir = tempdir()?;
+        let db = Database::new(dir.path().join("clipboard.db")).await?;
+        let backup_dir = dir.path().join("backups");
+
+        for _ in 0..4 {
+            create_backup(db.pool(), &backup_dir, 3).await?;
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let backups = list_backups(&backup_dir)?;
+        assert_eq!(backups.len(), 3);
+        assert!(backups[0].file_name > backups[1].file_name, "Newest first");
+        assert!(check_file(&backups[0].path).await?.is_empty());
+        assert!(!backup_due(&backup_dir, Duration::from_secs(3600)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_backups_leave_out_the_sync_passphrase() -> Result<()> {
+        let dir = tempdir()?;
+        let db = Database::new(dir.path().join("clipboard.db")).await?;
+        let mut settings = crate::settings::Settings::default();
+        settings.sync.directory = "/srv/sync".to_string();
+        settings.sync.passphrase = "correct horse battery".to_string();
+        crate::settings::save(db.pool(), &settings).await?;
+
+        let backup = create_backup(db.pool(), &dir.path().join("backups"), 3).await?;
+        let data = std::fs::read(&backup.path)?;
+        assert!(!data.windows(7).any(|window| window == b"battery"));
+
+        let restored = Database::new(backup.path).await?;
+        let loaded = crate::settings::load(restored.pool()).await?;
+        assert_eq!(loaded.sync.directory, "/srv/sync");
+        assert_eq!(loaded.sync.passphrase, "");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_staged_restore_replaces_database() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("clipboard.db");
+        let backup_dir = dir.path().join("backups");
+
+        let db = Database::new(db_path.clone()).await?;
+        database::insert_entry(db.pool(), NewClipboardEntry::new_text("kept".to_string())).await?;
+        let backup = create_backup(db.pool(), &backup_dir, 5).await?;
+        database::insert_entry(db.pool(), NewClipboardEntry::new_text("after backup".to_string())).await?;
+
+        stage_restore(&db_path, &backup.path).await?;
+        db.pool().close().await;
+
+        assert!(apply_pending_restore(&db_path)?);
+        assert!(!apply_pending_restore(&db_path)?, "Applied only once");
+
+        let db = Database::new(db_path.clone()).await?;
+        assert_eq!(texts(db.pool()).await?, vec!["kept"]);
+        assert!(sibling(&db_path, ".before-restore").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_startup_check_leaves_a_damaged_database_alone() -> Result<()> {
+        let dir = tempdir()?;
+        let db_path = dir.path().join("clipboard.db");
+        let backup_dir = dir.path().join("backups");
+        assert!(startup_check(&db_path).await.is_empty(), "No database yet");
+
+        let db = Database::new(db_path.clone()).await?;
+        database::insert_entry(db.pool(), NewClipboardEntry::new_text("precious".to_string())).await?;
+        let backup = create_backup(db.pool(), &backup_dir, 5).await?;
+        db.pool().close().await;
+        assert!(startup_check(&db_path).await.is_empty(), "Healthy database");
+
+        let garbage = b"definitely not a database, just garbage bytes";
+        std::fs::write(&db_path, garbage)?;
+        assert!(!startup_check(&db_path).await.is_empty());
+        assert_eq!(std::fs::read(&db_path)?, garbage, "Replacing it is up to the user");
+
+        // What the recovery window does when a backup is picked
+        stage_restore(&db_path, &backup.path).await?;
+        assert!(apply_pending_restore(&db_path)?);
+        assert!(startup_check(&db_path).await.is_empty());
+        let db = Database::new(db_path).await?;
+        assert_eq!(texts(db.pool()).await?, vec!["precious"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_backup_rejects_other_files() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("clipboard-20260101-000000-000.db"), b"").unwrap();
+
+        assert!(find_backup(dir.path(), "clipboard-20260101-000000-000.db").is_ok());
+        assert!(find_backup(dir.path(), "../clipboard.db").is_err());
+        assert!(find_backup(dir.path(), "notes.txt").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+// Throughput benchmarks for the history database. Ignored by default; run with
+//   cargo test --release --lib benchmarks -- --ignored --nocapture
+
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+use super::options::{JournalMode, Synchronous};
+use super::{queries, ClipboardSearchParams, Database, DatabaseOptions, NewClipboardEntry};
+
+const LARGE_HISTORY: usize = 100_000;
+
+/// Fill a database with `count` entries of varied text in one transaction
+async fn seed(db: &Database, count: usize) -> Result<()> {
+    let words = ["cargo", "build", "release", "https://example.org", "password", "TODO", "fn main()", "SELECT *"];
+    let now = chrono::Utc::now().timestamp();
+
+    let mut tx = db.pool().begin().await?;
+    for i in 0..count {
+        let text = format!("{} {} entry {}", words[i % words.len()], words[(i / 7) % words.len()], i);
+        sqlx::query(
+            "INSERT INTO clipboard_entries (content_type, text_content, created_at, favorite, source_app)
+             VALUES ('text', ?, ?, ?, 'Terminal')",
+        )
+        .bind(text)
+        .bind(now - (count - i) as i64)
+        .bind(i % 500 == 0)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+fn report(name: &str, operations: usize, elapsed: Duration) {
+    println!(
+        "{:<40} {:>8} ops in {:>8.1?}  {:>10.0} ops/s  {:>8.3} ms/op",
+        name,
+        operations,
+        elapsed,
+        operations as f64 / elapsed.as_secs_f64(),
+        elapsed.as_secs_f64() * 1000.0 / operations as f64
+    );
+}
+
+async fn insert_throughput(name: &str, options: &DatabaseOptions) -> Result<()> {
+    let dir = TempDir::new()?;
+    let db = Database::open(dir.path().join("bench.db"), options).await?;
+    seed(&db, LARGE_HISTORY).await?;
+
+    // One transaction per insert, as the clipboard monitor does
+    let inserts = 2_000;
+    let start = Instant::now();
+    for i in 0..inserts {
+        queries::insert_entry(db.pool(), NewClipboardEntry::new_text(format!("copied text {}", i))).await?;
+    }
+    report(name, inserts, start.elapsed());
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "benchmark"]
+async fn bench_insert_throughput() -> Result<()> {
+    insert_throughput("insert (wal, synchronous=normal)", &DatabaseOptions::default()).await?;
+
+    let rollback_journal = DatabaseOptions {
+        journal_mode: JournalMode::Delete,
+        synchronous: Synchronous::Full,
+        ..DatabaseOptions::default()
+    };
+    insert_throughput("insert (delete journal, synchronous=full)", &rollback_journal).await
+}
+
+#[tokio::test]
+#[ignore = "benchmark"]
+async fn bench_search_throughput() -> Result<()> {
+    let dir = TempDir::new()?;
+    let db = Database::new(dir.path().join("bench.db")).await?;
+
+    let start = Instant::now();
+    seed(&db, LARGE_HISTORY).await?;
+    report("seed 100k entries (one transaction)", LARGE_HISTORY, start.elapsed());
+
+    let searches = [
+        ("recent page", ClipboardSearchParams { limit: 100, ..Default::default() }),
+        ("substring search", ClipboardSearchParams { query: Some("release".to_string()), limit: 100, ..Default::default() }),
+        ("rare substring search", ClipboardSearchParams { query: Some("entry 99999".to_string()), limit: 100, ..Default::default() }),
+        ("favorites", ClipboardSearchParams { favorites_only: true, limit: 100, ..Default::default() }),
+    ];
+
+    for (name, params) in searches {
+        let runs = 50;
+        let start = Instant::now();
+        for _ in 0..runs {
+            let results = queries::search_entries(db.pool(), params.clone()).await?;
+            assert!(!results.is_empty());
+        }
+        report(name, runs, start.elapsed());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "benchmark"]
+async fn bench_reads_during_writes() -> Result<()> {
+    let dir = TempDir::new()?;
+    let db_path = dir.path().join("bench.db");
+    let db = Database::new(db_path.clone()).await?;
+    seed(&db, LARGE_HISTORY).await?;
+
+    // A reader on its own pool, like the CLI, while the app keeps inserting
+    let reader = Database::new(db_path).await?;
+    let writer = tokio::spawn({
+        let pool = db.pool().clone();
+        async move {
+            for i in 0..1_000 {
+                queries::insert_entry(&pool, NewClipboardEntry::new_text(format!("concurrent {}", i))).await?;
+            }
+            anyhow::Ok(())
+        }
+    });
+
+    let runs = 200;
+    let start = Instant::now();
+    for _ in 0..runs {
+        queries::get_recent_entries(reader.pool(), 20).await?;
+    }
+    report("recent entries while writing", runs, start.elapsed());
+
+    writer.await??;
+    Ok(())
+}
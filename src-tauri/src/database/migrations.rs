@@ -1,5 +1,30 @@
 use sqlx::{SqlitePool, Row};
 use anyhow::Result;
+use std::fmt;
+
+use crate::clipboard::classify;
+
+/// Newest schema this build knows how to use. Bump with every migration.
+pub const SCHEMA_VERSION: i64 = 11;
+
+/// The database was written by a newer version of the app
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaTooNew {
+    pub found: i64,
+    pub supported: i64,
+}
+
+impl fmt::Display for SchemaTooNew {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Database schema version {} is newer than this app supports ({})",
+            self.found, self.supported
+        )
+    }
+}
+
+impl std::error::Error for SchemaTooNew {}
 
 pub const INIT_SQL: &str = r#"
 CREATE TABLE IF NOT EXISTS clipboard_entries (
@@ -22,6 +47,62 @@ CREATE TABLE IF NOT EXISTS schema_version (
 );
 "#;
 
+const SYNC_SQL: &str = r#"
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sync_id ON clipboard_entries(sync_id);
+
+CREATE TABLE IF NOT EXISTS sync_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS sync_changes (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    sync_id TEXT NOT NULL,
+    op TEXT NOT NULL,
+    value TEXT,
+    ts INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS sync_versions (
+    sync_id TEXT NOT NULL,
+    field TEXT NOT NULL,
+    ts INTEGER NOT NULL,
+    device TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (sync_id, field)
+);
+
+CREATE TRIGGER IF NOT EXISTS sync_record_pin AFTER UPDATE OF favorite ON clipboard_entries
+WHEN NEW.sync_id IS NOT NULL AND NEW.favorite IS NOT OLD.favorite
+    AND EXISTS (SELECT 1 FROM sync_state WHERE key = 'enabled')
+    AND NOT EXISTS (SELECT 1 FROM sync_state WHERE key = 'applying')
+BEGIN
+    INSERT INTO sync_changes (sync_id, op, value, ts)
+    VALUES (NEW.sync_id, 'pin', CASE WHEN NEW.favorite THEN 'true' ELSE 'false' END,
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_record_update AFTER UPDATE OF text_content ON clipboard_entries
+WHEN NEW.sync_id IS NOT NULL AND NEW.text_content IS NOT OLD.text_content
+    AND EXISTS (SELECT 1 FROM sync_state WHERE key = 'enabled')
+    AND NOT EXISTS (SELECT 1 FROM sync_state WHERE key = 'applying')
+BEGIN
+    INSERT INTO sync_changes (sync_id, op, value, ts)
+    VALUES (NEW.sync_id, 'update', json_quote(NEW.text_content),
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_record_tag AFTER UPDATE OF metadata ON clipboard_entries
+WHEN NEW.sync_id IS NOT NULL AND NEW.metadata IS NOT OLD.metadata
+    AND EXISTS (SELECT 1 FROM sync_state WHERE key = 'enabled')
+    AND NOT EXISTS (SELECT 1 FROM sync_state WHERE key = 'applying')
+BEGIN
+    INSERT INTO sync_changes (sync_id, op, value, ts)
+    VALUES (NEW.sync_id, 'tag', json_quote(NEW.metadata),
+            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+END;
+"#;
+
 pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
     // Create tables
     sqlx::query(INIT_SQL).execute(pool).await?;
@@ -34,6 +115,11 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
 
     let current_version = version.unwrap_or(0);
 
+    // Running old migrations against a newer schema could damage it
+    if current_version > SCHEMA_VERSION {
+        return Err(SchemaTooNew { found: current_version, supported: SCHEMA_VERSION }.into());
+    }
+
     if current_version == 0 {
         // First migration
         let timestamp = chrono::Utc::now().timestamp();
@@ -58,6 +144,227 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
             .await?;
     }
 
+    // Migration 3: Per-app paste profiles
+    if current_version < 3 {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS paste_profiles (
+                app_name TEXT PRIMARY KEY COLLATE NOCASE,
+                paste_keys TEXT NOT NULL,
+                pre_delay_ms INTEGER NOT NULL,
+                post_delay_ms INTEGER NOT NULL,
+                restore_clipboard BOOLEAN NOT NULL DEFAULT 0,
+                strategy TEXT NOT NULL DEFAULT 'clipboard',
+                type_rate INTEGER NOT NULL DEFAULT 0
+            )"
+        )
+        .execute(pool)
+        .await?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(3)
+            .bind(timestamp)
+            .execute(pool)
+            .await?;
+    }
+
+    // Migration 4: Key-value settings
+    if current_version < 4 {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )"
+        )
+        .execute(pool)
+        .await?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(4)
+            .bind(timestamp)
+            .execute(pool)
+            .await?;
+    }
+
+    // Migration 5: Folder sync. Entries get a stable id shared across devices;
+    // triggers record local edits of synced entries while sync is enabled,
+    // except for changes the sync engine itself applies.
+    if current_version < 5 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("ALTER TABLE clipboard_entries ADD COLUMN sync_id TEXT")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(SYNC_SQL).execute(&mut *tx).await?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(5)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // Migration 6: Detected kind of text entries (url, email, code, ...),
+    // filled in for the existing history
+    if current_version < 6 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("ALTER TABLE clipboard_entries ADD COLUMN kind TEXT")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE clipboard_entries ADD COLUMN kind_confidence REAL")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_kind ON clipboard_entries(kind)")
+            .execute(&mut *tx)
+            .await?;
+
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, text_content FROM clipboard_entries WHERE content_type = 'text' AND text_content IS NOT NULL",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for (id, text) in rows {
+            let classification = classify(&text);
+            sqlx::query("UPDATE clipboard_entries SET kind = ?, kind_confidence = ? WHERE id = ?")
+                .bind(classification.kind.as_str())
+                .bind(classification.confidence)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(6)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // Migration 7: Language of code snippets, for highlighting
+    if current_version < 7 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("ALTER TABLE clipboard_entries ADD COLUMN language TEXT")
+            .execute(&mut *tx)
+            .await?;
+
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, text_content FROM clipboard_entries WHERE content_type = 'text' AND text_content IS NOT NULL",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for (id, text) in rows {
+            if let Some(language) = classify(&text).language {
+                sqlx::query("UPDATE clipboard_entries SET language = ? WHERE id = ?")
+                    .bind(language.as_str())
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(7)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // Migration 8: Entries derived from another one, like the text of a QR
+    // code found in a screenshot
+    if current_version < 8 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("ALTER TABLE clipboard_entries ADD COLUMN parent_id INTEGER REFERENCES clipboard_entries(id) ON DELETE CASCADE")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_parent_id ON clipboard_entries(parent_id)")
+            .execute(&mut *tx)
+            .await?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(8)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // Migration 9: Text recognized in image entries. NULL until OCR has run,
+    // empty when the image holds no text.
+    if current_version < 9 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("ALTER TABLE clipboard_entries ADD COLUMN ocr_text TEXT")
+            .execute(&mut *tx)
+            .await?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(9)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // Migration 10: Large texts kept in the blob store, with only a preview
+    // in text_content
+    if current_version < 10 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("ALTER TABLE clipboard_entries ADD COLUMN content_size INTEGER")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE clipboard_entries ADD COLUMN truncated BOOLEAN NOT NULL DEFAULT 0")
+            .execute(&mut *tx)
+            .await?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(10)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // Migration 11: Deletes are shared by `delete_entry` itself. A trigger also
+    // fired for retention cleanup and cascades, which removed entries on
+    // devices that keep their history.
+    if current_version < 11 {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("DROP TRIGGER IF EXISTS sync_record_delete")
+            .execute(&mut *tx)
+            .await?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(11)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
     Ok(())
 }
 
@@ -92,7 +399,29 @@ mod tests {
         .fetch_one(&pool)
         .await?;
 
-        assert_eq!(version, 1, "Schema version should be 1");
+        assert_eq!(version, SCHEMA_VERSION, "Schema version should be the latest");
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_is_refused() -> Result<()> {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await?;
+        run_migrations(&pool).await?;
+
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, 0)")
+            .bind(SCHEMA_VERSION + 1)
+            .execute(&pool)
+            .await?;
+
+        let error = run_migrations(&pool).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<SchemaTooNew>(),
+            Some(&SchemaTooNew { found: SCHEMA_VERSION + 1, supported: SCHEMA_VERSION })
+        );
 
         pool.close().await;
         Ok(())
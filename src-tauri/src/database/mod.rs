@@ -1,6 +1,12 @@
+pub mod backup;
+#[cfg(test)]
+mod benchmarks;
 pub mod models;
 pub mod migrations;
+pub mod offload;
+pub mod options;
 pub mod queries;
+pub mod recovery;
 
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use sqlx::SqlitePool;
@@ -9,6 +15,7 @@ use std::str::FromStr;
 use anyhow::Result;
 
 pub use models::*;
+pub use options::DatabaseOptions;
 pub use queries::*;
 
 pub struct Database {
@@ -17,20 +24,51 @@ pub struct Database {
 
 impl Database {
     pub async fn new(db_path: PathBuf) -> Result<Self> {
+        Self::open(db_path, &DatabaseOptions::default()).await
+    }
+
+    pub async fn open(db_path: PathBuf, options: &DatabaseOptions) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        let connection_options = SqliteConnectOptions::from_str(&format!("sqlite://{}", db_path.display()))?
-            .create_if_missing(true);
+        // The GUI, the CLI and backups can have the file open at the same time
+        let connection_options = options.apply(
+            SqliteConnectOptions::from_str(&format!("sqlite://{}", db_path.display()))?.create_if_missing(true),
+        );
+
+        // Run migrations on a single connection first. A pooled connection
+        // opened while a migration alters a table would keep the old schema
+        // cached, and sqlx can't cope with the column count of `SELECT *`
+        // changing under a prepared statement.
+        let setup = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(connection_options.clone())
+            .await?;
+        migrations::run_migrations(&setup).await?;
+        setup.close().await;
 
         let pool = SqlitePoolOptions::new()
-            .max_connections(5)
+            .max_connections(options.max_connections)
             .connect_with(connection_options)
             .await?;
 
-        // Run migrations
+        Ok(Self { pool })
+    }
+
+    /// A database that lives only as long as the app runs, for when the file
+    /// can't be opened
+    pub async fn in_memory() -> Result<Self> {
+        // One connection that never closes; each new in-memory connection
+        // would start out empty
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+
         migrations::run_migrations(&pool).await?;
 
         Ok(Self { pool })
@@ -65,4 +103,64 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_connection_pragmas() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db = Database::new(temp_dir.path().join("test.db")).await?;
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(db.pool()).await?;
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous").fetch_one(db.pool()).await?;
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(db.pool()).await?;
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(db.pool()).await?;
+
+        assert_eq!(journal_mode, "wal");
+        assert_eq!(synchronous, 1, "NORMAL");
+        assert_eq!(foreign_keys, 1);
+        assert_eq!(busy_timeout, 5000);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_custom_options() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let options = DatabaseOptions {
+            journal_mode: options::JournalMode::Delete,
+            synchronous: options::Synchronous::Full,
+            busy_timeout_ms: 250,
+            ..DatabaseOptions::default()
+        };
+        let db = Database::open(temp_dir.path().join("test.db"), &options).await?;
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(db.pool()).await?;
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous").fetch_one(db.pool()).await?;
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(db.pool()).await?;
+
+        assert_eq!(journal_mode, "delete");
+        assert_eq!(synchronous, 2, "FULL");
+        assert_eq!(busy_timeout, 250);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_writer_waits_for_lock_instead_of_failing() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test.db");
+        let app = Database::new(db_path.clone()).await?;
+        // A second process, like the CLI
+        let cli = Database::new(db_path).await?;
+
+        let mut tx = cli.pool().begin().await?;
+        sqlx::query("DELETE FROM clipboard_entries").execute(&mut *tx).await?;
+
+        let insert = tokio::spawn({
+            let pool = app.pool().clone();
+            async move { queries::insert_entry(&pool, NewClipboardEntry::new_text("waits".to_string())).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        tx.commit().await?;
+
+        assert!(insert.await?.is_ok(), "Insert should wait for the lock, not fail with SQLITE_BUSY");
+        Ok(())
+    }
 }
@@ -11,6 +11,27 @@ pub struct ClipboardEntry {
     pub created_at: i64,
     pub favorite: bool,
     pub source_app: Option<String>,
+    /// Detected kind of text entries, see `clipboard::classify`
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub kind_confidence: Option<f64>,
+    /// Programming or data language of code, see `clipboard::language`
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Entry this one was derived from, e.g. the image a QR code was read from
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    /// Text recognized in image entries, see `clipboard::ocr`
+    #[serde(default)]
+    pub ocr_text: Option<String>,
+    /// Length in bytes of the complete text
+    #[serde(default)]
+    pub content_size: Option<i64>,
+    /// `text_content` is only a preview: the body was moved to the blob
+    /// store (see `database::offload`) or a list query shortened it
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +69,8 @@ impl NewClipboardEntry {
 pub struct ClipboardSearchParams {
     pub query: Option<String>,
     pub content_type: Option<String>,
+    /// Detected kind, e.g. "url"
+    pub kind: Option<String>,
     pub favorites_only: bool,
     pub limit: i64,
     pub offset: i64,
@@ -58,9 +81,37 @@ impl Default for ClipboardSearchParams {
         Self {
             query: None,
             content_type: None,
+            kind: None,
             favorites_only: false,
             limit: 100,
             offset: 0,
         }
     }
 }
+
+/// Which entries to export. Unset fields don't filter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportFilter {
+    pub query: Option<String>,
+    pub content_type: Option<String>,
+    pub favorites_only: bool,
+    /// Unix timestamps, inclusive
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+/// Per-target-app paste settings. Stored rows are user overrides; built-in
+/// defaults live in `paste::profiles`.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PasteProfile {
+    pub app_name: String,
+    /// Paste shortcut, e.g. "Ctrl+Shift+V"
+    pub paste_keys: String,
+    pub pre_delay_ms: i64,
+    pub post_delay_ms: i64,
+    pub restore_clipboard: bool,
+    /// "clipboard" or "type"
+    pub strategy: String,
+    pub type_rate: i64,
+}
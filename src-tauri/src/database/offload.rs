@@ -0,0 +1,310 @@
+// Texts too large to keep in the database. The full body goes to the blob
+// store as "<sha256>.txt" and the entry keeps only a preview with `truncated`
+// set; `load_text` reads the whole body back when it's needed. Files in the
+// blob store are removed along with the last entry that uses them.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+use super::models::{ClipboardEntry, NewClipboardEntry};
+use super::queries;
+use crate::clipboard::thumbnail::{thumbnail_path, THUMBNAIL_SIZES};
+
+/// Name of the directory the app keeps its own files in: offloaded texts,
+/// imported images and thumbnails
+pub const BLOB_DIR_NAME: &str = "blobs";
+
+/// Characters kept inline for offloaded texts. List queries cut every text
+/// down to this length too.
+pub const PREVIEW_CHARS: usize = 2000;
+
+/// The first `PREVIEW_CHARS` characters of `text`
+pub fn preview(text: &str) -> &str {
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// Store `text` in `blob_dir`, named by its hash so a repeated copy reuses
+/// the file
+pub fn write_blob(blob_dir: &Path, text: &str) -> Result<PathBuf> {
+    let hash = Sha256::digest(text.as_bytes());
+    let path = blob_dir.join(format!("{:x}.txt", hash));
+    if path.exists() {
+        return Ok(path);
+    }
+
+    std::fs::create_dir_all(blob_dir)?;
+    // Written under a temporary name so a crash never leaves half a blob
+    let partial = path.with_extension("txt.partial");
+    std::fs::write(&partial, text).with_context(|| format!("Failed to write {}", partial.display()))?;
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// Like `upsert_entry`, but texts longer than `offload_bytes` are moved to
+/// the blob store. A repeated copy of the same large text bumps the existing
+/// entry instead of adding another one.
+pub async fn upsert_text(pool: &SqlitePool, blob_dir: &Path, mut entry: NewClipboardEntry, offload_bytes: u64) -> Result<i64> {
+    let Some(size) = offload(blob_dir, &mut entry, offload_bytes).await? else {
+        return queries::upsert_entry(pool, entry).await;
+    };
+
+    let existing: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM clipboard_entries WHERE content_type = ? AND file_path = ? LIMIT 1"
+    )
+    .bind(&entry.content_type)
+    .bind(&entry.file_path)
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(id) = existing {
+        sqlx::query("UPDATE clipboard_entries SET created_at = ?, source_app = ? WHERE id = ?")
+            .bind(Utc::now().timestamp())
+            .bind(&entry.source_app)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        return Ok(id);
+    }
+
+    queries::insert_truncated_at(pool, &entry, Utc::now().timestamp(), false, size).await
+}
+
+/// Like `insert_entry_at`, moving texts longer than `offload_bytes` to the
+/// blob store (imports)
+pub async fn insert_text_at(
+    pool: &SqlitePool,
+    blob_dir: &Path,
+    mut entry: NewClipboardEntry,
+    created_at: i64,
+    favorite: bool,
+    offload_bytes: u64,
+) -> Result<i64> {
+    match offload(blob_dir, &mut entry, offload_bytes).await? {
+        Some(size) => queries::insert_truncated_at(pool, &entry, created_at, favorite, size).await,
+        None => queries::insert_entry_at(pool, &entry, created_at, favorite).await,
+    }
+}
+
+/// Write the text of `entry` to the blob store if it's longer than
+/// `offload_bytes`, leaving the preview and the blob's path in the entry.
+/// Returns the size of the full text, None if it stays inline.
+async fn offload(blob_dir: &Path, entry: &mut NewClipboardEntry, offload_bytes: u64) -> Result<Option<i64>> {
+    if entry.text_content.as_ref().is_none_or(|text| text.len() as u64 <= offload_bytes) {
+        return Ok(None);
+    }
+    let text = entry.text_content.take().unwrap_or_default();
+    let size = text.len() as i64;
+
+    let blob_dir = blob_dir.to_path_buf();
+    let (path, text) = tokio::task::spawn_blocking(move || write_blob(&blob_dir, &text).map(|path| (path, text))).await??;
+
+    entry.text_content = Some(preview(&text).to_string());
+    entry.file_path = Some(path.to_string_lossy().into_owned());
+    Ok(Some(size))
+}
+
+/// Full text of an entry read with `get_entry`, from the blob store if it
+/// was offloaded
+pub async fn load_text(entry: &ClipboardEntry) -> Result<Option<String>> {
+    if !entry.truncated {
+        return Ok(entry.text_content.clone());
+    }
+    let path = entry.file_path.as_deref().context("Truncated entry has no stored body")?;
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path))?;
+    Ok(Some(text))
+}
+
+/// Delete the files of removed entries, with their thumbnails, unless another
+/// entry still refers to them. Only files in the blob store are touched, never
+/// a path that points somewhere else on disk.
+pub async fn remove_unused_files(pool: &SqlitePool, file_paths: impl IntoIterator<Item = String>) -> Result<()> {
+    let mut file_paths: Vec<String> = file_paths.into_iter().collect();
+    file_paths.sort();
+    file_paths.dedup();
+
+    for file_path in file_paths {
+        let original = PathBuf::from(&file_path);
+        if original.parent().and_then(Path::file_name).is_none_or(|dir| dir != BLOB_DIR_NAME) {
+            continue;
+        }
+        let in_use: Option<i64> = sqlx::query_scalar("SELECT 1 FROM clipboard_entries WHERE file_path = ? LIMIT 1")
+            .bind(&file_path)
+            .fetch_optional(pool)
+            .await?;
+        if in_use.is_some() {
+            continue;
+        }
+
+        let thumbnails = THUMBNAIL_SIZES.iter().map(|size| thumbnail_path(&original, *size));
+        for path in std::iter::once(original.clone()).chain(thumbnails) {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => println!("⚠️  [WARNING] Failed to delete {}: {}", path.display(), e),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{self, ClipboardSearchParams, Database};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_preview_keeps_whole_characters() {
+        assert_eq!(preview("short"), "short");
+        let long = "é".repeat(PREVIEW_CHARS + 10);
+        assert_eq!(preview(&long).chars().count(), PREVIEW_CHARS);
+        assert_eq!(preview(&long).len(), PREVIEW_CHARS * 2);
+    }
+
+    #[test]
+    fn test_write_blob_is_content_addressed() {
+        let dir = TempDir::new().unwrap();
+        let first = write_blob(&dir.path().join("blobs"), "body").unwrap();
+        let second = write_blob(&dir.path().join("blobs"), "body").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.extension().unwrap(), "txt");
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "body");
+        assert_ne!(write_blob(&dir.path().join("blobs"), "other").unwrap(), first);
+    }
+
+    #[tokio::test]
+    async fn test_large_text_is_offloaded() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+        let log = "2024-01-01 INFO request served\n".repeat(1000);
+
+        let id = upsert_text(pool, dir.path(), NewClipboardEntry::new_text(log.clone()), 10_000).await?;
+        let entry = database::get_entry(pool, id).await?.unwrap();
+        assert!(entry.truncated);
+        assert_eq!(entry.content_size, Some(log.len() as i64));
+        assert_eq!(entry.text_content.as_deref(), Some(preview(&log)));
+        assert!(entry.file_path.as_deref().unwrap().starts_with(&*dir.path().to_string_lossy()));
+        assert_eq!(load_text(&entry).await?.as_deref(), Some(log.as_str()));
+        assert_eq!(database::get_entry_content(pool, id).await?.as_deref(), Some(log.as_str()));
+
+        // Imports keep their timestamp and favorite flag
+        let other = "y".repeat(20_000);
+        let imported = insert_text_at(pool, dir.path(), NewClipboardEntry::new_text(other.clone()), 1_600_000_000, true, 10_000).await?;
+        let entry = database::get_entry(pool, imported).await?.unwrap();
+        assert!(entry.truncated && entry.favorite);
+        assert_eq!((entry.created_at, entry.content_size), (1_600_000_000, Some(other.len() as i64)));
+        assert_eq!(load_text(&entry).await?.as_deref(), Some(other.as_str()));
+
+        // Copying it again reuses the entry
+        assert_eq!(upsert_text(pool, dir.path(), NewClipboardEntry::new_text(log.clone()), 10_000).await?, id);
+
+        // Small texts stay inline
+        let small = upsert_text(pool, dir.path(), NewClipboardEntry::new_text("small".to_string()), 10_000).await?;
+        let entry = database::get_entry(pool, small).await?.unwrap();
+        assert!(!entry.truncated && entry.file_path.is_none());
+        assert_eq!(load_text(&entry).await?.as_deref(), Some("small"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copying_the_preview_is_a_new_entry() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+        let log = "x".repeat(PREVIEW_CHARS * 10);
+
+        let offloaded = upsert_text(pool, dir.path(), NewClipboardEntry::new_text(log.clone()), 10_000).await?;
+        let prefix = upsert_text(pool, dir.path(), NewClipboardEntry::new_text(preview(&log).to_string()), 10_000).await?;
+        assert_ne!(prefix, offloaded);
+        assert_eq!(database::get_entry_content(pool, prefix).await?.map(|text| text.len()), Some(PREVIEW_CHARS));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deleting_removes_unused_files() -> Result<()> {
+        let dir = TempDir::new()?;
+        let blob_dir = dir.path().join(BLOB_DIR_NAME);
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+
+        let text = "y".repeat(20_000);
+        let id = upsert_text(pool, &blob_dir, NewClipboardEntry::new_text(text.clone()), 10_000).await?;
+        let blob = PathBuf::from(database::get_entry(pool, id).await?.unwrap().file_path.unwrap());
+
+        // An image used by two entries, with its thumbnails
+        std::fs::write(blob_dir.join("abc.png"), "png")?;
+        for size in THUMBNAIL_SIZES {
+            std::fs::write(thumbnail_path(&blob_dir.join("abc.png"), size), "thumb")?;
+        }
+        let image = NewClipboardEntry {
+            content_type: "image".to_string(),
+            text_content: None,
+            file_path: Some(blob_dir.join("abc.png").to_string_lossy().into_owned()),
+            metadata: None,
+            source_app: None,
+        };
+        let first = database::insert_entry(pool, image.clone()).await?;
+        let second = database::insert_entry(pool, image).await?;
+
+        // A path outside the blob store is never deleted
+        let outside = dir.path().join("elsewhere.png");
+        std::fs::write(&outside, "png")?;
+        let foreign = NewClipboardEntry {
+            content_type: "image".to_string(),
+            text_content: None,
+            file_path: Some(outside.to_string_lossy().into_owned()),
+            metadata: None,
+            source_app: None,
+        };
+        let foreign = database::insert_entry(pool, foreign).await?;
+
+        assert!(database::delete_entry(pool, id).await?);
+        assert!(!blob.exists());
+
+        database::delete_entry(pool, first).await?;
+        assert!(blob_dir.join("abc.png").exists(), "Still used by the other entry");
+        sqlx::query("UPDATE clipboard_entries SET created_at = 0 WHERE id IN (?, ?)")
+            .bind(second)
+            .bind(foreign)
+            .execute(pool)
+            .await?;
+        assert_eq!(database::delete_old_entries(pool, 30).await?, 2);
+        assert!(!blob_dir.join("abc.png").exists());
+        assert!(!thumbnail_path(&blob_dir.join("abc.png"), 64).exists());
+        assert!(outside.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_queries_return_previews() -> Result<()> {
+        let db = Database::in_memory().await?;
+        let pool = db.pool();
+        let long = format!("needle {}", "x".repeat(PREVIEW_CHARS * 3));
+        let id = database::insert_entry(pool, NewClipboardEntry::new_text(long.clone())).await?;
+        database::insert_entry(pool, NewClipboardEntry::new_text("short".to_string())).await?;
+
+        let params = ClipboardSearchParams { query: Some("needle".to_string()), ..Default::default() };
+        let listed = database::search_entries(pool, params).await?;
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].truncated);
+        assert_eq!(listed[0].content_size, Some(long.len() as i64));
+        assert_eq!(listed[0].text_content.as_deref().map(|text| text.chars().count()), Some(PREVIEW_CHARS));
+
+        let recent = database::get_recent_entries(pool, 10).await?;
+        assert_eq!(recent.iter().map(|entry| entry.truncated).collect::<Vec<_>>(), vec![false, true]);
+
+        // The full body is one call away
+        assert_eq!(database::get_entry_content(pool, id).await?, Some(long));
+        assert_eq!(database::get_entry_content(pool, id + 100).await?, None);
+        Ok(())
+    }
+}
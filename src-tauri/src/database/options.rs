@@ -0,0 +1,84 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    /// Readers don't block the writer, so the CLI and backups can run while the app records
+    #[default]
+    Wal,
+    Delete,
+    Truncate,
+    Persist,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    /// With WAL, only a power loss can lose the last few commits; the file never corrupts
+    #[default]
+    Normal,
+    Full,
+    Extra,
+}
+
+/// How the history database is opened. Stored with the other settings and
+/// used the next time the database is opened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseOptions {
+    pub journal_mode: JournalMode,
+    pub synchronous: Synchronous,
+    /// How long a statement waits for another connection's lock before failing
+    pub busy_timeout_ms: u64,
+    pub foreign_keys: bool,
+    pub max_connections: u32,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        Self {
+            journal_mode: JournalMode::default(),
+            synchronous: Synchronous::default(),
+            busy_timeout_ms: 5000,
+            foreign_keys: true,
+            max_connections: 5,
+        }
+    }
+}
+
+impl DatabaseOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.busy_timeout_ms > 60_000 {
+            bail!("busy_timeout_ms must be at most 60000");
+        }
+        if !(1..=32).contains(&self.max_connections) {
+            bail!("max_connections must be between 1 and 32");
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, options: SqliteConnectOptions) -> SqliteConnectOptions {
+        let journal_mode = match self.journal_mode {
+            JournalMode::Wal => SqliteJournalMode::Wal,
+            JournalMode::Delete => SqliteJournalMode::Delete,
+            JournalMode::Truncate => SqliteJournalMode::Truncate,
+            JournalMode::Persist => SqliteJournalMode::Persist,
+        };
+        let synchronous = match self.synchronous {
+            Synchronous::Off => SqliteSynchronous::Off,
+            Synchronous::Normal => SqliteSynchronous::Normal,
+            Synchronous::Full => SqliteSynchronous::Full,
+            Synchronous::Extra => SqliteSynchronous::Extra,
+        };
+
+        options
+            .journal_mode(journal_mode)
+            .synchronous(synchronous)
+            .busy_timeout(Duration::from_millis(self.busy_timeout_ms))
+            .foreign_keys(self.foreign_keys)
+    }
+}
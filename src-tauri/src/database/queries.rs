@@ -2,14 +2,17 @@ use sqlx::SqlitePool;
 use anyhow::Result;
 use chrono::Utc;
 
-use super::models::{ClipboardEntry, NewClipboardEntry, ClipboardSearchParams};
+use super::models::{ClipboardEntry, NewClipboardEntry, ClipboardSearchParams, ExportFilter, PasteProfile};
+use super::offload::{self, PREVIEW_CHARS};
+use crate::clipboard::{classify, Classification};
 
 pub async fn insert_entry(pool: &SqlitePool, entry: NewClipboardEntry) -> Result<i64> {
     let timestamp = Utc::now().timestamp();
+    let classification = classify_entry(&entry);
 
     let result = sqlx::query(
-        "INSERT INTO clipboard_entries (content_type, text_content, file_path, metadata, created_at, favorite, source_app)
-         VALUES (?, ?, ?, ?, ?, 0, ?)"
+        "INSERT INTO clipboard_entries (content_type, text_content, file_path, metadata, created_at, favorite, source_app, kind, kind_confidence, language)
+         VALUES (?, ?, ?, ?, ?, 0, ?, ?, ?, ?)"
     )
     .bind(&entry.content_type)
     .bind(&entry.text_content)
@@ -17,12 +20,78 @@ pub async fn insert_entry(pool: &SqlitePool, entry: NewClipboardEntry) -> Result
     .bind(&entry.metadata)
     .bind(timestamp)
     .bind(&entry.source_app)
+    .bind(classification.map(|c| c.kind.as_str()))
+    .bind(classification.map(|c| c.confidence))
+    .bind(classification.and_then(|c| c.language).map(|language| language.as_str()))
     .execute(pool)
     .await?;
 
     Ok(result.last_insert_rowid())
 }
 
+/// Insert an entry that keeps its original timestamp and favorite flag (imports)
+pub async fn insert_entry_at(pool: &SqlitePool, entry: &NewClipboardEntry, created_at: i64, favorite: bool) -> Result<i64> {
+    insert_row(pool, entry, created_at, favorite, None).await
+}
+
+/// Insert an entry whose `text_content` is only a preview of a body of
+/// `content_size` bytes kept in `file_path`. The `truncated` flag goes in with
+/// the row, so there's never an offloaded entry that looks complete.
+pub async fn insert_truncated_at(pool: &SqlitePool, entry: &NewClipboardEntry, created_at: i64, favorite: bool, content_size: i64) -> Result<i64> {
+    insert_row(pool, entry, created_at, favorite, Some(content_size)).await
+}
+
+async fn insert_row(pool: &SqlitePool, entry: &NewClipboardEntry, created_at: i64, favorite: bool, truncated_size: Option<i64>) -> Result<i64> {
+    let classification = classify_entry(entry);
+
+    let result = sqlx::query(
+        "INSERT INTO clipboard_entries (content_type, text_content, file_path, metadata, created_at, favorite, source_app, kind, kind_confidence, language, truncated, content_size)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&entry.content_type)
+    .bind(&entry.text_content)
+    .bind(&entry.file_path)
+    .bind(&entry.metadata)
+    .bind(created_at)
+    .bind(favorite)
+    .bind(&entry.source_app)
+    .bind(classification.map(|c| c.kind.as_str()))
+    .bind(classification.map(|c| c.confidence))
+    .bind(classification.and_then(|c| c.language).map(|language| language.as_str()))
+    .bind(truncated_size.is_some())
+    .bind(truncated_size)
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Insert an entry derived from `parent_id`, dated like its parent so it
+/// sits next to it in the history
+pub async fn insert_child_entry(pool: &SqlitePool, parent_id: i64, entry: &NewClipboardEntry) -> Result<i64> {
+    let created_at: i64 = sqlx::query_scalar("SELECT created_at FROM clipboard_entries WHERE id = ?")
+        .bind(parent_id)
+        .fetch_one(pool)
+        .await?;
+    let id = insert_entry_at(pool, entry, created_at, false).await?;
+
+    sqlx::query("UPDATE clipboard_entries SET parent_id = ? WHERE id = ?")
+        .bind(parent_id)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(id)
+}
+
+/// Only text entries get a kind
+fn classify_entry(entry: &NewClipboardEntry) -> Option<Classification> {
+    match (entry.content_type.as_str(), &entry.text_content) {
+        ("text", Some(text)) => Some(classify(text)),
+        _ => None,
+    }
+}
+
 /// Insert or update clipboard entry. If the same text content already exists,
 /// update its timestamp instead of creating a duplicate.
 pub async fn upsert_entry(pool: &SqlitePool, entry: NewClipboardEntry) -> Result<i64> {
@@ -30,8 +99,9 @@ pub async fn upsert_entry(pool: &SqlitePool, entry: NewClipboardEntry) -> Result
 
     // Check if entry with same text content already exists
     if let Some(ref text_content) = entry.text_content {
+        // Offloaded entries only keep a preview inline, matching it isn't a repeat
         let existing: Option<(i64, bool)> = sqlx::query_as(
-            "SELECT id, favorite FROM clipboard_entries WHERE text_content = ? LIMIT 1"
+            "SELECT id, favorite FROM clipboard_entries WHERE text_content = ? AND NOT truncated LIMIT 1"
         )
         .bind(text_content)
         .fetch_optional(pool)
@@ -56,6 +126,18 @@ pub async fn upsert_entry(pool: &SqlitePool, entry: NewClipboardEntry) -> Result
     insert_entry(pool, entry).await
 }
 
+/// Columns for list queries: texts are cut down to a preview and flagged as
+/// truncated, so a huge entry doesn't slow down the picker
+fn list_columns() -> String {
+    format!(
+        "id, content_type, substr(text_content, 1, {0}) AS text_content, file_path, metadata, created_at, favorite,
+         source_app, kind, kind_confidence, language, parent_id, ocr_text,
+         COALESCE(content_size, length(CAST(text_content AS BLOB))) AS content_size,
+         (truncated OR length(text_content) > {0}) AS truncated",
+        PREVIEW_CHARS
+    )
+}
+
 pub async fn get_entry(pool: &SqlitePool, id: i64) -> Result<Option<ClipboardEntry>> {
     let entry = sqlx::query_as::<_, ClipboardEntry>(
         "SELECT * FROM clipboard_entries WHERE id = ?"
@@ -67,22 +149,48 @@ pub async fn get_entry(pool: &SqlitePool, id: i64) -> Result<Option<ClipboardEnt
     Ok(entry)
 }
 
+/// Split `type:` filters off a search query: "type:url github" searches URLs
+/// containing "github". A type is a detected kind (url, email, code, ...), a
+/// language (rust, sql, ...) or a content type (text, image, ...); several are
+/// alternatives.
+pub fn parse_type_filters(query: &str) -> (Vec<String>, String) {
+    let mut types = Vec::new();
+    let mut words = Vec::new();
+    for word in query.split_whitespace() {
+        match word.strip_prefix("type:") {
+            Some(name) if !name.is_empty() => types.push(name.to_lowercase()),
+            _ => words.push(word),
+        }
+    }
+    (types, words.join(" "))
+}
+
 pub async fn search_entries(pool: &SqlitePool, params: ClipboardSearchParams) -> Result<Vec<ClipboardEntry>> {
-    let mut query = String::from(
-        "SELECT * FROM clipboard_entries WHERE 1=1"
-    );
+    let mut query = format!("SELECT {} FROM clipboard_entries WHERE 1=1", list_columns());
+
+    let (types, search_text) = match params.query {
+        Some(ref search_query) => parse_type_filters(search_query),
+        None => (Vec::new(), String::new()),
+    };
 
     // Apply filters
-    if let Some(ref search_query) = params.query {
-        if !search_query.is_empty() {
-            query.push_str(" AND text_content LIKE '%' || ? || '%'");
-        }
+    if !search_text.is_empty() {
+        query.push_str(" AND (text_content LIKE '%' || ? || '%' OR ocr_text LIKE '%' || ? || '%')");
     }
 
-    if let Some(ref content_type) = params.content_type {
+    if !types.is_empty() {
+        let placeholders = vec!["?"; types.len()].join(", ");
+        query.push_str(&format!(" AND (kind IN ({0}) OR language IN ({0}) OR content_type IN ({0}))", placeholders));
+    }
+
+    if params.content_type.is_some() {
         query.push_str(" AND content_type = ?");
     }
 
+    if params.kind.is_some() {
+        query.push_str(" AND kind = ?");
+    }
+
     if params.favorites_only {
         query.push_str(" AND favorite = 1");
     }
@@ -96,9 +204,13 @@ pub async fn search_entries(pool: &SqlitePool, params: ClipboardSearchParams) ->
     let mut sql_query = sqlx::query_as::<_, ClipboardEntry>(&query);
 
     // Bind parameters
-    if let Some(ref search_query) = params.query {
-        if !search_query.is_empty() {
-            sql_query = sql_query.bind(search_query);
+    if !search_text.is_empty() {
+        sql_query = sql_query.bind(search_text.clone()).bind(search_text);
+    }
+
+    for _ in 0..3 {
+        for name in &types {
+            sql_query = sql_query.bind(name);
         }
     }
 
@@ -106,6 +218,10 @@ pub async fn search_entries(pool: &SqlitePool, params: ClipboardSearchParams) ->
         sql_query = sql_query.bind(content_type);
     }
 
+    if let Some(ref kind) = params.kind {
+        sql_query = sql_query.bind(kind);
+    }
+
     sql_query = sql_query.bind(params.limit).bind(params.offset);
 
     let entries = sql_query.fetch_all(pool).await?;
@@ -136,26 +252,138 @@ pub async fn toggle_favorite(pool: &SqlitePool, id: i64) -> Result<bool> {
     }
 }
 
+/// Set the favorite flag explicitly. Returns false if the entry doesn't exist.
+pub async fn set_favorite(pool: &SqlitePool, id: i64, favorite: bool) -> Result<bool> {
+    let result = sqlx::query("UPDATE clipboard_entries SET favorite = ? WHERE id = ?")
+        .bind(favorite)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Replace the entry's tags, kept as `tags` in its metadata JSON alongside any
+/// other metadata. Returns false if the entry doesn't exist.
+pub async fn set_tags(pool: &SqlitePool, id: i64, tags: &[String]) -> Result<bool> {
+    let mut fields = serde_json::Map::new();
+    fields.insert("tags".to_string(), serde_json::to_value(tags)?);
+    merge_metadata(pool, id, fields).await
+}
+
+/// Set fields of an entry's metadata object, keeping the others
+pub async fn merge_metadata(pool: &SqlitePool, id: i64, fields: serde_json::Map<String, serde_json::Value>) -> Result<bool> {
+    let Some(entry) = get_entry(pool, id).await? else {
+        return Ok(false);
+    };
+
+    // Metadata that isn't a JSON object is replaced
+    let mut metadata = entry
+        .metadata
+        .and_then(|metadata| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&metadata).ok())
+        .unwrap_or_default();
+    metadata.extend(fields);
+
+    let result = sqlx::query("UPDATE clipboard_entries SET metadata = ? WHERE id = ?")
+        .bind(serde_json::to_string(&metadata)?)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Image entries whose dimensions haven't been recorded yet
+pub async fn get_images_without_dimensions(pool: &SqlitePool) -> Result<Vec<ClipboardEntry>> {
+    let entries = sqlx::query_as::<_, ClipboardEntry>(
+        "SELECT * FROM clipboard_entries
+         WHERE content_type = 'image' AND file_path IS NOT NULL
+           AND (CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.width') END) IS NULL
+           AND (CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.thumbnail_error') END) IS NULL
+         ORDER BY created_at DESC"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+/// Complete text of an entry, which list queries only return a preview of
+pub async fn get_entry_content(pool: &SqlitePool, id: i64) -> Result<Option<String>> {
+    match get_entry(pool, id).await? {
+        Some(entry) => offload::load_text(&entry).await,
+        None => Ok(None),
+    }
+}
+
+/// Image entries that haven't been through OCR yet, oldest first. Entries
+/// that already failed with the same `setup` (see `ocr::setup_key`) are left out.
+pub async fn get_images_without_ocr(pool: &SqlitePool, setup: &str) -> Result<Vec<ClipboardEntry>> {
+    let entries = sqlx::query_as::<_, ClipboardEntry>(
+        "SELECT * FROM clipboard_entries
+         WHERE content_type = 'image' AND file_path IS NOT NULL AND ocr_text IS NULL
+           AND (CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.ocr_setup') END) IS NOT ?
+         ORDER BY created_at ASC"
+    )
+    .bind(setup)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+pub async fn set_ocr_text(pool: &SqlitePool, id: i64, text: &str) -> Result<bool> {
+    let result = sqlx::query("UPDATE clipboard_entries SET ocr_text = ? WHERE id = ?")
+        .bind(text)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 pub async fn delete_old_entries(pool: &SqlitePool, days: i64) -> Result<u64> {
     let cutoff_timestamp = Utc::now().timestamp() - (days * 24 * 60 * 60);
 
-    let result = sqlx::query(
-        "DELETE FROM clipboard_entries WHERE created_at < ? AND favorite = 0"
+    let file_paths: Vec<Option<String>> = sqlx::query_scalar(
+        "DELETE FROM clipboard_entries WHERE created_at < ? AND favorite = 0 RETURNING file_path"
     )
     .bind(cutoff_timestamp)
-    .execute(pool)
+    .fetch_all(pool)
     .await?;
 
-    Ok(result.rows_affected())
+    let deleted = file_paths.len() as u64;
+    offload::remove_unused_files(pool, file_paths.into_iter().flatten()).await?;
+    Ok(deleted)
 }
 
+/// Delete an entry the user chose to remove. With sync enabled the delete is
+/// recorded so other devices remove it too; retention cleanup and cascades
+/// stay local.
 pub async fn delete_entry(pool: &SqlitePool, id: i64) -> Result<bool> {
-    let result = sqlx::query("DELETE FROM clipboard_entries WHERE id = ?")
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO sync_changes (sync_id, op, value, ts)
+         SELECT sync_id, 'delete', NULL, ? FROM clipboard_entries
+         WHERE id = ? AND sync_id IS NOT NULL AND EXISTS (SELECT 1 FROM sync_state WHERE key = 'enabled')"
+    )
+    .bind(Utc::now().timestamp_millis())
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    let deleted: Option<Option<String>> = sqlx::query_scalar("DELETE FROM clipboard_entries WHERE id = ? RETURNING file_path")
         .bind(id)
-        .execute(pool)
+        .fetch_optional(&mut *tx)
         .await?;
 
-    Ok(result.rows_affected() > 0)
+    tx.commit().await?;
+    let Some(file_path) = deleted else {
+        return Ok(false);
+    };
+    offload::remove_unused_files(pool, file_path).await?;
+    Ok(true)
 }
 
 pub async fn get_last_entry(pool: &SqlitePool) -> Result<Option<ClipboardEntry>> {
@@ -168,6 +396,126 @@ pub async fn get_last_entry(pool: &SqlitePool) -> Result<Option<ClipboardEntry>>
     Ok(entry)
 }
 
+pub async fn get_paste_profiles(pool: &SqlitePool) -> Result<Vec<PasteProfile>> {
+    let profiles = sqlx::query_as::<_, PasteProfile>(
+        "SELECT * FROM paste_profiles ORDER BY app_name COLLATE NOCASE"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(profiles)
+}
+
+/// Insert a paste profile, replacing any existing profile for the same app
+pub async fn upsert_paste_profile(pool: &SqlitePool, profile: &PasteProfile) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO paste_profiles (app_name, paste_keys, pre_delay_ms, post_delay_ms, restore_clipboard, strategy, type_rate)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(app_name) DO UPDATE SET
+            paste_keys = excluded.paste_keys,
+            pre_delay_ms = excluded.pre_delay_ms,
+            post_delay_ms = excluded.post_delay_ms,
+            restore_clipboard = excluded.restore_clipboard,
+            strategy = excluded.strategy,
+            type_rate = excluded.type_rate"
+    )
+    .bind(&profile.app_name)
+    .bind(&profile.paste_keys)
+    .bind(profile.pre_delay_ms)
+    .bind(profile.post_delay_ms)
+    .bind(profile.restore_clipboard)
+    .bind(&profile.strategy)
+    .bind(profile.type_rate)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_paste_profile(pool: &SqlitePool, app_name: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM paste_profiles WHERE app_name = ?")
+        .bind(app_name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Entries matching `filter`, oldest first
+pub async fn get_entries_for_export(pool: &SqlitePool, filter: &ExportFilter) -> Result<Vec<ClipboardEntry>> {
+    let mut sql = String::from("SELECT * FROM clipboard_entries WHERE 1=1");
+
+    if filter.query.as_deref().is_some_and(|query| !query.is_empty()) {
+        sql.push_str(" AND text_content LIKE '%' || ? || '%'");
+    }
+    if filter.content_type.is_some() {
+        sql.push_str(" AND content_type = ?");
+    }
+    if filter.favorites_only {
+        sql.push_str(" AND favorite = 1");
+    }
+    if filter.since.is_some() {
+        sql.push_str(" AND created_at >= ?");
+    }
+    if filter.until.is_some() {
+        sql.push_str(" AND created_at <= ?");
+    }
+    sql.push_str(" ORDER BY created_at ASC, id ASC");
+
+    let mut query = sqlx::query_as::<_, ClipboardEntry>(&sql);
+    if let Some(text) = filter.query.as_deref().filter(|query| !query.is_empty()) {
+        query = query.bind(text);
+    }
+    if let Some(ref content_type) = filter.content_type {
+        query = query.bind(content_type);
+    }
+    if let Some(since) = filter.since {
+        query = query.bind(since);
+    }
+    if let Some(until) = filter.until {
+        query = query.bind(until);
+    }
+
+    Ok(query.fetch_all(pool).await?)
+}
+
+pub async fn get_recent_entries(pool: &SqlitePool, limit: i64) -> Result<Vec<ClipboardEntry>> {
+    let sql = format!("SELECT {} FROM clipboard_entries ORDER BY created_at DESC, id DESC LIMIT ?", list_columns());
+    let entries = sqlx::query_as::<_, ClipboardEntry>(&sql)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+pub async fn get_all_settings(pool: &SqlitePool) -> Result<Vec<(String, String)>> {
+    let rows = sqlx::query_as::<_, (String, String)>("SELECT key, value FROM settings")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+/// Write several settings at once; either all of them are stored or none
+pub async fn set_settings(pool: &SqlitePool, entries: &[(String, String)]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    for (key, value) in entries {
+        sqlx::query(
+            "INSERT INTO settings (key, value) VALUES (?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value"
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,6 +583,89 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_entries_are_classified_on_insert() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        let url = insert_entry(&pool, NewClipboardEntry::new_text("https://example.org".to_string())).await?;
+        let text = insert_entry(&pool, NewClipboardEntry::new_text("just words".to_string())).await?;
+
+        let url = get_entry(&pool, url).await?.unwrap();
+        assert_eq!(url.kind.as_deref(), Some("url"));
+        assert!(url.kind_confidence.unwrap() > 0.9);
+        assert_eq!(get_entry(&pool, text).await?.unwrap().kind.as_deref(), Some("text"));
+
+        let image = NewClipboardEntry { content_type: "image".to_string(), text_content: None, file_path: Some("a.png".to_string()), metadata: None, source_app: None };
+        let image = insert_entry(&pool, image).await?;
+        assert_eq!(get_entry(&pool, image).await?.unwrap().kind, None);
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_child_entries_follow_their_parent() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        let image = NewClipboardEntry { content_type: "image".to_string(), text_content: None, file_path: Some("qr.png".to_string()), metadata: None, source_app: None };
+        let parent = insert_entry_at(&pool, &image, 1_700_000_000, false).await?;
+        let child = insert_child_entry(&pool, parent, &NewClipboardEntry::new_text("https://example.org/menu".to_string())).await?;
+
+        let child = get_entry(&pool, child).await?.unwrap();
+        assert_eq!(child.parent_id, Some(parent));
+        assert_eq!(child.created_at, 1_700_000_000);
+        assert_eq!(child.kind.as_deref(), Some("url"));
+
+        // Searchable like any entry, and gone with its parent
+        let params = ClipboardSearchParams { query: Some("menu".to_string()), ..Default::default() };
+        assert_eq!(search_entries(&pool, params).await?.len(), 1);
+        delete_entry(&pool, parent).await?;
+        assert!(get_entry(&pool, child.id).await?.is_none());
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_type_filters() {
+        assert_eq!(parse_type_filters("type:url github"), (vec!["url".to_string()], "github".to_string()));
+        assert_eq!(parse_type_filters("deploy  TYPE:code"), (Vec::new(), "deploy TYPE:code".to_string()));
+        assert_eq!(
+            parse_type_filters("type:Email type:phone"),
+            (vec!["email".to_string(), "phone".to_string()], String::new())
+        );
+        assert_eq!(parse_type_filters("type: x"), (Vec::new(), "type: x".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_search_by_type() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        insert_entry(&pool, NewClipboardEntry::new_text("https://github.com/rust-lang".to_string())).await?;
+        insert_entry(&pool, NewClipboardEntry::new_text("https://example.org".to_string())).await?;
+        insert_entry(&pool, NewClipboardEntry::new_text("github is down again".to_string())).await?;
+        insert_entry(&pool, NewClipboardEntry::new_text("me@example.org".to_string())).await?;
+
+        let search = |query: &str| ClipboardSearchParams { query: Some(query.to_string()), ..Default::default() };
+
+        assert_eq!(search_entries(&pool, search("type:url")).await?.len(), 2);
+        let results = search_entries(&pool, search("type:url github")).await?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].text_content.as_deref(), Some("https://github.com/rust-lang"));
+        assert_eq!(search_entries(&pool, search("type:url type:email")).await?.len(), 3);
+        assert_eq!(search_entries(&pool, search("type:text")).await?.len(), 4, "Content types match too");
+        assert_eq!(search_entries(&pool, search("type:json")).await?.len(), 0);
+
+        insert_entry(&pool, NewClipboardEntry::new_text("SELECT * FROM users WHERE id = 1".to_string())).await?;
+        assert_eq!(search_entries(&pool, search("type:sql")).await?.len(), 1, "Languages match too");
+
+        let by_kind = ClipboardSearchParams { kind: Some("email".to_string()), ..Default::default() };
+        assert_eq!(search_entries(&pool, by_kind).await?.len(), 1);
+
+        pool.close().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_favorite_toggle() -> Result<()> {
         let pool = setup_test_db().await?;
@@ -257,6 +688,25 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_set_favorite() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        let id = insert_entry(&pool, NewClipboardEntry::new_text("Pin me".to_string())).await?;
+
+        assert!(set_favorite(&pool, id, true).await?);
+        assert!(set_favorite(&pool, id, true).await?, "Pinning twice keeps it pinned");
+        assert!(get_entry(&pool, id).await?.unwrap().favorite);
+
+        assert!(set_favorite(&pool, id, false).await?);
+        assert!(!get_entry(&pool, id).await?.unwrap().favorite);
+
+        assert!(!set_favorite(&pool, id + 100, true).await?, "Missing entries report false");
+
+        pool.close().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_favorites_show_first() -> Result<()> {
         let pool = setup_test_db().await?;
@@ -412,4 +862,76 @@ mod tests {
         pool.close().await;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_paste_profile_upsert_and_delete() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        let profile = PasteProfile {
+            app_name: "kitty".to_string(),
+            paste_keys: "Ctrl+Shift+V".to_string(),
+            pre_delay_ms: 60,
+            post_delay_ms: 200,
+            restore_clipboard: false,
+            strategy: "clipboard".to_string(),
+            type_rate: 0,
+        };
+        upsert_paste_profile(&pool, &profile).await?;
+
+        // Same app with different casing replaces the existing profile
+        let updated = PasteProfile {
+            app_name: "Kitty".to_string(),
+            strategy: "type".to_string(),
+            ..profile
+        };
+        upsert_paste_profile(&pool, &updated).await?;
+
+        let profiles = get_paste_profiles(&pool).await?;
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].strategy, "type");
+
+        assert!(delete_paste_profile(&pool, "KITTY").await?);
+        assert!(get_paste_profiles(&pool).await?.is_empty());
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_settings_roundtrip() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        assert!(get_all_settings(&pool).await?.is_empty());
+
+        set_settings(&pool, &[("history_limit".to_string(), "100".to_string())]).await?;
+        set_settings(&pool, &[
+            ("history_limit".to_string(), "250".to_string()),
+            ("restore_clipboard".to_string(), "true".to_string()),
+        ]).await?;
+
+        let mut stored = get_all_settings(&pool).await?;
+        stored.sort();
+        assert_eq!(stored, vec![
+            ("history_limit".to_string(), "250".to_string()),
+            ("restore_clipboard".to_string(), "true".to_string()),
+        ], "Second write should replace the first");
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recent_entries_newest_first() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        let older = insert_entry(&pool, NewClipboardEntry::new_text("Older".to_string())).await?;
+        let newer = insert_entry(&pool, NewClipboardEntry::new_text("Newer".to_string())).await?;
+
+        let recent = get_recent_entries(&pool, 2).await?;
+        let ids: Vec<i64> = recent.iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, vec![newer, older]);
+
+        pool.close().await;
+        Ok(())
+    }
 }
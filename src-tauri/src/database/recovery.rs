@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::migrations::SchemaTooNew;
+
+// SQLite primary result codes (the low byte of an extended code)
+const SQLITE_PERM: i32 = 3;
+const SQLITE_BUSY: i32 = 5;
+const SQLITE_LOCKED: i32 = 6;
+const SQLITE_READONLY: i32 = 8;
+const SQLITE_CORRUPT: i32 = 11;
+const SQLITE_FULL: i32 = 13;
+const SQLITE_CANTOPEN: i32 = 14;
+const SQLITE_AUTH: i32 = 23;
+const SQLITE_NOTADB: i32 = 26;
+
+/// Why the history database couldn't be opened at startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenFailureKind {
+    /// Another process holds a lock; retrying may work
+    Locked,
+    Corrupt,
+    DiskFull,
+    /// The file or its directory isn't readable/writable
+    PermissionDenied,
+    /// Written by a newer version of the app
+    SchemaTooNew,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenFailure {
+    pub kind: OpenFailureKind,
+    pub message: String,
+    pub db_path: PathBuf,
+}
+
+/// What the user picked in the recovery window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    Retry,
+    RestoreBackup,
+    /// Move the old file aside and start with an empty history
+    StartFresh,
+    /// Keep running on the in-memory database until the app quits
+    MemoryOnly,
+}
+
+// Set when this session runs on an in-memory database because the file failed to open
+static STARTUP_FAILURE: Mutex<Option<OpenFailure>> = Mutex::new(None);
+
+pub fn set_startup_failure(failure: OpenFailure) {
+    *STARTUP_FAILURE.lock().unwrap() = Some(failure);
+}
+
+pub fn startup_failure() -> Option<OpenFailure> {
+    STARTUP_FAILURE.lock().unwrap().clone()
+}
+
+/// True when nothing this session does is written to disk. Backups must not
+/// run then, or rotation would replace good backups with empty ones.
+pub fn is_memory_only() -> bool {
+    STARTUP_FAILURE.lock().unwrap().is_some()
+}
+
+/// Work out from an error returned by `Database::new` what went wrong
+pub fn classify(error: &anyhow::Error) -> OpenFailureKind {
+    for cause in error.chain() {
+        if cause.downcast_ref::<SchemaTooNew>().is_some() {
+            return OpenFailureKind::SchemaTooNew;
+        }
+
+        if let Some(sqlx::Error::Database(db_error)) = cause.downcast_ref::<sqlx::Error>() {
+            let code = db_error.code().and_then(|code| code.parse::<i32>().ok());
+            if let Some(kind) = code.and_then(|code| classify_sqlite_code(code & 0xff)) {
+                return kind;
+            }
+        }
+
+        let io_error = match cause.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::Io(io_error)) => Some(io_error),
+            _ => cause.downcast_ref::<std::io::Error>(),
+        };
+        if let Some(io_error) = io_error {
+            return classify_io(io_error);
+        }
+    }
+
+    OpenFailureKind::Other
+}
+
+fn classify_sqlite_code(code: i32) -> Option<OpenFailureKind> {
+    match code {
+        SQLITE_BUSY | SQLITE_LOCKED => Some(OpenFailureKind::Locked),
+        SQLITE_CORRUPT | SQLITE_NOTADB => Some(OpenFailureKind::Corrupt),
+        SQLITE_FULL => Some(OpenFailureKind::DiskFull),
+        SQLITE_PERM | SQLITE_READONLY | SQLITE_CANTOPEN | SQLITE_AUTH => Some(OpenFailureKind::PermissionDenied),
+        _ => None,
+    }
+}
+
+fn classify_io(error: &std::io::Error) -> OpenFailureKind {
+    match error.kind() {
+        std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem => OpenFailureKind::PermissionDenied,
+        std::io::ErrorKind::StorageFull | std::io::ErrorKind::QuotaExceeded => OpenFailureKind::DiskFull,
+        _ => OpenFailureKind::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    #[test]
+    fn test_classify_sqlite_codes() {
+        assert_eq!(classify_sqlite_code(SQLITE_BUSY), Some(OpenFailureKind::Locked));
+        assert_eq!(classify_sqlite_code(SQLITE_NOTADB), Some(OpenFailureKind::Corrupt));
+        assert_eq!(classify_sqlite_code(SQLITE_FULL), Some(OpenFailureKind::DiskFull));
+        assert_eq!(classify_sqlite_code(SQLITE_READONLY), Some(OpenFailureKind::PermissionDenied));
+        assert_eq!(classify_sqlite_code(1), None);
+    }
+
+    #[test]
+    fn test_classify_wrapped_errors() {
+        let schema = anyhow::Error::from(SchemaTooNew { found: 99, supported: 4 });
+        assert_eq!(classify(&schema), OpenFailureKind::SchemaTooNew);
+
+        let denied = anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied)).context("creating dir");
+        assert_eq!(classify(&denied), OpenFailureKind::PermissionDenied);
+
+        assert_eq!(classify(&anyhow::anyhow!("something else")), OpenFailureKind::Other);
+    }
+
+    #[tokio::test]
+    async fn test_garbage_file_is_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("clipboard.db");
+        std::fs::write(&db_path, vec![0x42; 4096]).unwrap();
+
+        let error = Database::new(db_path).await.err().unwrap();
+        assert_eq!(classify(&error), OpenFailureKind::Corrupt);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_database_keeps_data() -> anyhow::Result<()> {
+        let db = Database::in_memory().await?;
+        let id = crate::database::insert_entry(db.pool(), crate::database::NewClipboardEntry::new_text("x".to_string())).await?;
+
+        // Every pool connection must see the same data
+        let handles: Vec<_> = (0..3)
+            .map(|_| {
+                let pool = db.pool().clone();
+                tokio::spawn(async move { crate::database::get_entry(&pool, id).await })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.await??.is_some());
+        }
+        Ok(())
+    }
+}
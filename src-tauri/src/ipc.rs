@@ -0,0 +1,483 @@
+// Local JSON-RPC server on a Unix-domain socket, for editor plugins and scripts.
+// Each request and response is one line of JSON-RPC 2.0:
+//
+//   → {"jsonrpc":"2.0","id":1,"method":"search","params":{"query":"foo","limit":5}}
+//   ← {"jsonrpc":"2.0","id":1,"result":[...]}
+//
+// Access control is the filesystem: the socket lives in a 0700 directory, is
+// itself 0600, and connections from other users are dropped.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::clipboard::ClipboardContent;
+use crate::database::{self, offload, ClipboardSearchParams, NewClipboardEntry};
+use crate::{cli, paste, settings, window};
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+// Requests larger than this are rejected instead of buffered
+const MAX_REQUEST_BYTES: usize = 1024 * 1024;
+
+/// Where the server listens: `$SUPERKBD_SOCKET`, else the user's runtime dir
+/// (Linux), else the app data dir (macOS)
+pub fn socket_path() -> Result<PathBuf> {
+    if let Ok(path) = std::env::var("SUPERKBD_SOCKET") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let dir = dirs::runtime_dir()
+        .map(|dir| dir.join("superkbd"))
+        .or_else(|| dirs::data_dir().map(|dir| dir.join(cli::APP_IDENTIFIER)))
+        .ok_or_else(|| anyhow!("Can't find a directory for the IPC socket"))?;
+
+    Ok(dir.join("superkbd.sock"))
+}
+
+/// What request handlers need from the running app
+#[derive(Clone)]
+pub struct IpcContext {
+    pub pool: Arc<SqlitePool>,
+    /// Where texts too large for the database are stored
+    pub blob_dir: PathBuf,
+    /// Used to notify the frontend; None in tests
+    pub app: Option<AppHandle>,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(SERVER_ERROR, e.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: Option<String>,
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    limit: Option<i64>,
+    #[serde(default)]
+    favorites_only: bool,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct IdParams {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct AddParams {
+    text: String,
+    source_app: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PinParams {
+    id: i64,
+    #[serde(default = "default_true")]
+    favorite: bool,
+}
+
+/// Either an existing entry or literal text
+#[derive(Deserialize)]
+struct PasteParams {
+    id: Option<i64>,
+    text: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Bind the socket and serve connections until the app exits
+pub async fn start(context: IpcContext) -> Result<()> {
+    let path = socket_path()?;
+    let listener = bind(&path).await?;
+    println!("✅ IPC server listening on {}", path.display());
+
+    let owner_uid = std::fs::metadata(&path)?.uid();
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("❌ [ERROR] IPC accept failed: {}", e);
+                continue;
+            }
+        };
+
+        // The socket is 0600 already; this also covers a loosened umask or chmod
+        match stream.peer_cred() {
+            Ok(cred) if cred.uid() == owner_uid => {}
+            _ => {
+                println!("⚠️  [WARNING] Rejected IPC connection from another user");
+                continue;
+            }
+        }
+
+        let context = context.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, context).await {
+                eprintln!("❌ [ERROR] IPC connection failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn bind(path: &Path) -> Result<UnixListener> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+        // Only lock down a directory we own exclusively, not e.g. /tmp
+        if parent.file_name().is_some_and(|name| name == "superkbd") {
+            std::fs::set_permissions(parent, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            bail!("Another SuperKBD instance is already listening on {}", path.display());
+        }
+        // Left over from a crash
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+async fn serve_connection(stream: UnixStream, context: IpcContext) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let read = (&mut reader).take(MAX_REQUEST_BYTES as u64 + 1).read_line(&mut line).await?;
+        if read == 0 {
+            return Ok(());
+        }
+
+        let response = if line.len() > MAX_REQUEST_BYTES {
+            error_response(Value::Null, RpcError::new(INVALID_REQUEST, "Request too large"))
+        } else if line.trim().is_empty() {
+            continue;
+        } else {
+            match handle_line(&context, &line).await {
+                Some(response) => response,
+                None => continue,
+            }
+        };
+
+        let mut bytes = serde_json::to_vec(&response)?;
+        bytes.push(b'\n');
+        writer.write_all(&bytes).await?;
+
+        if line.len() > MAX_REQUEST_BYTES {
+            return Ok(());
+        }
+    }
+}
+
+/// The response to one request line, or None for a notification (a request
+/// without an id), which is carried out without answering
+async fn handle_line(context: &IpcContext, line: &str) -> Option<Value> {
+    let request: Request = match serde_json::from_str::<Value>(line) {
+        Err(e) => return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))),
+        Ok(value) => match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(Value::Null, RpcError::new(INVALID_REQUEST, e.to_string()))),
+        },
+    };
+
+    let id = request.id.clone().unwrap_or(Value::Null);
+    if request.jsonrpc.as_deref() != Some("2.0") {
+        return Some(error_response(id, RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"")));
+    }
+
+    let result = dispatch(context, &request.method, request.params).await;
+    if request.id.is_none() {
+        if let Err(e) = result {
+            println!("⚠️  [WARNING] IPC notification {} failed: {}", request.method, e.message);
+        }
+        return None;
+    }
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => error_response(id, e),
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    // Methods without required params may be called with no params at all
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+async fn dispatch(context: &IpcContext, method: &str, raw_params: Value) -> Result<Value, RpcError> {
+    let pool = &context.pool;
+
+    match method {
+        "list" => {
+            let p: ListParams = params(raw_params)?;
+            let search = ClipboardSearchParams {
+                favorites_only: p.favorites_only,
+                limit: p.limit.unwrap_or_else(|| settings::current().history_limit),
+                ..Default::default()
+            };
+            Ok(json!(database::search_entries(pool, search).await?))
+        }
+        "search" => {
+            let p: SearchParams = params(raw_params)?;
+            let search = ClipboardSearchParams {
+                query: Some(p.query),
+                limit: p.limit.unwrap_or_else(|| settings::current().history_limit),
+                ..Default::default()
+            };
+            Ok(json!(database::search_entries(pool, search).await?))
+        }
+        "get" => {
+            let p: IdParams = params(raw_params)?;
+            let entry = database::get_entry(pool, p.id)
+                .await?
+                .ok_or_else(|| RpcError::new(SERVER_ERROR, format!("No entry with id {}", p.id)))?;
+            Ok(json!(entry))
+        }
+        "add" => {
+            let p: AddParams = params(raw_params)?;
+            if p.text.trim().is_empty() {
+                return Err(RpcError::new(INVALID_PARAMS, "text must not be empty"));
+            }
+
+            let current = settings::current();
+            if p.text.len() as u64 > current.max_capture_bytes {
+                return Err(RpcError::new(INVALID_PARAMS, format!("text is larger than {} bytes", current.max_capture_bytes)));
+            }
+
+            let content = ClipboardContent::new_text(offload::preview(&p.text).to_string());
+            let entry = NewClipboardEntry::new_text_with_source(p.text, p.source_app);
+            let id = offload::upsert_text(pool, &context.blob_dir, entry, current.offload_threshold_bytes).await?;
+
+            if let Some(app) = &context.app {
+                let _ = app.emit("clipboard-update", &content);
+            }
+            Ok(json!({ "id": id }))
+        }
+        "pin" => {
+            let p: PinParams = params(raw_params)?;
+            if !database::set_favorite(pool, p.id, p.favorite).await? {
+                return Err(RpcError::new(SERVER_ERROR, format!("No entry with id {}", p.id)));
+            }
+            Ok(json!({ "id": p.id, "favorite": p.favorite }))
+        }
+        "paste" => {
+            let p: PasteParams = params(raw_params)?;
+            let text = match (p.id, p.text) {
+                (Some(id), None) => database::get_entry_content(pool, id)
+                    .await?
+                    .ok_or_else(|| RpcError::new(SERVER_ERROR, format!("No text entry with id {}", id)))?,
+                (None, Some(text)) => text,
+                _ => return Err(RpcError::new(INVALID_PARAMS, "Pass exactly one of id or text")),
+            };
+
+            // The paste lands in the app in front; use its profile if it has one
+            let user_profiles = database::get_paste_profiles(pool).await?;
+            let options = match paste::profiles::find_profile(window::frontmost_app().as_deref(), &user_profiles) {
+                Some(profile) => paste::profiles::options_for_profile(&profile)?,
+                None => settings::current().paste_options(),
+            };
+
+            let outcome = paste::simulate_paste(text, &options).await?;
+            Ok(json!({ "pasted": outcome == paste::PasteOutcome::Pasted }))
+        }
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_context() -> Result<IpcContext> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await?;
+        database::migrations::run_migrations(&pool).await?;
+        Ok(IpcContext { pool: Arc::new(pool), blob_dir: std::env::temp_dir().join("superkbd-ipc-test-blobs"), app: None })
+    }
+
+    async fn call(context: &IpcContext, request: Value) -> Value {
+        handle_line(context, &request.to_string()).await.expect("Requests with an id are answered")
+    }
+
+    #[tokio::test]
+    async fn test_add_then_get_and_search() -> Result<()> {
+        let context = setup_context().await?;
+
+        let added = call(&context, json!({"jsonrpc": "2.0", "id": 1, "method": "add", "params": {"text": "from vim", "source_app": "nvim"}})).await;
+        assert_eq!(added["id"], 1);
+        let entry_id = added["result"]["id"].as_i64().unwrap();
+
+        let entry = call(&context, json!({"jsonrpc": "2.0", "id": 2, "method": "get", "params": {"id": entry_id}})).await;
+        assert_eq!(entry["result"]["text_content"], "from vim");
+        assert_eq!(entry["result"]["source_app"], "nvim");
+
+        let found = call(&context, json!({"jsonrpc": "2.0", "id": 3, "method": "search", "params": {"query": "vim"}})).await;
+        assert_eq!(found["result"].as_array().unwrap().len(), 1);
+
+        let listed = call(&context, json!({"jsonrpc": "2.0", "id": 4, "method": "list"})).await;
+        assert_eq!(listed["result"].as_array().unwrap().len(), 1, "list works without params");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pin() -> Result<()> {
+        let context = setup_context().await?;
+        let id = database::insert_entry(&context.pool, NewClipboardEntry::new_text("pin me".to_string())).await?;
+
+        let pinned = call(&context, json!({"jsonrpc": "2.0", "id": 1, "method": "pin", "params": {"id": id}})).await;
+        assert_eq!(pinned["result"]["favorite"], true);
+        assert!(database::get_entry(&context.pool, id).await?.unwrap().favorite);
+
+        let missing = call(&context, json!({"jsonrpc": "2.0", "id": 2, "method": "pin", "params": {"id": id + 1}})).await;
+        assert_eq!(missing["error"]["code"], SERVER_ERROR);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_notifications_are_not_answered() -> Result<()> {
+        let context = setup_context().await?;
+
+        let request = json!({"jsonrpc": "2.0", "method": "add", "params": {"text": "fire and forget"}});
+        assert!(handle_line(&context, &request.to_string()).await.is_none());
+        let found = call(&context, json!({"jsonrpc": "2.0", "id": 1, "method": "search", "params": {"query": "forget"}})).await;
+        assert_eq!(found["result"].as_array().unwrap().len(), 1, "The notification was still carried out");
+
+        // Not even with an error
+        let request = json!({"jsonrpc": "2.0", "method": "shutdown"});
+        assert!(handle_line(&context, &request.to_string()).await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors() -> Result<()> {
+        let context = setup_context().await?;
+
+        let response = handle_line(&context, "{not json").await.unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+
+        let response = call(&context, json!({"id": 1, "method": "list"})).await;
+        assert_eq!(response["error"]["code"], INVALID_REQUEST, "jsonrpc version is required");
+
+        let response = call(&context, json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown"})).await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let response = call(&context, json!({"jsonrpc": "2.0", "id": 1, "method": "get", "params": {"id": "x"}})).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let response = call(&context, json!({"jsonrpc": "2.0", "id": 1, "method": "add", "params": {"text": "  "}})).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let response = call(&context, json!({"jsonrpc": "2.0", "id": 1, "method": "paste", "params": {}})).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS, "paste needs an id or text");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_socket_roundtrip_and_permissions() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("superkbd").join("superkbd.sock");
+        let context = setup_context().await?;
+
+        let listener = bind(&path).await?;
+        let mode = std::fs::metadata(&path)?.permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        let dir_mode = std::fs::metadata(path.parent().unwrap())?.permissions().mode() & 0o777;
+        assert_eq!(dir_mode, 0o700);
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve_connection(stream, context).await.unwrap();
+        });
+
+        let stream = UnixStream::connect(&path).await?;
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(b"{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"list\"}\n").await?;
+
+        let mut response = String::new();
+        BufReader::new(reader).read_line(&mut response).await?;
+        let response: Value = serde_json::from_str(&response)?;
+        assert_eq!(response["id"], 7);
+        assert!(response["result"].is_array());
+
+        drop(writer);
+        server.await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stale_socket_is_replaced() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("superkbd.sock");
+
+        // A socket file nobody listens on, as left behind by a crash
+        drop(std::os::unix::net::UnixListener::bind(&path)?);
+        assert!(path.exists());
+        let _listener = bind(&path).await?;
+
+        // A live listener is not taken over
+        assert!(bind(&path).await.is_err());
+
+        Ok(())
+    }
+}
@@ -1,23 +1,39 @@
+mod actions;
+mod calculator;
+pub mod cli;
 mod clipboard;
 mod commands;
 mod database;
+#[cfg(unix)]
+mod ipc;
 mod paste;
+mod protocol;
+mod qr;
+mod settings;
+mod shortcuts;
+mod sync;
+mod transfer;
 mod window;
 
-use clipboard::ClipboardMonitor;
+use clipboard::{thumbnail, ClipboardMonitor};
 use commands::AppState;
 use database::Database;
 use std::sync::Arc;
 use tauri::tray::{TrayIconBuilder, TrayIconEvent, MouseButton, MouseButtonState};
 use tauri::menu::{Menu, MenuItem};
 use tauri::{Emitter, Manager};
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .register_asynchronous_uri_scheme_protocol(protocol::SCHEME, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                responder.respond(protocol::handle(&app, request).await);
+            });
+        })
         .setup(|app| {
             // Get app data directory
             let app_data_dir = app
@@ -25,11 +41,59 @@ pub fn run() {
                 .app_data_dir()
                 .expect("Failed to get app data directory");
 
-            // Initialize database
+            // Initialize database, swapping in a restored backup first
             let db_path = app_data_dir.join("clipboard.db");
-            let db = tauri::async_runtime::block_on(async {
-                Database::new(db_path).await.expect("Failed to initialize database")
+            let backup_dir = app_data_dir.join("backups");
+            match database::backup::apply_pending_restore(&db_path) {
+                Ok(true) => println!("✅ Restored database from backup"),
+                Ok(false) => {}
+                Err(e) => eprintln!("❌ [ERROR] Failed to apply restored backup: {}", e),
+            }
+            let problems = tauri::async_runtime::block_on(database::backup::startup_check(&db_path));
+
+            // If it is damaged or won't open, run on an in-memory database and
+            // let the user decide in the recovery window
+            let opened = if problems.is_empty() {
+                tauri::async_runtime::block_on(Database::new(db_path.clone())).map_err(|e| (database::recovery::classify(&e), format!("{:#}", e)))
+            } else {
+                Err((database::recovery::OpenFailureKind::Corrupt, problems.join("; ")))
+            };
+            let db = match opened {
+                Ok(db) => db,
+                Err((kind, message)) => {
+                    eprintln!("❌ [ERROR] Failed to open database ({:?}): {}", kind, message);
+                    database::recovery::set_startup_failure(database::recovery::OpenFailure {
+                        kind,
+                        message,
+                        db_path: db_path.clone(),
+                    });
+                    tauri::async_runtime::block_on(Database::in_memory()).expect("Failed to create in-memory database")
+                }
+            };
+
+            // Load settings before anything that depends on them starts
+            let loaded_settings = tauri::async_runtime::block_on(settings::load(db.pool())).unwrap_or_else(|e| {
+                eprintln!("❌ [ERROR] Failed to load settings, using defaults: {}", e);
+                settings::Settings::default()
             });
+            settings::publish(loaded_settings.clone());
+
+            // Connection settings live in the database itself, so non-default
+            // ones need a second open
+            let db = if loaded_settings.database != database::DatabaseOptions::default() && !database::recovery::is_memory_only() {
+                match tauri::async_runtime::block_on(Database::open(db_path.clone(), &loaded_settings.database)) {
+                    Ok(tuned) => {
+                        tauri::async_runtime::block_on(db.pool().close());
+                        tuned
+                    }
+                    Err(e) => {
+                        eprintln!("❌ [ERROR] Database options not applied, using defaults: {}", e);
+                        db
+                    }
+                }
+            } else {
+                db
+            };
 
             let pool = Arc::new(db.pool().clone());
 
@@ -38,8 +102,87 @@ pub fn run() {
                 pool: Arc::clone(&pool),
             });
 
+            // Apply the retention period
+            if loaded_settings.retention_days > 0 {
+                let pool = Arc::clone(&pool);
+                let days = loaded_settings.retention_days;
+                tauri::async_runtime::spawn(async move {
+                    match database::delete_old_entries(&pool, days).await {
+                        Ok(deleted) if deleted > 0 => println!("🔧 [DEBUG] Removed {} entries older than {} days", deleted, days),
+                        Ok(_) => {}
+                        Err(e) => eprintln!("❌ [ERROR] Retention cleanup failed: {}", e),
+                    }
+                });
+            }
+
+            // Thumbnails for images that arrived while the app wasn't running
+            // or whose generation was interrupted
+            thumbnail::spawn_pending(Arc::clone(&pool));
+
+            // Back up the database periodically
+            if !database::recovery::is_memory_only() {
+                let pool = Arc::clone(&pool);
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        let current = settings::current();
+                        let interval = std::time::Duration::from_secs(current.backup_interval_hours * 3600);
+                        if current.backup_interval_hours > 0 && database::backup::backup_due(&backup_dir, interval) {
+                            match database::backup::create_backup(&pool, &backup_dir, current.backup_keep).await {
+                                Ok(backup) => println!("🔧 [DEBUG] Database backed up to {}", backup.file_name),
+                                Err(e) => eprintln!("❌ [ERROR] Database backup failed: {}", e),
+                            }
+                        }
+                        tokio::time::sleep(std::time::Duration::from_secs(15 * 60)).await;
+                    }
+                });
+            }
+
+            // Exchange changes with other devices through the sync folder
+            if !database::recovery::is_memory_only() {
+                let pool = Arc::clone(&pool);
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = sync::configure(&pool, &settings::current().sync).await {
+                        eprintln!("❌ [ERROR] Failed to set up sync: {}", e);
+                    }
+                    loop {
+                        let current = settings::current().sync;
+                        if current.enabled {
+                            match sync::sync_now(&pool, &current).await {
+                                Ok(report) => {
+                                    for error in &report.errors {
+                                        println!("⚠️  [WARNING] Skipped sync log {}", error);
+                                    }
+                                    if report.applied > 0 {
+                                        println!("🔧 [DEBUG] Synced {} changes from other devices", report.applied);
+                                        let _ = app_handle.emit("history-synced", &report);
+                                    }
+                                }
+                                Err(e) => eprintln!("❌ [ERROR] Sync failed: {}", e),
+                            }
+                        }
+                        tokio::time::sleep(std::time::Duration::from_secs(current.interval_secs)).await;
+                    }
+                });
+            }
+
+            // Serve local tools (editor plugins, scripts) over a Unix socket
+            #[cfg(unix)]
+            {
+                let context = ipc::IpcContext {
+                    pool: Arc::clone(&pool),
+                    blob_dir: app_data_dir.join("blobs"),
+                    app: Some(app.handle().clone()),
+                };
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = ipc::start(context).await {
+                        eprintln!("❌ [ERROR] IPC server not started: {}", e);
+                    }
+                });
+            }
+
             // Start clipboard monitoring
-            let monitor = ClipboardMonitor::new(Arc::clone(&pool));
+            let monitor = ClipboardMonitor::new(Arc::clone(&pool), app_data_dir.join("blobs"));
             monitor.start(app.handle().clone());
 
             // Check accessibility permissions on startup
@@ -62,28 +205,24 @@ pub fn run() {
                 });
             }
 
-            // Register global shortcut using the working pattern from demo
-            let alt_i_shortcut = Shortcut::new(Some(Modifiers::ALT), Code::KeyI);
-            let app_handle_for_shortcut = app.handle().clone();
-
+            // Register the user's global shortcuts (Alt+I for the history by default)
             app.handle().plugin(
                 tauri_plugin_global_shortcut::Builder::new()
-                    .with_handler(move |_app, shortcut, _event| {
-                        if shortcut == &alt_i_shortcut {
-                            println!("Alt+I pressed - showing window");
-                            let _ = window::show_window(&app_handle_for_shortcut);
-                        }
-                    })
+                    .with_handler(shortcuts::handle_event)
                     .build(),
             )?;
 
-            app.global_shortcut().register(alt_i_shortcut)?;
+            shortcuts::register_all(app.handle(), &loaded_settings.shortcuts);
 
             // Setup window event handlers
             if let Some(window) = app.get_webview_window("main") {
                 window::setup_window_events(&window);
             }
 
+            if database::recovery::is_memory_only() {
+                window::show_recovery_window(app.handle())?;
+            }
+
             // Setup system tray
             let show_item = MenuItem::with_id(app, "show", "Show Clipboard History", true, None::<&str>)?;
             let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
@@ -122,11 +261,41 @@ pub fn run() {
             commands::get_clipboard_history,
             commands::toggle_favorite,
             commands::delete_clipboard_entry,
+            commands::set_entry_tags,
+            commands::get_highlighted,
+            commands::get_entry_content,
+            commands::get_paste_stack,
+            commands::add_to_paste_stack,
+            commands::remove_from_paste_stack,
+            commands::clear_paste_stack,
+            commands::render_qr,
+            commands::list_actions,
+            commands::run_action,
             commands::paste_and_close,
             commands::copy_to_clipboard_only,
+            commands::get_paste_profiles,
+            commands::save_paste_profile,
+            commands::delete_paste_profile,
             commands::check_accessibility_permission,
+            commands::diagnose_paste,
+            commands::get_shortcuts,
+            commands::set_shortcut,
+            commands::set_recording_paused,
+            commands::is_recording_paused,
             commands::open_accessibility_settings,
             commands::cleanup_old_entries,
+            commands::export_history,
+            commands::import_history,
+            commands::import_from_manager,
+            commands::list_backups,
+            commands::create_backup,
+            commands::restore_backup,
+            commands::sync_now,
+            commands::get_startup_failure,
+            commands::recover_database,
+            commands::get_ocr_status,
+            commands::get_settings,
+            commands::update_settings,
             commands::hide_window_command,
         ])
         .run(tauri::generate_context!())
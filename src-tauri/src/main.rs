@@ -2,5 +2,11 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // `superkbd list`, `superkbd get 42`, ... work on the history without starting the app
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = superkbd_lib::cli::run(&args) {
+        std::process::exit(code);
+    }
+
     superkbd_lib::run()
 }
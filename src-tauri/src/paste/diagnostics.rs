@@ -0,0 +1,284 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::KeyInjector;
+
+// How many paste errors to keep for the report
+const MAX_RECENT_ERRORS: usize = 10;
+
+static LAST_PASTE_LATENCY_MS: Mutex<Option<u64>> = Mutex::new(None);
+static RECENT_ERRORS: Mutex<VecDeque<PasteError>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayServer {
+    MacOS,
+    Windows,
+    X11,
+    Wayland,
+    /// No graphical session (e.g. Linux without DISPLAY/WAYLAND_DISPLAY)
+    None,
+}
+
+/// How focus gets back to the target app before the paste keystroke
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusRestore {
+    /// `open -a <app>` activates the app that was frontmost before the picker
+    OpenApp,
+    /// Hiding our window lets the window manager refocus the previous window
+    WindowManager,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PasteError {
+    pub at: i64,
+    pub message: String,
+}
+
+/// Everything that determines whether auto-paste can work in this session
+#[derive(Debug, Clone, Serialize)]
+pub struct PasteDiagnostics {
+    pub display_server: DisplayServer,
+    pub clipboard_backend: String,
+    pub clipboard_available: bool,
+    pub clipboard_error: Option<String>,
+    pub key_injector: KeyInjector,
+    pub key_injection_works: bool,
+    /// macOS only: whether the process is trusted for accessibility
+    pub accessibility_trusted: Option<bool>,
+    pub focus_restore: FocusRestore,
+    pub last_paste_latency_ms: Option<u64>,
+    pub recent_errors: Vec<PasteError>,
+    /// Human-readable next steps for whatever is broken
+    pub hints: Vec<String>,
+}
+
+pub fn record_paste_latency(latency_ms: u64) {
+    if let Ok(mut last) = LAST_PASTE_LATENCY_MS.lock() {
+        *last = Some(latency_ms);
+    }
+}
+
+pub fn record_paste_error(message: &str) {
+    if let Ok(mut errors) = RECENT_ERRORS.lock() {
+        push_error(&mut errors, message.to_string(), chrono::Utc::now().timestamp());
+    }
+}
+
+fn push_error(errors: &mut VecDeque<PasteError>, message: String, at: i64) {
+    errors.push_back(PasteError { at, message });
+    while errors.len() > MAX_RECENT_ERRORS {
+        errors.pop_front();
+    }
+}
+
+pub fn display_server() -> DisplayServer {
+    if cfg!(target_os = "macos") {
+        DisplayServer::MacOS
+    } else if cfg!(target_os = "windows") {
+        DisplayServer::Windows
+    } else {
+        unix_display_server()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn unix_display_server() -> DisplayServer {
+    linux_display_server(
+        super::wayland::is_wayland_session(),
+        std::env::var("DISPLAY").ok().as_deref(),
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unix_display_server() -> DisplayServer {
+    DisplayServer::None
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn linux_display_server(wayland_session: bool, display: Option<&str>) -> DisplayServer {
+    if wayland_session {
+        DisplayServer::Wayland
+    } else if display.is_some_and(|display| !display.is_empty()) {
+        DisplayServer::X11
+    } else {
+        DisplayServer::None
+    }
+}
+
+/// Whether macOS has granted us accessibility access. Unlike creating an
+/// Enigo instance, this notices when the permission is revoked.
+#[cfg(target_os = "macos")]
+pub fn accessibility_trusted() -> bool {
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+    }
+
+    unsafe { AXIsProcessTrusted() }
+}
+
+fn clipboard_backend(display_server: DisplayServer) -> &'static str {
+    match display_server {
+        DisplayServer::MacOS => "NSPasteboard",
+        DisplayServer::Windows => "Win32 clipboard",
+        DisplayServer::X11 => "X11 selections",
+        // arboard is built without wayland-data-control, so it goes through XWayland
+        DisplayServer::Wayland => "X11 selections (XWayland)",
+        DisplayServer::None => "none",
+    }
+}
+
+/// Build the full report. Probes the clipboard and injection tools, so call it
+/// off the main thread.
+pub fn diagnose() -> PasteDiagnostics {
+    let display_server = display_server();
+
+    let (clipboard_available, clipboard_error) = match arboard::Clipboard::new() {
+        Ok(_) => (true, None),
+        Err(e) => (false, Some(e.to_string())),
+    };
+
+    let key_injector = super::detect_key_injector();
+
+    #[cfg(target_os = "macos")]
+    let accessibility_trusted = Some(accessibility_trusted());
+
+    #[cfg(not(target_os = "macos"))]
+    let accessibility_trusted = None;
+
+    let key_injection_works = key_injector != KeyInjector::Unavailable && accessibility_trusted != Some(false);
+
+    let focus_restore = if cfg!(target_os = "macos") {
+        FocusRestore::OpenApp
+    } else {
+        FocusRestore::WindowManager
+    };
+
+    let last_paste_latency_ms = LAST_PASTE_LATENCY_MS.lock().ok().and_then(|last| *last);
+    let recent_errors = RECENT_ERRORS
+        .lock()
+        .map(|errors| errors.iter().cloned().collect())
+        .unwrap_or_default();
+
+    let mut report = PasteDiagnostics {
+        display_server,
+        clipboard_backend: clipboard_backend(display_server).to_string(),
+        clipboard_available,
+        clipboard_error,
+        key_injector,
+        key_injection_works,
+        accessibility_trusted,
+        focus_restore,
+        last_paste_latency_ms,
+        recent_errors,
+        hints: Vec::new(),
+    };
+    report.hints = hints(&report);
+    report
+}
+
+fn hints(report: &PasteDiagnostics) -> Vec<String> {
+    let mut hints = Vec::new();
+
+    if report.display_server == DisplayServer::None {
+        hints.push("No graphical session found. Set DISPLAY or WAYLAND_DISPLAY, or run SuperKBD inside your desktop session.".to_string());
+    }
+
+    if !report.clipboard_available {
+        hints.push("The clipboard can't be opened, so nothing can be pasted or recorded.".to_string());
+    }
+
+    if report.accessibility_trusted == Some(false) {
+        hints.push("Grant SuperKBD access in System Settings > Privacy & Security > Accessibility, then restart it.".to_string());
+    }
+
+    if report.key_injector == KeyInjector::Unavailable {
+        match report.display_server {
+            DisplayServer::Wayland => hints.push(
+                "Install wtype (wlroots compositors, KDE) or run ydotoold (GNOME) to enable auto-paste. Until then entries are only copied.".to_string(),
+            ),
+            DisplayServer::X11 => hints.push(
+                "Keystrokes can't be sent to the X server. Check that the XTEST extension is enabled.".to_string(),
+            ),
+            _ => {}
+        }
+    }
+
+    if report.display_server == DisplayServer::Wayland && report.key_injector == KeyInjector::Ydotool {
+        hints.push("ydotool sends raw keycodes, so paste shortcuts assume a US keyboard layout.".to_string());
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(display_server: DisplayServer, key_injector: KeyInjector) -> PasteDiagnostics {
+        PasteDiagnostics {
+            display_server,
+            clipboard_backend: clipboard_backend(display_server).to_string(),
+            clipboard_available: true,
+            clipboard_error: None,
+            key_injector,
+            key_injection_works: key_injector != KeyInjector::Unavailable,
+            accessibility_trusted: None,
+            focus_restore: FocusRestore::WindowManager,
+            last_paste_latency_ms: None,
+            recent_errors: Vec::new(),
+            hints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_linux_display_server() {
+        assert_eq!(linux_display_server(true, Some(":0")), DisplayServer::Wayland);
+        assert_eq!(linux_display_server(false, Some(":0")), DisplayServer::X11);
+        assert_eq!(linux_display_server(false, Some("")), DisplayServer::None);
+        assert_eq!(linux_display_server(false, None), DisplayServer::None);
+    }
+
+    #[test]
+    fn test_recent_errors_are_capped() {
+        let mut errors = VecDeque::new();
+        for i in 0..(MAX_RECENT_ERRORS + 3) {
+            push_error(&mut errors, format!("error {}", i), i as i64);
+        }
+
+        assert_eq!(errors.len(), MAX_RECENT_ERRORS);
+        assert_eq!(errors.front().unwrap().message, "error 3", "Oldest errors are dropped first");
+    }
+
+    #[test]
+    fn test_healthy_session_has_no_hints() {
+        assert!(hints(&report(DisplayServer::X11, KeyInjector::Enigo)).is_empty());
+    }
+
+    #[test]
+    fn test_wayland_without_injector_hint() {
+        let hints = hints(&report(DisplayServer::Wayland, KeyInjector::Unavailable));
+        assert_eq!(hints.len(), 1);
+        assert!(hints[0].contains("wtype"));
+    }
+
+    #[test]
+    fn test_revoked_accessibility_hint() {
+        let mut report = report(DisplayServer::MacOS, KeyInjector::CgEvent);
+        report.accessibility_trusted = Some(false);
+
+        let hints = hints(&report);
+        assert!(hints.iter().any(|hint| hint.contains("Accessibility")));
+    }
+
+    #[test]
+    fn test_headless_hint() {
+        let mut report = report(DisplayServer::None, KeyInjector::Unavailable);
+        report.clipboard_available = false;
+
+        assert_eq!(hints(&report).len(), 2);
+    }
+}
@@ -0,0 +1,645 @@
+pub mod diagnostics;
+#[cfg(target_os = "linux")]
+pub mod owner;
+pub mod profiles;
+pub mod stack;
+pub mod typing;
+#[cfg(target_os = "linux")]
+pub mod wayland;
+
+use anyhow::Result;
+use enigo::{Enigo, Keyboard, Settings};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use profiles::KeyChord;
+
+// Delay between putting the entry on the clipboard (and activating the target
+// app) and sending the paste shortcut
+#[cfg(target_os = "macos")]
+pub const DEFAULT_PRE_DELAY_MS: u64 = 30;
+
+#[cfg(not(target_os = "macos"))]
+pub const DEFAULT_PRE_DELAY_MS: u64 = 60;
+
+// How long to wait after the paste keystroke before restoring the old clipboard.
+// The target app reads the clipboard asynchronously, so restoring too early
+// would paste the old contents instead.
+pub const DEFAULT_POST_DELAY_MS: u64 = 200;
+
+/// How keystrokes are sent to other apps in this session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyInjector {
+    /// Core Graphics events (macOS)
+    CgEvent,
+    /// enigo (Windows, Linux X11)
+    Enigo,
+    /// `wtype` via the Wayland virtual-keyboard protocol
+    Wtype,
+    /// `ydotool` via /dev/uinput
+    Ydotool,
+    /// Nothing works; pastes fall back to copy-only
+    Unavailable,
+}
+
+static KEY_INJECTOR: OnceLock<KeyInjector> = OnceLock::new();
+
+/// The injector for this session, detected once on first use
+pub fn key_injector() -> KeyInjector {
+    *KEY_INJECTOR.get_or_init(detect_key_injector)
+}
+
+/// Probe which injection method works right now
+pub fn detect_key_injector() -> KeyInjector {
+    #[cfg(target_os = "macos")]
+    {
+        KeyInjector::CgEvent
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        #[cfg(target_os = "linux")]
+        if wayland::is_wayland_session() {
+            return wayland::detect_injector();
+        }
+
+        // enigo connects fine to a server without XTEST, then every
+        // keystroke it sends is silently dropped
+        #[cfg(target_os = "linux")]
+        if !xtest_available(None) {
+            return KeyInjector::Unavailable;
+        }
+
+        if enigo_available() {
+            KeyInjector::Enigo
+        } else {
+            KeyInjector::Unavailable
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn enigo_available() -> bool {
+    // Try to create Enigo instance - if it fails, we don't have permissions
+    match std::panic::catch_unwind(|| {
+        Enigo::new(&Settings::default())
+    }) {
+        Ok(Ok(_)) => true,
+        _ => false,
+    }
+}
+
+/// Whether the X server `display` (the session's when None) can be reached
+/// and has the XTEST extension enigo injects keystrokes through
+#[cfg(target_os = "linux")]
+fn xtest_available(display: Option<&str>) -> bool {
+    use x11rb::protocol::xproto::ConnectionExt;
+
+    let present = x11rb::connect(display)
+        .map_err(anyhow::Error::from)
+        .and_then(|(conn, _)| Ok(conn.query_extension(b"XTEST")?.reply()?.present));
+    match present {
+        Ok(present) => present,
+        Err(e) => {
+            println!("🔧 [DEBUG] Can't query the X server for XTEST: {}", e);
+            false
+        }
+    }
+}
+
+/// Check if we have accessibility permissions
+/// Returns true if we can use keyboard simulation
+pub fn check_accessibility_permission() -> bool {
+    // Creating Enigo succeeds even after the permission has been revoked
+    #[cfg(target_os = "macos")]
+    {
+        diagnostics::accessibility_trusted()
+    }
+
+    // On Wayland creating Enigo succeeds even though it can't reach native windows
+    #[cfg(target_os = "linux")]
+    {
+        detect_key_injector() != KeyInjector::Unavailable
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        enigo_available()
+    }
+}
+
+/// Run `f` with a keyboard for the session's injector. Blocking; call from
+/// `spawn_blocking`.
+fn with_keyboard<F>(f: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn Keyboard) -> Result<()>,
+{
+    match key_injector() {
+        #[cfg(target_os = "linux")]
+        injector @ (KeyInjector::Wtype | KeyInjector::Ydotool) => {
+            let mut keyboard = wayland::ToolKeyboard::new(injector)?;
+            f(&mut keyboard)?;
+            keyboard.flush()
+        }
+        KeyInjector::Unavailable => Err(anyhow::anyhow!("No way to send keystrokes in this session")),
+        _ => {
+            let mut enigo = Enigo::new(&Settings::default())
+                .map_err(|e| anyhow::anyhow!("Failed to initialize keyboard simulator: {}", e))?;
+            f(&mut enigo)
+        }
+    }
+}
+
+/// What a paste request actually did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteOutcome {
+    Pasted,
+    /// Keystrokes can't be injected, so the entry was only put on the clipboard
+    CopiedOnly,
+}
+
+/// How an entry is delivered to the target app
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteStrategy {
+    /// Put the entry on the clipboard and send the paste shortcut
+    #[default]
+    Clipboard,
+    /// Type the entry as keystrokes, for apps that block clipboard paste
+    Type,
+}
+
+impl PasteStrategy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "clipboard" => Some(Self::Clipboard),
+            "type" => Some(Self::Type),
+            _ => None,
+        }
+    }
+}
+
+/// Options that control how a single paste is delivered
+#[derive(Debug, Clone)]
+pub struct PasteOptions {
+    /// Put the user's previous clipboard contents back once the paste is delivered
+    pub restore_clipboard: bool,
+    pub strategy: PasteStrategy,
+    /// Typing speed limit for `PasteStrategy::Type` in characters per second (0 = unlimited)
+    pub type_rate: u32,
+    pub paste_keys: KeyChord,
+    pub pre_delay_ms: u64,
+    /// Wait after the paste keystroke before restoring the clipboard
+    pub post_delay_ms: u64,
+}
+
+impl Default for PasteOptions {
+    fn default() -> Self {
+        Self {
+            restore_clipboard: false,
+            strategy: PasteStrategy::default(),
+            type_rate: 0,
+            paste_keys: KeyChord::default(),
+            pre_delay_ms: DEFAULT_PRE_DELAY_MS,
+            post_delay_ms: DEFAULT_POST_DELAY_MS,
+        }
+    }
+}
+
+/// Everything that was on the clipboard before we overwrote it, in all the
+/// flavors arboard can read back
+#[derive(Default)]
+pub struct ClipboardSnapshot {
+    text: Option<String>,
+    html: Option<String>,
+    image: Option<arboard::ImageData<'static>>,
+    files: Option<Vec<std::path::PathBuf>>,
+}
+
+impl ClipboardSnapshot {
+    pub fn capture(clipboard: &mut arboard::Clipboard) -> Self {
+        Self {
+            text: clipboard.get_text().ok(),
+            html: clipboard.get().html().ok(),
+            image: clipboard.get_image().ok(),
+            files: clipboard.get().file_list().ok().filter(|files| !files.is_empty()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_none() && self.html.is_none() && self.image.is_none() && self.files.is_none()
+    }
+
+    /// Write the snapshot back. On X11 every captured flavor is served
+    /// together by `owner::serve`. Elsewhere, and when there's no X server,
+    /// arboard can only write one flavor (HTML with its text), so the richest
+    /// one that was present wins.
+    pub fn restore(self, clipboard: &mut arboard::Clipboard) -> Result<()> {
+        if self.is_empty() {
+            clipboard.clear()?;
+            return Ok(());
+        }
+
+        if let Some(text) = &self.text {
+            crate::clipboard::ignore_self_write(text);
+        }
+
+        #[cfg(target_os = "linux")]
+        let Some(this) = self.restore_x11() else {
+            return Ok(());
+        };
+        #[cfg(not(target_os = "linux"))]
+        let this = self;
+
+        if let Some(files) = this.files {
+            clipboard.set().file_list(&files)?;
+        } else if let Some(image) = this.image {
+            clipboard.set_image(image)?;
+        } else if let Some(html) = this.html {
+            clipboard.set_html(html, this.text)?;
+        } else if let Some(text) = this.text {
+            clipboard.set_text(text)?;
+        }
+
+        Ok(())
+    }
+
+    /// Serve all flavors as the X11 clipboard owner. Hands the snapshot back
+    /// when that isn't possible, for arboard to write instead.
+    #[cfg(target_os = "linux")]
+    fn restore_x11(self) -> Option<Self> {
+        let png = match &self.image {
+            Some(image) => match encode_png(image) {
+                Ok(png) => Some(png),
+                Err(e) => {
+                    println!("⚠️  [WARNING] Couldn't encode the clipboard image: {}", e);
+                    return Some(self);
+                }
+            },
+            None => None,
+        };
+        let flavors = owner::Flavors {
+            text: self.text.clone(),
+            html: self.html.clone(),
+            png,
+            files: self.files.clone(),
+        };
+        match owner::serve(flavors) {
+            Ok(()) => None,
+            Err(e) => {
+                println!("⚠️  [WARNING] Restoring one clipboard flavor only: {}", e);
+                Some(self)
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn encode_png(image: &arboard::ImageData) -> Result<Vec<u8>> {
+    let rgba = image::RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.to_vec())
+        .ok_or_else(|| anyhow::anyhow!("Image data doesn't match its size"))?;
+    let mut png = Vec::new();
+    rgba.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Copy `content` to the clipboard, returning a snapshot of what was there
+/// before if the caller asked for it to be restored
+fn write_paste_content(content: &str, options: &PasteOptions) -> Result<(arboard::Clipboard, Option<ClipboardSnapshot>)> {
+    let mut clipboard = arboard::Clipboard::new()?;
+
+    let snapshot = if options.restore_clipboard {
+        Some(ClipboardSnapshot::capture(&mut clipboard))
+    } else {
+        None
+    };
+
+    crate::clipboard::ignore_self_write(content);
+    clipboard.set_text(content.to_string())?;
+
+    Ok((clipboard, snapshot))
+}
+
+async fn restore_after_paste(
+    mut clipboard: arboard::Clipboard,
+    snapshot: Option<ClipboardSnapshot>,
+    options: &PasteOptions,
+) -> Result<()> {
+    if let Some(snapshot) = snapshot {
+        tokio::time::sleep(Duration::from_millis(options.post_delay_ms)).await;
+        snapshot.restore(&mut clipboard)?;
+        println!("✅ Original clipboard restored");
+    }
+    Ok(())
+}
+
+/// Wait until no modifier key is held, so an injected chord isn't combined
+/// with the keys of the shortcut that triggered it. Only X11 can be asked;
+/// elsewhere this returns right away.
+pub async fn wait_for_modifiers_released(timeout: Duration) {
+    #[cfg(target_os = "linux")]
+    {
+        let result = tokio::task::spawn_blocking(move || -> Result<()> {
+            use x11rb::connection::Connection;
+            use x11rb::protocol::xproto::{ConnectionExt, KeyButMask};
+
+            let (conn, screen_num) = x11rb::connect(None)?;
+            let root = conn.setup().roots[screen_num].root;
+            let held = KeyButMask::SHIFT | KeyButMask::CONTROL | KeyButMask::MOD1 | KeyButMask::MOD4;
+            let deadline = std::time::Instant::now() + timeout;
+            while conn.query_pointer(root)?.reply()?.mask.intersects(held) {
+                if std::time::Instant::now() >= deadline {
+                    println!("⚠️  [WARNING] Modifier keys still held, pasting anyway");
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            Ok(())
+        })
+        .await;
+
+        if let Ok(Err(e)) = result {
+            println!("🔧 [DEBUG] Can't read modifier state: {}", e);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    let _ = timeout;
+}
+
+/// Type the content into the focused app instead of pasting it
+pub async fn type_out(content: String, options: &PasteOptions) -> Result<()> {
+    println!("🔧 [DEBUG] Typing {} characters...", content.chars().count());
+
+    let chars_per_second = options.type_rate;
+    let pre_delay = Duration::from_millis(options.pre_delay_ms);
+
+    let result = tokio::task::spawn_blocking(move || -> Result<()> {
+        // Give the target app a moment to take focus
+        thread::sleep(pre_delay);
+
+        with_keyboard(|keyboard| typing::type_text(keyboard, &content, chars_per_second, thread::sleep))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            println!("✅ Type-out completed successfully!");
+            Ok(())
+        }
+        Ok(Err(e)) => {
+            eprintln!("❌ [ERROR] Typing failed: {}", e);
+            Err(e)
+        }
+        Err(e) => Err(anyhow::anyhow!("Failed to spawn blocking task: {}", e)),
+    }
+}
+
+pub async fn simulate_paste(content: String, options: &PasteOptions) -> Result<PasteOutcome> {
+    if key_injector() == KeyInjector::Unavailable {
+        println!("⚠️  [WARNING] Keystroke injection unavailable - copying only");
+        crate::clipboard::ignore_self_write(&content);
+        arboard::Clipboard::new()?.set_text(content)?;
+        return Ok(PasteOutcome::CopiedOnly);
+    }
+
+    if options.strategy == PasteStrategy::Type {
+        type_out(content, options).await?;
+        return Ok(PasteOutcome::Pasted);
+    }
+
+    // First, copy to clipboard
+    let (clipboard, snapshot) = write_paste_content(&content, options)?;
+
+    println!("✅ Content copied to clipboard: {}", content);
+
+    // Let the clipboard update and the target app take focus
+    tokio::time::sleep(Duration::from_millis(options.pre_delay_ms)).await;
+
+    send_paste_keystroke(&options.paste_keys).await?;
+
+    restore_after_paste(clipboard, snapshot, options).await?;
+    Ok(PasteOutcome::Pasted)
+}
+
+async fn send_paste_keystroke(chord: &KeyChord) -> Result<()> {
+    println!("🔧 [DEBUG] Starting keyboard simulation for {}...", chord);
+
+    // Use platform-specific paste simulation
+    #[cfg(target_os = "macos")]
+    {
+        // On macOS, use CGEvent for fast keyboard simulation
+        // This is much faster than AppleScript
+        println!("🔧 [DEBUG] Using CGEvent to simulate {}...", chord);
+
+        use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation, CGKeyCode};
+        use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+        use profiles::ChordModifier;
+
+        // Create event source
+        let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+            .map_err(|_| anyhow::anyhow!("Failed to create event source"))?;
+
+        let keycode: CGKeyCode = chord
+            .key
+            .mac_keycode()
+            .ok_or_else(|| anyhow::anyhow!("No macOS key code for {}", chord))?;
+
+        let mut flags = CGEventFlags::empty();
+        for modifier in &chord.modifiers {
+            flags |= match modifier {
+                ChordModifier::Ctrl => CGEventFlags::CGEventFlagControl,
+                ChordModifier::Shift => CGEventFlags::CGEventFlagShift,
+                ChordModifier::Alt => CGEventFlags::CGEventFlagAlternate,
+                ChordModifier::Meta => CGEventFlags::CGEventFlagCommand,
+            };
+        }
+
+        // Create key down event with the chord's modifiers
+        let key_down = CGEvent::new_keyboard_event(source.clone(), keycode, true)
+            .map_err(|_| anyhow::anyhow!("Failed to create key down event"))?;
+        key_down.set_flags(flags);
+
+        // Create key up event with the chord's modifiers
+        let key_up = CGEvent::new_keyboard_event(source.clone(), keycode, false)
+            .map_err(|_| anyhow::anyhow!("Failed to create key up event"))?;
+        key_up.set_flags(flags);
+
+        // Post events
+        key_down.post(CGEventTapLocation::HID);
+        key_up.post(CGEventTapLocation::HID);
+
+        println!("✅ Auto-paste completed successfully!");
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        // On Windows/Linux, use enigo (or wtype/ydotool on Wayland)
+        println!("🔧 [DEBUG] Using {:?} for paste simulation...", key_injector());
+
+        let chord = chord.clone();
+
+        // Spawn blocking task for keyboard simulation
+        let result = tokio::task::spawn_blocking(move || -> Result<()> {
+            with_keyboard(|keyboard| profiles::press_chord(keyboard, &chord))?;
+
+            println!("✅ Auto-paste completed successfully!");
+            Ok(())
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => {
+                eprintln!("❌ [ERROR] Keyboard simulation failed: {}", e);
+                Err(e)
+            }
+            Err(e) => {
+                eprintln!("❌ [ERROR] Task spawn failed: {}", e);
+                Err(anyhow::anyhow!("Failed to spawn blocking task: {}", e))
+            }
+        }
+    }
+}
+
+/// Fast paste with immediate app switching (Raycast-style)
+pub async fn simulate_paste_with_app_switch(
+    content: String,
+    target_app: Option<String>,
+    options: &PasteOptions,
+    start: std::time::Instant,
+) -> Result<PasteOutcome> {
+    #[cfg(target_os = "macos")]
+    {
+        use std::process::Command;
+
+        // Typing doesn't touch the clipboard at all
+        let typing = options.strategy == PasteStrategy::Type;
+
+        // First, copy to clipboard
+        let (clipboard, snapshot) = if typing {
+            (None, None)
+        } else {
+            let (clipboard, snapshot) = write_paste_content(&content, options)?;
+            println!("🔧 [PERF] T+{}ms: Content copied to clipboard", start.elapsed().as_millis());
+            (Some(clipboard), snapshot)
+        };
+
+        // If we have a target app, activate it using 'open -a' (faster than AppleScript)
+        if let Some(app_name) = target_app {
+            println!("🔧 [PERF] T+{}ms: Starting to activate app with 'open -a': {}", start.elapsed().as_millis(), app_name);
+
+            let activate_start = std::time::Instant::now();
+
+            // Use 'open -a' instead of AppleScript - it's faster
+            let _ = Command::new("open")
+                .arg("-a")
+                .arg(&app_name)
+                .spawn(); // Use spawn instead of output to not wait for completion
+
+            println!("🔧 [PERF] T+{}ms: 'open -a' command sent (took {}ms)",
+                start.elapsed().as_millis(), activate_start.elapsed().as_millis());
+
+            // Short delay to let app start receiving focus
+            tokio::time::sleep(Duration::from_millis(options.pre_delay_ms)).await;
+            println!("🔧 [PERF] T+{}ms: After {}ms delay", start.elapsed().as_millis(), options.pre_delay_ms);
+        }
+
+        if typing {
+            // Focus was already given time above
+            let options = PasteOptions {
+                pre_delay_ms: 0,
+                ..options.clone()
+            };
+            type_out(content, &options).await?;
+            return Ok(PasteOutcome::Pasted);
+        }
+
+        println!("🔧 [PERF] T+{}ms: Posting {}...", start.elapsed().as_millis(), options.paste_keys);
+        send_paste_keystroke(&options.paste_keys).await?;
+        println!("🔧 [PERF] T+{}ms: Paste keystroke posted", start.elapsed().as_millis());
+
+        if let Some(clipboard) = clipboard {
+            restore_after_paste(clipboard, snapshot, options).await?;
+        }
+        Ok(PasteOutcome::Pasted)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (target_app, start);
+        // Fall back to regular paste for non-macOS
+        simulate_paste(content, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modifier_key_is_correct() {
+        use profiles::ChordModifier;
+
+        let chord = KeyChord::default();
+
+        #[cfg(target_os = "macos")]
+        assert_eq!(chord.modifiers, vec![ChordModifier::Meta]);
+
+        #[cfg(target_os = "windows")]
+        assert_eq!(chord.modifiers, vec![ChordModifier::Ctrl]);
+
+        #[cfg(target_os = "linux")]
+        assert_eq!(chord.modifiers, vec![ChordModifier::Ctrl]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_xtest_needs_a_reachable_server() {
+        assert!(!xtest_available(Some(":4242")));
+    }
+
+    // Needs an X server: xvfb-run -a cargo test --lib paste -- --ignored
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "needs an X server"]
+    fn test_xtest_is_found_on_x11() {
+        assert!(xtest_available(None));
+        assert_eq!(detect_key_injector(), KeyInjector::Enigo);
+    }
+
+    #[test]
+    fn test_parse_strategy() {
+        assert_eq!(PasteStrategy::parse("clipboard"), Some(PasteStrategy::Clipboard));
+        assert_eq!(PasteStrategy::parse("Type"), Some(PasteStrategy::Type));
+        assert_eq!(PasteStrategy::parse("paste"), None);
+    }
+
+    #[test]
+    fn test_paste_options_default_keeps_clipboard() {
+        // Restoring is opt-in; the default matches the old behaviour
+        assert!(!PasteOptions::default().restore_clipboard);
+    }
+
+    #[test]
+    fn test_empty_snapshot() {
+        assert!(ClipboardSnapshot::default().is_empty());
+
+        let snapshot = ClipboardSnapshot {
+            text: Some("previous".to_string()),
+            ..Default::default()
+        };
+        assert!(!snapshot.is_empty());
+    }
+
+    #[test]
+    fn test_paste_function_signature() {
+        // Just verify the function exists and compiles
+        // Actual paste simulation is hard to test automatically
+        assert!(true);
+    }
+}
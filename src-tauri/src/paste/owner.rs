@@ -0,0 +1,285 @@
+// Serving several clipboard flavors at once on X11. arboard finishes a write
+// with a single flavor (HTML can bring its plain-text alternative), so a copy
+// of an image with a caption or of files would lose its text when restored.
+// Here we own the CLIPBOARD selection ourselves and answer every target the
+// snapshot had, from a thread that runs until another app takes it over.
+
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt, CreateWindowAux, EventMask, PropMode, Property,
+    SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+/// Larger values are sent in chunks of this size (the INCR protocol)
+const INCR_CHUNK: usize = 256 * 1024;
+
+/// Clipboard contents to serve, each flavor under all the targets apps ask for
+#[derive(Debug, Default)]
+pub struct Flavors {
+    pub text: Option<String>,
+    pub html: Option<String>,
+    pub png: Option<Vec<u8>>,
+    pub files: Option<Vec<PathBuf>>,
+}
+
+impl Flavors {
+    /// (target name, data) pairs, richest first
+    pub fn targets(&self) -> Vec<(&'static str, Vec<u8>)> {
+        let mut targets = Vec::new();
+        if let Some(files) = &self.files {
+            let uris: Vec<String> = files.iter().map(|path| file_uri(path)).collect();
+            targets.push(("text/uri-list", format!("{}\r\n", uris.join("\r\n")).into_bytes()));
+            targets.push(("x-special/gnome-copied-files", format!("copy\n{}", uris.join("\n")).into_bytes()));
+        }
+        if let Some(png) = &self.png {
+            targets.push(("image/png", png.clone()));
+        }
+        if let Some(html) = &self.html {
+            targets.push(("text/html", html.clone().into_bytes()));
+        }
+        if let Some(text) = &self.text {
+            for name in ["UTF8_STRING", "text/plain;charset=utf-8", "text/plain", "STRING", "TEXT"] {
+                targets.push((name, text.clone().into_bytes()));
+            }
+        }
+        targets
+    }
+}
+
+/// `file://` URI of an absolute path, with everything but unreserved
+/// characters and slashes percent-encoded
+pub fn file_uri(path: &std::path::Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Become the CLIPBOARD owner and serve `flavors` in the background. Fails
+/// without an X server or when the selection couldn't be taken, so the caller
+/// can fall back to arboard.
+pub fn serve(flavors: Flavors) -> Result<()> {
+    let (conn, screen_num) = x11rb::connect(None)?;
+    let root = conn.setup().roots[screen_num].root;
+    let window = conn.generate_id()?;
+    conn.create_window(
+        x11rb::COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_ONLY,
+        x11rb::COPY_FROM_PARENT,
+        &CreateWindowAux::new(),
+    )?;
+
+    let intern = |name: &str| -> Result<Atom> { Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom) };
+    let clipboard = intern("CLIPBOARD")?;
+    let atoms = Atoms { targets: intern("TARGETS")?, incr: intern("INCR")? };
+    let mut data = HashMap::new();
+    for (name, value) in flavors.targets() {
+        data.insert(intern(name)?, value);
+    }
+
+    conn.set_selection_owner(window, clipboard, x11rb::CURRENT_TIME)?;
+    if conn.get_selection_owner(clipboard)?.reply()?.owner != window {
+        bail!("Couldn't take over the clipboard");
+    }
+    conn.flush()?;
+
+    let owner = Owner { conn, window, clipboard, atoms, data, transfers: Vec::new() };
+    std::thread::Builder::new()
+        .name("clipboard-owner".to_string())
+        .spawn(move || {
+            if let Err(e) = owner.run() {
+                println!("⚠️  [WARNING] Stopped serving the restored clipboard: {}", e);
+            }
+        })?;
+    Ok(())
+}
+
+struct Atoms {
+    targets: Atom,
+    incr: Atom,
+}
+
+/// A value being sent in chunks, continued each time the requestor deletes
+/// the property
+struct Transfer {
+    requestor: Window,
+    property: Atom,
+    target: Atom,
+    data: Vec<u8>,
+    sent: usize,
+}
+
+struct Owner {
+    conn: RustConnection,
+    window: Window,
+    clipboard: Atom,
+    atoms: Atoms,
+    data: HashMap<Atom, Vec<u8>>,
+    transfers: Vec<Transfer>,
+}
+
+impl Owner {
+    /// Answer requests until another app owns the clipboard and every
+    /// chunked transfer has finished
+    fn run(mut self) -> Result<()> {
+        let mut owned = true;
+        while owned || !self.transfers.is_empty() {
+            match self.conn.wait_for_event()? {
+                Event::SelectionRequest(request) if owned => self.answer(request)?,
+                Event::SelectionClear(event) if event.selection == self.clipboard => owned = false,
+                Event::PropertyNotify(event) if event.state == Property::DELETE => {
+                    self.continue_transfer(event.window, event.atom)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn answer(&mut self, request: SelectionRequestEvent) -> Result<()> {
+        // Obsolete clients leave the property out and expect the target's name
+        let property = if request.property == x11rb::NONE { request.target } else { request.property };
+        let conn = &self.conn;
+
+        let served = if request.selection != self.clipboard {
+            false
+        } else if request.target == self.atoms.targets {
+            let mut targets: Vec<Atom> = vec![self.atoms.targets];
+            targets.extend(self.data.keys());
+            conn.change_property32(PropMode::REPLACE, request.requestor, property, AtomEnum::ATOM, &targets)?;
+            true
+        } else if let Some(data) = self.data.get(&request.target) {
+            if data.len() > INCR_CHUNK {
+                conn.change_window_attributes(
+                    request.requestor,
+                    &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+                )?;
+                conn.change_property32(PropMode::REPLACE, request.requestor, property, self.atoms.incr, &[data.len() as u32])?;
+                self.transfers.push(Transfer {
+                    requestor: request.requestor,
+                    property,
+                    target: request.target,
+                    data: data.clone(),
+                    sent: 0,
+                });
+            } else {
+                conn.change_property8(PropMode::REPLACE, request.requestor, property, request.target, data)?;
+            }
+            true
+        } else {
+            false
+        };
+
+        let notify = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property: if served { property } else { x11rb::NONE },
+        };
+        conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+        conn.flush()?;
+        Ok(())
+    }
+
+    /// Send the next chunk once the requestor has read the last one. An
+    /// empty chunk ends the transfer.
+    fn continue_transfer(&mut self, requestor: Window, property: Atom) -> Result<()> {
+        let Some(index) = self
+            .transfers
+            .iter()
+            .position(|transfer| transfer.requestor == requestor && transfer.property == property)
+        else {
+            return Ok(());
+        };
+
+        let transfer = &mut self.transfers[index];
+        let end = (transfer.sent + INCR_CHUNK).min(transfer.data.len());
+        let chunk = &transfer.data[transfer.sent..end];
+        self.conn
+            .change_property8(PropMode::REPLACE, requestor, property, transfer.target, chunk)?;
+        if chunk.is_empty() {
+            self.transfers.remove(index);
+            self.conn
+                .change_window_attributes(requestor, &ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT))?;
+        } else {
+            transfer.sent = end;
+        }
+        self.conn.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Owner {
+    fn drop(&mut self) {
+        let _ = self.conn.destroy_window(self.window);
+        let _ = self.conn.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_file_uri_escapes_reserved_characters() {
+        assert_eq!(file_uri(Path::new("/home/me/notes.txt")), "file:///home/me/notes.txt");
+        assert_eq!(file_uri(Path::new("/tmp/a b#1%.png")), "file:///tmp/a%20b%231%25.png");
+        assert_eq!(file_uri(Path::new("/tmp/é")), "file:///tmp/%C3%A9");
+    }
+
+    #[test]
+    fn test_every_flavor_is_offered() {
+        let flavors = Flavors {
+            text: Some("caption".to_string()),
+            png: Some(vec![0x89, b'P', b'N', b'G']),
+            files: Some(vec![PathBuf::from("/tmp/a.png"), PathBuf::from("/tmp/b.png")]),
+            ..Default::default()
+        };
+        let targets: HashMap<_, _> = flavors.targets().into_iter().collect();
+        assert_eq!(targets["text/uri-list"], b"file:///tmp/a.png\r\nfile:///tmp/b.png\r\n");
+        assert_eq!(targets["x-special/gnome-copied-files"], b"copy\nfile:///tmp/a.png\nfile:///tmp/b.png");
+        assert_eq!(targets["image/png"], vec![0x89, b'P', b'N', b'G']);
+        assert_eq!(targets["UTF8_STRING"], b"caption");
+        assert!(!targets.contains_key("text/html"));
+    }
+
+    // Needs an X server: xvfb-run cargo test --lib paste::owner -- --ignored
+    #[test]
+    #[ignore = "needs an X server"]
+    fn test_image_and_text_are_both_served() -> Result<()> {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(3, 2, image::Rgba([255, 0, 0, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+        let large = "x".repeat(INCR_CHUNK * 3 + 17);
+        serve(Flavors { text: Some(large.clone()), png: Some(png), ..Default::default() })?;
+
+        let mut clipboard = arboard::Clipboard::new()?;
+        assert_eq!(clipboard.get_text()?, large, "Sent in chunks");
+        let image = clipboard.get_image()?;
+        assert_eq!((image.width, image.height), (3, 2));
+        Ok(())
+    }
+}
@@ -0,0 +1,394 @@
+use anyhow::Result;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{PasteOptions, PasteStrategy, DEFAULT_POST_DELAY_MS, DEFAULT_PRE_DELAY_MS};
+use crate::database::PasteProfile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChordModifier {
+    Ctrl,
+    Shift,
+    Alt,
+    /// Cmd on macOS, Super/Windows key elsewhere
+    Meta,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChordKey {
+    Char(char),
+    Insert,
+}
+
+/// A paste shortcut such as `Ctrl+Shift+V` or `Shift+Insert`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyChord {
+    pub modifiers: Vec<ChordModifier>,
+    pub key: ChordKey,
+}
+
+impl Default for KeyChord {
+    /// The platform's standard paste shortcut
+    fn default() -> Self {
+        #[cfg(target_os = "macos")]
+        let modifier = ChordModifier::Meta;
+
+        #[cfg(not(target_os = "macos"))]
+        let modifier = ChordModifier::Ctrl;
+
+        Self {
+            modifiers: vec![modifier],
+            key: ChordKey::Char('v'),
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = Vec::new();
+        let mut key = None;
+
+        for part in s.split('+').map(str::trim) {
+            let modifier = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Some(ChordModifier::Ctrl),
+                "shift" => Some(ChordModifier::Shift),
+                "alt" | "option" => Some(ChordModifier::Alt),
+                "cmd" | "command" | "meta" | "super" => Some(ChordModifier::Meta),
+                _ => None,
+            };
+
+            if let Some(modifier) = modifier {
+                if key.is_some() {
+                    return Err(anyhow::anyhow!("Modifier '{}' must come before the key in '{}'", part, s));
+                }
+                if !modifiers.contains(&modifier) {
+                    modifiers.push(modifier);
+                }
+                continue;
+            }
+
+            if key.is_some() {
+                return Err(anyhow::anyhow!("Only one non-modifier key is allowed in '{}'", s));
+            }
+
+            let mut chars = part.chars();
+            key = match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_alphanumeric() => Some(ChordKey::Char(c.to_ascii_lowercase())),
+                _ if part.eq_ignore_ascii_case("insert") || part.eq_ignore_ascii_case("ins") => Some(ChordKey::Insert),
+                _ => return Err(anyhow::anyhow!("Unsupported key '{}' in '{}'", part, s)),
+            };
+        }
+
+        let key = key.ok_or_else(|| anyhow::anyhow!("No key given in '{}'", s))?;
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            let name = match modifier {
+                ChordModifier::Ctrl => "Ctrl",
+                ChordModifier::Shift => "Shift",
+                ChordModifier::Alt => "Alt",
+                ChordModifier::Meta => "Cmd",
+            };
+            write!(f, "{}+", name)?;
+        }
+        match self.key {
+            ChordKey::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            ChordKey::Insert => write!(f, "Insert"),
+        }
+    }
+}
+
+impl ChordKey {
+    /// ANSI virtual keycode used by CGEvent
+    #[cfg(target_os = "macos")]
+    pub fn mac_keycode(&self) -> Option<u16> {
+        let code = match self {
+            // macOS has no Insert key; Help sits in the same spot
+            ChordKey::Insert => 114,
+            ChordKey::Char(c) => match c {
+                'a' => 0, 's' => 1, 'd' => 2, 'f' => 3, 'h' => 4, 'g' => 5, 'z' => 6,
+                'x' => 7, 'c' => 8, 'v' => 9, 'b' => 11, 'q' => 12, 'w' => 13, 'e' => 14,
+                'r' => 15, 'y' => 16, 't' => 17, '1' => 18, '2' => 19, '3' => 20, '4' => 21,
+                '6' => 22, '5' => 23, '9' => 25, '7' => 26, '8' => 28, '0' => 29, 'o' => 31,
+                'u' => 32, 'i' => 34, 'p' => 35, 'l' => 37, 'j' => 38, 'k' => 40, 'n' => 45,
+                'm' => 46,
+                _ => return None,
+            },
+        };
+        Some(code)
+    }
+}
+
+/// Press the chord through enigo: modifiers down, key click, modifiers up in reverse
+#[cfg(not(target_os = "macos"))]
+pub fn press_chord<K: enigo::Keyboard + ?Sized>(keyboard: &mut K, chord: &KeyChord) -> Result<()> {
+    use enigo::{Direction, Key};
+
+    let modifier_key = |modifier: &ChordModifier| match modifier {
+        ChordModifier::Ctrl => Key::Control,
+        ChordModifier::Shift => Key::Shift,
+        ChordModifier::Alt => Key::Alt,
+        ChordModifier::Meta => Key::Meta,
+    };
+
+    for modifier in &chord.modifiers {
+        keyboard
+            .key(modifier_key(modifier), Direction::Press)
+            .map_err(|e| anyhow::anyhow!("Failed to press modifier key: {}", e))?;
+    }
+
+    let key = match chord.key {
+        ChordKey::Char(c) => Key::Unicode(c),
+        ChordKey::Insert => Key::Insert,
+    };
+    keyboard
+        .key(key, Direction::Click)
+        .map_err(|e| anyhow::anyhow!("Failed to click {}: {}", chord, e))?;
+
+    for modifier in chord.modifiers.iter().rev() {
+        keyboard
+            .key(modifier_key(modifier), Direction::Release)
+            .map_err(|e| anyhow::anyhow!("Failed to release modifier key: {}", e))?;
+    }
+
+    Ok(())
+}
+
+impl Default for PasteProfile {
+    fn default() -> Self {
+        Self {
+            app_name: String::new(),
+            paste_keys: KeyChord::default().to_string(),
+            pre_delay_ms: DEFAULT_PRE_DELAY_MS as i64,
+            post_delay_ms: DEFAULT_POST_DELAY_MS as i64,
+            restore_clipboard: false,
+            strategy: "clipboard".to_string(),
+            type_rate: 0,
+        }
+    }
+}
+
+/// Built-in profiles for apps that don't accept the standard paste shortcut.
+/// Users can override any of these with their own profile for the same app.
+pub fn default_profiles() -> Vec<PasteProfile> {
+    let terminal = |app_name: &str, paste_keys: &str| PasteProfile {
+        app_name: app_name.to_string(),
+        paste_keys: paste_keys.to_string(),
+        ..PasteProfile::default()
+    };
+
+    vec![
+        // Linux terminals reserve Ctrl+V for literal input
+        terminal("Gnome-terminal", "Ctrl+Shift+V"),
+        terminal("konsole", "Ctrl+Shift+V"),
+        terminal("Alacritty", "Ctrl+Shift+V"),
+        terminal("kitty", "Ctrl+Shift+V"),
+        terminal("org.wezfurlong.wezterm", "Ctrl+Shift+V"),
+        terminal("Tilix", "Ctrl+Shift+V"),
+        terminal("Terminator", "Ctrl+Shift+V"),
+        terminal("Xfce4-terminal", "Ctrl+Shift+V"),
+        terminal("foot", "Ctrl+Shift+V"),
+        // xterm-style terminals only paste the clipboard with Shift+Insert
+        terminal("XTerm", "Shift+Insert"),
+        terminal("URxvt", "Shift+Insert"),
+        // Electron apps can be slow to take focus after we hide the picker
+        PasteProfile {
+            app_name: "Slack".to_string(),
+            pre_delay_ms: 120,
+            ..PasteProfile::default()
+        },
+        PasteProfile {
+            app_name: "Discord".to_string(),
+            pre_delay_ms: 120,
+            ..PasteProfile::default()
+        },
+    ]
+}
+
+/// The profile that applies to `app_name`: a user profile if one exists,
+/// otherwise a built-in default
+pub fn find_profile(app_name: Option<&str>, user_profiles: &[PasteProfile]) -> Option<PasteProfile> {
+    let app_name = app_name?;
+
+    user_profiles
+        .iter()
+        .find(|profile| profile.app_name.eq_ignore_ascii_case(app_name))
+        .cloned()
+        .or_else(|| {
+            default_profiles()
+                .into_iter()
+                .find(|profile| profile.app_name.eq_ignore_ascii_case(app_name))
+        })
+}
+
+/// All effective profiles: the built-in defaults with user profiles applied on top
+pub fn merged_profiles(user_profiles: &[PasteProfile]) -> Vec<PasteProfile> {
+    let mut profiles: Vec<PasteProfile> = default_profiles()
+        .into_iter()
+        .filter(|default| {
+            !user_profiles
+                .iter()
+                .any(|user| user.app_name.eq_ignore_ascii_case(&default.app_name))
+        })
+        .collect();
+
+    profiles.extend(user_profiles.iter().cloned());
+    profiles.sort_by_key(|profile| profile.app_name.to_lowercase());
+    profiles
+}
+
+/// Check a profile before saving it
+pub fn validate_profile(profile: &PasteProfile) -> Result<()> {
+    if profile.app_name.trim().is_empty() {
+        return Err(anyhow::anyhow!("App name must not be empty"));
+    }
+    options_for_profile(profile).map(|_| ())
+}
+
+/// Turn a stored profile into the options used for a paste
+pub fn options_for_profile(profile: &PasteProfile) -> Result<PasteOptions> {
+    let strategy = PasteStrategy::parse(&profile.strategy)
+        .ok_or_else(|| anyhow::anyhow!("Unknown paste strategy '{}'", profile.strategy))?;
+
+    if profile.pre_delay_ms < 0 || profile.post_delay_ms < 0 || profile.type_rate < 0 {
+        return Err(anyhow::anyhow!("Delays and type rate must not be negative"));
+    }
+
+    Ok(PasteOptions {
+        restore_clipboard: profile.restore_clipboard,
+        strategy,
+        type_rate: profile.type_rate as u32,
+        paste_keys: profile.paste_keys.parse()?,
+        pre_delay_ms: profile.pre_delay_ms as u64,
+        post_delay_ms: profile.post_delay_ms as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_chords() -> Result<()> {
+        let chord: KeyChord = "Ctrl+Shift+V".parse()?;
+        assert_eq!(chord.modifiers, vec![ChordModifier::Ctrl, ChordModifier::Shift]);
+        assert_eq!(chord.key, ChordKey::Char('v'));
+
+        let chord: KeyChord = "shift + insert".parse()?;
+        assert_eq!(chord.modifiers, vec![ChordModifier::Shift]);
+        assert_eq!(chord.key, ChordKey::Insert);
+
+        let chord: KeyChord = "Cmd+V".parse()?;
+        assert_eq!(chord.to_string(), "Cmd+V");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_invalid_chords() {
+        assert!("Ctrl+Shift".parse::<KeyChord>().is_err(), "Needs a key");
+        assert!("Ctrl+V+C".parse::<KeyChord>().is_err(), "Only one key");
+        assert!("V+Ctrl".parse::<KeyChord>().is_err(), "Modifiers come first");
+        assert!("Ctrl+F13".parse::<KeyChord>().is_err(), "Unsupported key");
+        assert!("".parse::<KeyChord>().is_err());
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_press_chord_order() -> Result<()> {
+        let mut keyboard = super::super::typing::RecordingKeyboard::default();
+        press_chord(&mut keyboard, &"Ctrl+Shift+V".parse()?)?;
+
+        assert_eq!(
+            keyboard.events,
+            vec![
+                "key:Control:Press",
+                "key:Shift:Press",
+                "key:Unicode('v'):Click",
+                "key:Shift:Release",
+                "key:Control:Release",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_profiles_are_valid() {
+        for profile in default_profiles() {
+            assert!(validate_profile(&profile).is_ok(), "Invalid default profile: {}", profile.app_name);
+        }
+    }
+
+    #[test]
+    fn test_user_profile_overrides_default() -> Result<()> {
+        let user = PasteProfile {
+            app_name: "kitty".to_string(),
+            paste_keys: "Ctrl+V".to_string(),
+            ..PasteProfile::default()
+        };
+
+        let profile = find_profile(Some("Kitty"), std::slice::from_ref(&user)).unwrap();
+        assert_eq!(profile.paste_keys, "Ctrl+V");
+
+        let profile = find_profile(Some("kitty"), &[]).unwrap();
+        assert_eq!(profile.paste_keys, "Ctrl+Shift+V");
+
+        assert!(find_profile(Some("Some Editor"), &[]).is_none());
+        assert!(find_profile(None, &[user]).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merged_profiles_replace_defaults() {
+        let user = PasteProfile {
+            app_name: "XTERM".to_string(),
+            strategy: "type".to_string(),
+            ..PasteProfile::default()
+        };
+
+        let merged = merged_profiles(&[user]);
+        let xterms: Vec<_> = merged
+            .iter()
+            .filter(|p| p.app_name.eq_ignore_ascii_case("xterm"))
+            .collect();
+
+        assert_eq!(xterms.len(), 1);
+        assert_eq!(xterms[0].strategy, "type");
+        assert_eq!(merged.len(), default_profiles().len());
+    }
+
+    #[test]
+    fn test_options_for_profile() -> Result<()> {
+        let profile = PasteProfile {
+            app_name: "Remote Desktop".to_string(),
+            paste_keys: "Ctrl+Shift+V".to_string(),
+            pre_delay_ms: 100,
+            post_delay_ms: 300,
+            restore_clipboard: true,
+            strategy: "type".to_string(),
+            type_rate: 40,
+        };
+
+        let options = options_for_profile(&profile)?;
+        assert_eq!(options.strategy, PasteStrategy::Type);
+        assert_eq!(options.type_rate, 40);
+        assert_eq!(options.pre_delay_ms, 100);
+        assert_eq!(options.post_delay_ms, 300);
+        assert!(options.restore_clipboard);
+
+        let bad = PasteProfile {
+            strategy: "teleport".to_string(),
+            ..profile
+        };
+        assert!(options_for_profile(&bad).is_err());
+
+        Ok(())
+    }
+}
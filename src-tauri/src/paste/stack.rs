@@ -0,0 +1,89 @@
+// The paste stack: entries queued up to be pasted one after another, e.g. to
+// fill in a form field by field. Pasting an entry from the stack takes it
+// off. It only lives for the session.
+
+use std::sync::Mutex;
+
+static STACK: Mutex<PasteStack> = Mutex::new(PasteStack::new());
+
+/// Entry ids in the order they will be pasted
+#[derive(Debug, Default)]
+pub struct PasteStack {
+    ids: Vec<i64>,
+}
+
+impl PasteStack {
+    pub const fn new() -> Self {
+        Self { ids: Vec::new() }
+    }
+
+    /// Queue an entry after the ones already on the stack. An entry is only
+    /// on it once.
+    pub fn push(&mut self, id: i64) {
+        if !self.ids.contains(&id) {
+            self.ids.push(id);
+        }
+    }
+
+    pub fn remove(&mut self, id: i64) -> bool {
+        let before = self.ids.len();
+        self.ids.retain(|queued| *queued != id);
+        self.ids.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+    }
+
+    pub fn ids(&self) -> Vec<i64> {
+        self.ids.clone()
+    }
+}
+
+fn with_stack<T>(f: impl FnOnce(&mut PasteStack) -> T) -> T {
+    f(&mut STACK.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+pub fn push(id: i64) -> Vec<i64> {
+    with_stack(|stack| {
+        stack.push(id);
+        stack.ids()
+    })
+}
+
+pub fn remove(id: i64) -> Vec<i64> {
+    with_stack(|stack| {
+        stack.remove(id);
+        stack.ids()
+    })
+}
+
+pub fn clear() {
+    with_stack(PasteStack::clear);
+}
+
+pub fn ids() -> Vec<i64> {
+    with_stack(|stack| stack.ids())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_pasted_in_the_order_they_were_added() {
+        let mut stack = PasteStack::new();
+        stack.push(3);
+        stack.push(1);
+        stack.push(3);
+        stack.push(2);
+        assert_eq!(stack.ids(), vec![3, 1, 2], "Adding an entry twice keeps its place");
+
+        assert!(stack.remove(3));
+        assert!(!stack.remove(3));
+        assert_eq!(stack.ids(), vec![1, 2]);
+
+        stack.clear();
+        assert!(stack.ids().is_empty());
+    }
+}
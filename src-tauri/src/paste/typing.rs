@@ -0,0 +1,289 @@
+use anyhow::Result;
+use enigo::{Direction, Key, Keyboard};
+use std::time::Duration;
+
+// Characters sent per `text()` call when a rate limit is set. Small chunks keep
+// the typing smooth without paying a sleep per character.
+const CHUNK_SIZE: usize = 16;
+
+/// Type `text` as keystrokes instead of pasting it.
+///
+/// Newlines and tabs are sent as Return/Tab key presses so they behave the same
+/// in terminals and text fields. `chars_per_second` limits the typing speed
+/// (0 means as fast as possible); `sleep` is called between chunks so tests
+/// can run without real delays.
+pub fn type_text<K, S>(keyboard: &mut K, text: &str, chars_per_second: u32, mut sleep: S) -> Result<()>
+where
+    K: Keyboard + ?Sized,
+    S: FnMut(Duration),
+{
+    // Normalize Windows line endings so "\r\n" doesn't produce two Returns
+    let text = text.replace("\r\n", "\n");
+
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            keyboard
+                .key(Key::Return, Direction::Click)
+                .map_err(|e| anyhow::anyhow!("Failed to press Return: {}", e))?;
+        }
+
+        for (j, segment) in line.split('\t').enumerate() {
+            if j > 0 {
+                keyboard
+                    .key(Key::Tab, Direction::Click)
+                    .map_err(|e| anyhow::anyhow!("Failed to press Tab: {}", e))?;
+            }
+
+            type_segment(keyboard, segment, chars_per_second, &mut sleep)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn type_segment<K, S>(keyboard: &mut K, segment: &str, chars_per_second: u32, sleep: &mut S) -> Result<()>
+where
+    K: Keyboard + ?Sized,
+    S: FnMut(Duration),
+{
+    if segment.is_empty() {
+        return Ok(());
+    }
+
+    if chars_per_second == 0 {
+        return keyboard
+            .text(segment)
+            .map_err(|e| anyhow::anyhow!("Failed to type text: {}", e));
+    }
+
+    let chars: Vec<char> = segment.chars().collect();
+    for chunk in chars.chunks(CHUNK_SIZE) {
+        let chunk: String = chunk.iter().collect();
+        keyboard
+            .text(&chunk)
+            .map_err(|e| anyhow::anyhow!("Failed to type text: {}", e))?;
+
+        sleep(chunk_delay(chunk.chars().count(), chars_per_second));
+    }
+
+    Ok(())
+}
+
+/// Time to wait after typing `chars` characters to stay under the rate limit
+pub fn chunk_delay(chars: usize, chars_per_second: u32) -> Duration {
+    if chars_per_second == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(chars as u64 * 1000 / chars_per_second as u64)
+}
+
+/// Keystroke capture harness: records what would have been sent to the
+/// display server, so typing can be tested without X or xdotool
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct RecordingKeyboard {
+    pub events: Vec<String>,
+}
+
+#[cfg(test)]
+impl Keyboard for RecordingKeyboard {
+    fn fast_text(&mut self, text: &str) -> enigo::InputResult<Option<()>> {
+        self.events.push(format!("text:{}", text));
+        Ok(Some(()))
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> enigo::InputResult<()> {
+        self.events.push(format!("key:{:?}:{:?}", key, direction));
+        Ok(())
+    }
+
+    fn raw(&mut self, keycode: u16, direction: Direction) -> enigo::InputResult<()> {
+        self.events.push(format!("raw:{}:{:?}", keycode, direction));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_types_plain_text() -> Result<()> {
+        let mut keyboard = RecordingKeyboard::default();
+        type_text(&mut keyboard, "hello", 0, |_| {})?;
+
+        assert_eq!(keyboard.events, vec!["text:hello"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_newlines_and_tabs_become_keys() -> Result<()> {
+        let mut keyboard = RecordingKeyboard::default();
+        type_text(&mut keyboard, "a\tb\r\nc\n", 0, |_| {})?;
+
+        assert_eq!(
+            keyboard.events,
+            vec![
+                "text:a",
+                "key:Tab:Click",
+                "text:b",
+                "key:Return:Click",
+                "text:c",
+                "key:Return:Click",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unicode_is_typed_intact() -> Result<()> {
+        let mut keyboard = RecordingKeyboard::default();
+        type_text(&mut keyboard, "héllo 世界 ❤️", 0, |_| {})?;
+
+        assert_eq!(keyboard.events, vec!["text:héllo 世界 ❤️"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_limit_chunks_and_sleeps() -> Result<()> {
+        let mut keyboard = RecordingKeyboard::default();
+        let mut slept = Duration::ZERO;
+        let text = "x".repeat(CHUNK_SIZE * 2 + 4);
+
+        type_text(&mut keyboard, &text, 100, |d| slept += d)?;
+
+        assert_eq!(keyboard.events.len(), 3, "Should type in three chunks");
+        // 36 chars at 100 chars/s
+        assert_eq!(slept, Duration::from_millis(360));
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunks_split_on_char_boundaries() -> Result<()> {
+        let mut keyboard = RecordingKeyboard::default();
+        let text = "世".repeat(CHUNK_SIZE + 1);
+
+        type_text(&mut keyboard, &text, 1000, |_| {})?;
+
+        assert_eq!(keyboard.events[1], "text:世");
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunk_delay() {
+        assert_eq!(chunk_delay(10, 0), Duration::ZERO);
+        assert_eq!(chunk_delay(16, 80), Duration::from_millis(200));
+    }
+
+    // Needs an X server: xvfb-run -a cargo test --lib paste -- --ignored
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "needs an X server"]
+    fn test_typed_text_arrives_in_the_focused_window() -> Result<()> {
+        let window = x11::FocusedWindow::open()?;
+        let mut enigo = enigo::Enigo::new(&enigo::Settings::default())?;
+        type_text(&mut enigo, "Hello, World!\tcol 2\r\nline 2", 40, std::thread::sleep)?;
+
+        assert_eq!(window.typed_text(Duration::from_millis(500))?, "Hello, World!\tcol 2\nline 2");
+        Ok(())
+    }
+
+    /// A window that takes the keyboard focus and turns the key presses it
+    /// receives back into text
+    #[cfg(target_os = "linux")]
+    mod x11 {
+        use anyhow::Result;
+        use std::time::{Duration, Instant};
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{ConnectionExt, CreateWindowAux, EventMask, InputFocus, KeyButMask, Window, WindowClass};
+        use x11rb::protocol::Event;
+        use x11rb::rust_connection::RustConnection;
+
+        const XK_TAB: u32 = 0xff09;
+        const XK_RETURN: u32 = 0xff0d;
+
+        pub struct FocusedWindow {
+            conn: RustConnection,
+            window: Window,
+        }
+
+        impl FocusedWindow {
+            pub fn open() -> Result<FocusedWindow> {
+                let (conn, screen_num) = x11rb::connect(None)?;
+                let root = conn.setup().roots[screen_num].root;
+                let window = conn.generate_id()?;
+                let events = EventMask::KEY_PRESS | EventMask::STRUCTURE_NOTIFY;
+                conn.create_window(
+                    x11rb::COPY_DEPTH_FROM_PARENT,
+                    window,
+                    root,
+                    0,
+                    0,
+                    200,
+                    100,
+                    0,
+                    WindowClass::INPUT_OUTPUT,
+                    x11rb::COPY_FROM_PARENT,
+                    &CreateWindowAux::new().event_mask(events),
+                )?;
+                conn.map_window(window)?;
+                conn.flush()?;
+
+                // Focusing a window that isn't viewable yet fails
+                while !matches!(conn.wait_for_event()?, Event::MapNotify(_)) {}
+                conn.set_input_focus(InputFocus::PARENT, window, x11rb::CURRENT_TIME)?;
+                conn.get_input_focus()?.reply()?;
+                Ok(FocusedWindow { conn, window })
+            }
+
+            /// Text of the key presses received until none arrived for `quiet`
+            pub fn typed_text(&self, quiet: Duration) -> Result<String> {
+                let setup = self.conn.setup();
+                let (min_keycode, max_keycode) = (setup.min_keycode, setup.max_keycode);
+
+                let mut presses = Vec::new();
+                let mut last = Instant::now();
+                while last.elapsed() < quiet {
+                    match self.conn.poll_for_event()? {
+                        // Look the keysym up right away: keys for characters
+                        // missing from the layout are only mapped while typed
+                        Some(Event::KeyPress(event)) if event.event == self.window => {
+                            let mapping = self.conn.get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)?.reply()?;
+                            let per_keycode = mapping.keysyms_per_keycode as usize;
+                            let index = (event.detail - min_keycode) as usize * per_keycode;
+                            let shifted = event.state.contains(KeyButMask::SHIFT);
+                            let keysyms = &mapping.keysyms[index..index + per_keycode];
+                            let keysym = match (shifted, keysyms) {
+                                (true, [_, upper, ..]) if *upper != 0 => *upper,
+                                (_, [lower, ..]) => *lower,
+                                _ => 0,
+                            };
+                            presses.push(keysym);
+                            last = Instant::now();
+                        }
+                        Some(_) => {}
+                        None => std::thread::sleep(Duration::from_millis(10)),
+                    }
+                }
+
+                Ok(presses
+                    .into_iter()
+                    .filter_map(|keysym| match keysym {
+                        XK_TAB => Some('\t'),
+                        XK_RETURN => Some('\n'),
+                        0x20..=0x7e => char::from_u32(keysym),
+                        // Modifiers
+                        _ => None,
+                    })
+                    .collect())
+            }
+        }
+
+        impl Drop for FocusedWindow {
+            fn drop(&mut self) {
+                let _ = self.conn.destroy_window(self.window);
+                let _ = self.conn.flush();
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use anyhow::Result;
+use enigo::{Direction, InputError, InputResult, Key, Keyboard};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use super::KeyInjector;
+
+/// True when running inside a Wayland session, where enigo's X11 backend can
+/// only reach XWayland windows (if any)
+pub fn is_wayland_session() -> bool {
+    session_is_wayland(
+        std::env::var("XDG_SESSION_TYPE").ok().as_deref(),
+        std::env::var("WAYLAND_DISPLAY").ok().as_deref(),
+    )
+}
+
+fn session_is_wayland(session_type: Option<&str>, wayland_display: Option<&str>) -> bool {
+    match session_type {
+        Some(session_type) if !session_type.is_empty() => session_type.eq_ignore_ascii_case("wayland"),
+        _ => wayland_display.is_some_and(|display| !display.is_empty()),
+    }
+}
+
+/// Find a working injection tool for this Wayland session.
+///
+/// `wtype` talks the virtual-keyboard protocol, which wlroots compositors and
+/// KDE support. GNOME doesn't, so fall back to `ydotool`, which writes to
+/// `/dev/uinput` through its daemon.
+pub fn detect_injector() -> KeyInjector {
+    // `wtype -s 0` connects to the compositor and sleeps for 0ms; it fails
+    // when the virtual-keyboard protocol isn't offered
+    let wtype_works = Command::new("wtype")
+        .args(["-s", "0"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false);
+
+    if wtype_works {
+        return KeyInjector::Wtype;
+    }
+
+    let ydotool_installed = Command::new("ydotool")
+        .arg("help")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok();
+
+    if ydotool_installed && ydotool_socket().is_some() {
+        return KeyInjector::Ydotool;
+    }
+
+    KeyInjector::Unavailable
+}
+
+/// The ydotoold socket, if the daemon is running
+fn ydotool_socket() -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Ok(socket) = std::env::var("YDOTOOL_SOCKET") {
+        candidates.push(PathBuf::from(socket));
+    }
+    if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+        candidates.push(PathBuf::from(runtime_dir).join(".ydotool_socket"));
+    }
+    candidates.push(PathBuf::from("/tmp/.ydotool_socket"));
+
+    candidates.into_iter().find(|path| path.exists())
+}
+
+type Runner = Box<dyn FnMut(&[String], Option<&str>) -> Result<()> + Send>;
+
+/// Runs an external tool, feeding `stdin` to it if given
+fn run_tool(args: &[String], stdin: Option<&str>) -> Result<()> {
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("Empty command"))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", program, e))?;
+
+    if let (Some(text), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(text.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+/// An enigo `Keyboard` backed by `wtype` or `ydotool`, so the chord and typing
+/// code works unchanged on Wayland.
+///
+/// Each tool invocation gets a fresh virtual keyboard, so key events are
+/// buffered while modifiers are held and sent as one command once they are
+/// all released.
+pub struct ToolKeyboard {
+    injector: KeyInjector,
+    pending: Vec<String>,
+    held_modifiers: usize,
+    run: Runner,
+}
+
+impl ToolKeyboard {
+    pub fn new(injector: KeyInjector) -> Result<Self> {
+        Self::with_runner(injector, Box::new(run_tool))
+    }
+
+    fn with_runner(injector: KeyInjector, run: Runner) -> Result<Self> {
+        if !matches!(injector, KeyInjector::Wtype | KeyInjector::Ydotool) {
+            return Err(anyhow::anyhow!("{:?} is not a command-line injector", injector));
+        }
+
+        Ok(Self {
+            injector,
+            pending: Vec::new(),
+            held_modifiers: 0,
+            run,
+        })
+    }
+
+    /// Send any buffered key events
+    pub fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut args = match self.injector {
+            KeyInjector::Ydotool => vec!["ydotool".to_string(), "key".to_string()],
+            _ => vec!["wtype".to_string()],
+        };
+        args.append(&mut self.pending);
+
+        (self.run)(&args, None)
+    }
+
+    fn push_key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+        match self.injector {
+            KeyInjector::Ydotool => {
+                let code = evdev_code(key).ok_or(InputError::InvalidInput("Key is not supported by ydotool"))?;
+                if matches!(direction, Direction::Press | Direction::Click) {
+                    self.pending.push(format!("{}:1", code));
+                }
+                if matches!(direction, Direction::Release | Direction::Click) {
+                    self.pending.push(format!("{}:0", code));
+                }
+            }
+            _ => {
+                if let Some(modifier) = wtype_modifier(key) {
+                    if matches!(direction, Direction::Press | Direction::Click) {
+                        self.pending.extend(["-M".to_string(), modifier.to_string()]);
+                    }
+                    if matches!(direction, Direction::Release | Direction::Click) {
+                        self.pending.extend(["-m".to_string(), modifier.to_string()]);
+                    }
+                } else {
+                    let name = wtype_key(key).ok_or(InputError::InvalidInput("Key is not supported by wtype"))?;
+                    let flag = match direction {
+                        Direction::Press => "-P",
+                        Direction::Release => "-p",
+                        Direction::Click => "-k",
+                    };
+                    self.pending.extend([flag.to_string(), name]);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Keyboard for ToolKeyboard {
+    fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
+        self.flush().map_err(|_| InputError::Simulate("Failed to send pending keys"))?;
+
+        let args: Vec<String> = match self.injector {
+            KeyInjector::Ydotool => ["ydotool", "type", "--file", "-"].iter().map(|s| s.to_string()).collect(),
+            _ => vec!["wtype".to_string(), "-".to_string()],
+        };
+
+        (self.run)(&args, Some(text)).map_err(|_| InputError::Simulate("Failed to type text"))?;
+        Ok(Some(()))
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+        let is_modifier = wtype_modifier(key).is_some();
+        self.push_key(key, direction)?;
+
+        if is_modifier {
+            match direction {
+                Direction::Press => self.held_modifiers += 1,
+                Direction::Release => self.held_modifiers = self.held_modifiers.saturating_sub(1),
+                Direction::Click => {}
+            }
+        }
+
+        if self.held_modifiers == 0 {
+            self.flush().map_err(|_| InputError::Simulate("Failed to send key events"))?;
+        }
+        Ok(())
+    }
+
+    fn raw(&mut self, _keycode: u16, _direction: Direction) -> InputResult<()> {
+        Err(InputError::InvalidInput("Raw keycodes are not supported on Wayland"))
+    }
+}
+
+fn wtype_modifier(key: Key) -> Option<&'static str> {
+    match key {
+        Key::Control => Some("ctrl"),
+        Key::Shift => Some("shift"),
+        Key::Alt => Some("alt"),
+        Key::Meta => Some("logo"),
+        _ => None,
+    }
+}
+
+/// XKB keysym name understood by `wtype -k`
+fn wtype_key(key: Key) -> Option<String> {
+    let name = match key {
+        Key::Return => "Return".to_string(),
+        Key::Tab => "Tab".to_string(),
+        Key::Insert => "Insert".to_string(),
+        Key::Unicode(c) if c.is_ascii_alphanumeric() => c.to_string(),
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Linux input event code (from `linux/input-event-codes.h`) used by `ydotool key`
+fn evdev_code(key: Key) -> Option<u16> {
+    let code = match key {
+        Key::Control => 29,
+        Key::Shift => 42,
+        Key::Alt => 56,
+        Key::Meta => 125,
+        Key::Return => 28,
+        Key::Tab => 15,
+        Key::Insert => 110,
+        Key::Unicode(c) => match c.to_ascii_lowercase() {
+            '1' => 2, '2' => 3, '3' => 4, '4' => 5, '5' => 6, '6' => 7, '7' => 8, '8' => 9,
+            '9' => 10, '0' => 11, 'q' => 16, 'w' => 17, 'e' => 18, 'r' => 19, 't' => 20,
+            'y' => 21, 'u' => 22, 'i' => 23, 'o' => 24, 'p' => 25, 'a' => 30, 's' => 31,
+            'd' => 32, 'f' => 33, 'g' => 34, 'h' => 35, 'j' => 36, 'k' => 37, 'l' => 38,
+            'z' => 44, 'x' => 45, 'c' => 46, 'v' => 47, 'b' => 48, 'n' => 49, 'm' => 50,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Tell the user the entry was only copied, since our window is already hidden
+/// and can't show anything itself
+pub fn notify_copy_only(message: &str) {
+    let _ = Command::new("notify-send")
+        .args(["--app-name=SuperKBD", "SuperKBD", message])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paste::profiles::press_chord;
+    use crate::paste::typing::type_text;
+    use std::sync::{Arc, Mutex};
+
+    type Calls = Arc<Mutex<Vec<(Vec<String>, Option<String>)>>>;
+
+    fn recording_keyboard(injector: KeyInjector) -> (ToolKeyboard, Calls) {
+        let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&calls);
+        let keyboard = ToolKeyboard::with_runner(
+            injector,
+            Box::new(move |args, stdin| {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((args.to_vec(), stdin.map(str::to_string)));
+                Ok(())
+            }),
+        )
+        .unwrap();
+        (keyboard, calls)
+    }
+
+    #[test]
+    fn test_session_detection() {
+        assert!(session_is_wayland(Some("wayland"), None));
+        assert!(!session_is_wayland(Some("x11"), Some("wayland-0")));
+        assert!(session_is_wayland(None, Some("wayland-0")));
+        assert!(session_is_wayland(Some(""), Some("wayland-0")));
+        assert!(!session_is_wayland(None, None));
+    }
+
+    #[test]
+    fn test_wtype_chord_is_one_command() -> Result<()> {
+        let (mut keyboard, calls) = recording_keyboard(KeyInjector::Wtype);
+        press_chord(&mut keyboard, &"Ctrl+Shift+V".parse()?)?;
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1, "Modifiers must stay held within one wtype call");
+        assert_eq!(
+            calls[0].0,
+            vec!["wtype", "-M", "ctrl", "-M", "shift", "-k", "v", "-m", "shift", "-m", "ctrl"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ydotool_chord_uses_evdev_codes() -> Result<()> {
+        let (mut keyboard, calls) = recording_keyboard(KeyInjector::Ydotool);
+        press_chord(&mut keyboard, &"Shift+Insert".parse()?)?;
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls[0].0, vec!["ydotool", "key", "42:1", "110:1", "110:0", "42:0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_typing_sends_text_on_stdin() -> Result<()> {
+        let (mut keyboard, calls) = recording_keyboard(KeyInjector::Wtype);
+        type_text(&mut keyboard, "héllo\n-rf", 0, |_| {})?;
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0], (vec!["wtype".to_string(), "-".to_string()], Some("héllo".to_string())));
+        assert_eq!(calls[1].0, vec!["wtype", "-k", "Return"]);
+        // Text starting with '-' must not be parsed as a flag
+        assert_eq!(calls[2].1.as_deref(), Some("-rf"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_non_tool_injector() {
+        assert!(ToolKeyboard::new(KeyInjector::Enigo).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+// The "clipimg" URI scheme serves image entries to the webview by id, so list
+// rows can use <img src> instead of passing image bytes over IPC:
+//
+//   clipimg://localhost/original/<id>
+//   clipimg://localhost/thumbnail/<id>?size=64
+//
+// On Windows the webview reaches it as http://clipimg.localhost/... instead.
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::clipboard::thumbnail;
+use crate::commands::AppState;
+use crate::database;
+
+pub const SCHEME: &str = "clipimg";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageRequest {
+    Original(i64),
+    Thumbnail { id: i64, size: u32 },
+}
+
+/// "/thumbnail/5?size=64" style paths; size defaults to the smallest thumbnail
+fn parse_request(path: &str, query: Option<&str>) -> Option<ImageRequest> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    let (variant, id) = (segments.next()?, segments.next()?.parse::<i64>().ok()?);
+    if segments.next().is_some() {
+        return None;
+    }
+
+    match variant {
+        "original" => Some(ImageRequest::Original(id)),
+        "thumbnail" => {
+            let requested = query
+                .unwrap_or_default()
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("size="))
+                .map(|size| size.parse::<u32>().ok())
+                .unwrap_or(Some(0))?;
+            Some(ImageRequest::Thumbnail { id, size: thumbnail::thumbnail_size_for(requested) })
+        }
+        _ => None,
+    }
+}
+
+pub async fn handle<R: Runtime>(app: &AppHandle<R>, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Some(image_request) = parse_request(request.uri().path(), request.uri().query()) else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let Some(state) = app.try_state::<AppState>() else {
+        return status(StatusCode::SERVICE_UNAVAILABLE);
+    };
+
+    let id = match image_request {
+        ImageRequest::Original(id) | ImageRequest::Thumbnail { id, .. } => id,
+    };
+    let entry = match database::get_entry(&state.pool, id).await {
+        Ok(Some(entry)) if entry.content_type == "image" && entry.file_path.is_some() => entry,
+        Ok(_) => return status(StatusCode::NOT_FOUND),
+        Err(e) => {
+            eprintln!("❌ [ERROR] Failed to load image entry {}: {}", id, e);
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let original = PathBuf::from(entry.file_path.as_deref().unwrap_or_default());
+
+    let path = match image_request {
+        ImageRequest::Original(_) => original,
+        ImageRequest::Thumbnail { size, .. } => {
+            let path = thumbnail::thumbnail_path(&original, size);
+            if thumbnail::has_failed(&entry) {
+                return status(StatusCode::NOT_FOUND);
+            }
+            // Not generated yet, or deleted: only the files are written here,
+            // codes and metadata are left to the background queue
+            if !path.exists() {
+                let generated = tokio::task::spawn_blocking(move || thumbnail::generate(&original)).await;
+                if let Err(e) = generated.map_err(anyhow::Error::from).and_then(|result| result) {
+                    println!("⚠️  [WARNING] No thumbnail for entry {}: {:#}", id, e);
+                    return status(StatusCode::NOT_FOUND);
+                }
+            }
+            path
+        }
+    };
+
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).and_then(|value| value.to_str().ok());
+    serve_file(&path, if_none_match)
+}
+
+/// The file with caching headers, or 304 when the webview's copy is current
+fn serve_file(path: &Path, if_none_match: Option<&str>) -> Response<Vec<u8>> {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_millis())
+        .unwrap_or_default();
+    let etag = format!("\"{:x}-{:x}\"", metadata.len(), modified);
+
+    let builder = Response::builder()
+        .header(header::CACHE_CONTROL, "private, max-age=86400")
+        .header(header::ETAG, &etag);
+    if if_none_match == Some(etag.as_str()) {
+        return builder.status(StatusCode::NOT_MODIFIED).body(Vec::new()).unwrap_or_else(|_| status(StatusCode::NOT_MODIFIED));
+    }
+
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(_) => return status(StatusCode::NOT_FOUND),
+    };
+    let content_type = image::guess_format(&data)
+        .map(|format| format.to_mime_type())
+        .unwrap_or("application/octet-stream");
+    builder
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(data)
+        .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = code;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(parse_request("/original/5", None), Some(ImageRequest::Original(5)));
+        assert_eq!(parse_request("/thumbnail/5", None), Some(ImageRequest::Thumbnail { id: 5, size: 64 }));
+        assert_eq!(parse_request("/thumbnail/5", Some("size=200")), Some(ImageRequest::Thumbnail { id: 5, size: 256 }));
+        assert_eq!(parse_request("/thumbnail/5", Some("v=2&size=64")), Some(ImageRequest::Thumbnail { id: 5, size: 64 }));
+        for (path, query) in [("/original/x", None), ("/original", None), ("/original/5/extra", None), ("/other/5", None), ("/thumbnail/5", Some("size=big"))] {
+            assert_eq!(parse_request(path, query), None, "{path} {query:?}");
+        }
+    }
+
+    #[test]
+    fn test_serve_file_caching() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.png");
+        image::RgbaImage::new(2, 2).save(&path).unwrap();
+
+        let response = serve_file(&path, None);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let cached = serve_file(&path, Some(&etag));
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert!(cached.body().is_empty());
+
+        assert_eq!(serve_file(&dir.path().join("gone.png"), None).status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,221 @@
+// EAN-13 and UPC-A barcodes, read from rows of a screenshot. A barcode has
+// 59 runs: guards 101, six digits, guard 01010, six digits, guard 101. Each
+// digit is four runs seven modules wide. Readings must agree on two rows
+// and pass the check digit.
+
+use image::GrayImage;
+
+use super::decode::otsu_threshold;
+
+/// Run widths of the left-hand odd parity (L) digits; G digits are the same
+/// widths reversed, right-hand digits the same widths starting dark
+const DIGIT_WIDTHS: [[u8; 4]; 10] = [
+    [3, 2, 1, 1],
+    [2, 2, 2, 1],
+    [2, 1, 2, 2],
+    [1, 4, 1, 1],
+    [1, 1, 3, 2],
+    [1, 2, 3, 1],
+    [1, 1, 1, 4],
+    [1, 3, 1, 2],
+    [1, 2, 1, 3],
+    [3, 1, 1, 2],
+];
+
+/// G (even parity) digits among the six on the left, by first digit
+const FIRST_DIGIT_PARITY: [[bool; 6]; 10] = [
+    [false, false, false, false, false, false],
+    [false, false, true, false, true, true],
+    [false, false, true, true, false, true],
+    [false, false, true, true, true, false],
+    [false, true, false, false, true, true],
+    [false, true, true, false, false, true],
+    [false, true, true, true, false, false],
+    [false, true, false, true, false, true],
+    [false, true, false, true, true, false],
+    [false, true, true, false, true, false],
+];
+
+/// The 13 digits of every EAN-13 barcode found
+pub fn decode(image: &GrayImage) -> Vec<String> {
+    let binary = Binary::new(image);
+    let mut readings: Vec<(String, usize)> = Vec::new();
+    for y in 0..binary.height {
+        let runs = binary.row_runs(y);
+        let mut row_codes = Vec::new();
+        // Upside down barcodes read right to left
+        for reversed in [false, true] {
+            let runs: Vec<(bool, usize)> = if reversed {
+                runs.iter().rev().map(|&(dark, _, length)| (dark, length)).collect()
+            } else {
+                runs.iter().map(|&(dark, _, length)| (dark, length)).collect()
+            };
+            let lengths: Vec<usize> = runs.iter().map(|&(_, length)| length).collect();
+            for offset in 0..runs.len().saturating_sub(58) {
+                if !runs[offset].0 {
+                    continue;
+                }
+                if let Some(code) = read_runs(&lengths[offset..offset + 59]) {
+                    if !row_codes.contains(&code) {
+                        row_codes.push(code);
+                    }
+                }
+            }
+        }
+        for code in row_codes {
+            match readings.iter_mut().find(|(reading, _)| *reading == code) {
+                Some((_, rows)) => *rows += 1,
+                None => readings.push((code, 1)),
+            }
+        }
+    }
+    readings.into_iter().filter(|(_, rows)| *rows >= 2).map(|(code, _)| code).collect()
+}
+
+/// Read 59 runs starting with the first dark bar of the start guard
+fn read_runs(runs: &[usize]) -> Option<String> {
+    let module = runs.iter().sum::<usize>() as f64 / 95.0;
+    let is_guard = |guard: &[usize]| guard.iter().all(|&run| (run as f64 - module).abs() < module * 0.6);
+    if !is_guard(&runs[..3]) || !is_guard(&runs[27..32]) || !is_guard(&runs[56..]) {
+        return None;
+    }
+
+    let mut digits = Vec::with_capacity(13);
+    let mut parity = [false; 6];
+    for i in 0..6 {
+        let (digit, even) = read_digit(&runs[3 + i * 4..7 + i * 4])?;
+        digits.push(digit);
+        parity[i] = even;
+    }
+    for i in 0..6 {
+        let (digit, even) = read_digit(&runs[32 + i * 4..36 + i * 4])?;
+        // Right-hand digits only come in one parity
+        if even {
+            return None;
+        }
+        digits.push(digit);
+    }
+    let first = FIRST_DIGIT_PARITY.iter().position(|pattern| *pattern == parity)? as u8;
+    digits.insert(0, first);
+
+    let checksum: u32 = digits[..12].iter().enumerate().map(|(i, &digit)| digit as u32 * if i % 2 == 0 { 1 } else { 3 }).sum();
+    if (10 - checksum % 10) % 10 != digits[12] as u32 {
+        return None;
+    }
+    Some(digits.iter().map(|digit| char::from(b'0' + digit)).collect())
+}
+
+/// Digit and whether it used the reversed (G) widths
+fn read_digit(runs: &[usize]) -> Option<(u8, bool)> {
+    let total: usize = runs.iter().sum();
+    let mut widths = [0u8; 4];
+    for (width, &run) in widths.iter_mut().zip(runs) {
+        *width = (run as f64 * 7.0 / total as f64).round() as u8;
+        if !(1..=4).contains(width) {
+            return None;
+        }
+    }
+    if widths.iter().sum::<u8>() != 7 {
+        return None;
+    }
+
+    let reversed = [widths[3], widths[2], widths[1], widths[0]];
+    DIGIT_WIDTHS
+        .iter()
+        .position(|pattern| *pattern == widths)
+        .map(|digit| (digit as u8, false))
+        .or_else(|| DIGIT_WIDTHS.iter().position(|pattern| *pattern == reversed).map(|digit| (digit as u8, true)))
+}
+
+/// Black and white version of an image, split at Otsu's threshold
+struct Binary {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Binary {
+    fn new(image: &GrayImage) -> Binary {
+        let threshold = otsu_threshold(image);
+        Binary {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            dark: image.pixels().map(|pixel| pixel.0[0] <= threshold).collect(),
+        }
+    }
+
+    /// Alternating runs of a row as (dark, start, length)
+    fn row_runs(&self, y: usize) -> Vec<(bool, usize, usize)> {
+        let row = &self.dark[y * self.width..(y + 1) * self.width];
+        let mut runs: Vec<(bool, usize, usize)> = Vec::new();
+        for (x, &dark) in row.iter().enumerate() {
+            match runs.last_mut() {
+                Some(run) if run.0 == dark => run.2 += 1,
+                _ => runs.push((dark, x, 1)),
+            }
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{imageops, Luma};
+
+    /// Draw a barcode with the given module width and a quiet zone
+    fn render(code: &str, module: u32) -> GrayImage {
+        let digits: Vec<usize> = code.bytes().map(|byte| (byte - b'0') as usize).collect();
+        let mut widths: Vec<u8> = vec![1, 1, 1];
+        for (i, &digit) in digits[1..7].iter().enumerate() {
+            let pattern = DIGIT_WIDTHS[digit];
+            if FIRST_DIGIT_PARITY[digits[0]][i] {
+                widths.extend(pattern.iter().rev());
+            } else {
+                widths.extend(pattern);
+            }
+        }
+        widths.extend([1, 1, 1, 1, 1]);
+        for &digit in &digits[7..] {
+            widths.extend(DIGIT_WIDTHS[digit]);
+        }
+        widths.extend([1, 1, 1]);
+
+        let quiet = 10 * module;
+        let width = quiet * 2 + 95 * module;
+        let mut image = GrayImage::from_pixel(width, 40, Luma([255]));
+        let mut x = quiet;
+        // Runs alternate starting with the first bar of the start guard
+        for (i, &run) in widths.iter().enumerate() {
+            for _ in 0..run as u32 * module {
+                if i % 2 == 0 {
+                    for y in 5..35 {
+                        image.put_pixel(x, y, Luma([0]));
+                    }
+                }
+                x += 1;
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn test_reads_ean13() {
+        for code in ["4006381333931", "5901234123457", "0036000291452", "9780306406157"] {
+            assert_eq!(decode(&render(code, 2)), vec![code.to_string()], "{code}");
+        }
+        assert_eq!(decode(&render("4006381333931", 3)), vec!["4006381333931".to_string()]);
+    }
+
+    #[test]
+    fn test_reads_upside_down() {
+        let image = imageops::rotate180(&render("5901234123457", 2));
+        assert_eq!(decode(&image), vec!["5901234123457".to_string()]);
+    }
+
+    #[test]
+    fn test_rejects_bad_check_digit() {
+        assert!(decode(&render("4006381333932", 2)).is_empty());
+        assert!(decode(&GrayImage::from_pixel(200, 40, Luma([255]))).is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+// Reads QR codes from screenshots with the `rqrr` crate, a pure-Rust port
+// of quirc. Several symbols in one image are all returned, each text once.
+
+use image::GrayImage;
+use rqrr::BitGrid;
+
+/// Offsets of the top-left corner, in modules, tried when resampling
+const JIGGLE: [(f64, f64); 9] = [(0.0, 0.0), (-0.25, 0.0), (0.25, 0.0), (0.0, -0.25), (0.0, 0.25), (-0.25, -0.25), (0.25, 0.25), (-0.25, 0.25), (0.25, -0.25)];
+
+/// Upscaled copies for the second pass stay below this many pixels
+const MAX_UPSCALED_PIXELS: usize = 16_000_000;
+
+/// Text of every QR code found in `image`
+pub fn decode(image: &GrayImage) -> Vec<String> {
+    let gray = Gray::new(image);
+    let mut texts = decode_at(&gray, 1);
+
+    // A symbol scaled by a fraction (HiDPI screenshots) has blurred module
+    // edges. Smoothing it onto a finer grid before thresholding keeps the
+    // modules square enough for the detector.
+    let scale = ((MAX_UPSCALED_PIXELS / (gray.width * gray.height).max(1)) as f64).sqrt() as usize;
+    if texts.is_empty() && scale >= 2 {
+        texts = decode_at(&gray, scale.min(3));
+    }
+    texts
+}
+
+fn decode_at(gray: &Gray, scale: usize) -> Vec<String> {
+    let dark = |x: usize, y: usize| gray.is_dark_at((x as f64 + 0.5) / scale as f64, (y as f64 + 0.5) / scale as f64);
+    let mut prepared = if scale == 1 {
+        rqrr::PreparedImage::prepare_from_greyscale(gray.width, gray.height, |x, y| gray.luma[y * gray.width + x])
+    } else {
+        rqrr::PreparedImage::prepare_from_bitmap(gray.width * scale, gray.height * scale, &dark)
+    };
+
+    let mut texts = Vec::new();
+    for grid in prepared.detect_grids() {
+        let text = match grid.decode() {
+            Ok((_, text)) => Some(text),
+            Err(_) => resample(&grid.bounds, grid.grid.size(), &dark),
+        };
+        if let Some(text) = text.filter(|text| !texts.contains(text)) {
+            texts.push(text);
+        }
+    }
+    texts
+}
+
+/// Read a symbol again from two of its corners. Without an alignment
+/// pattern (version 1) the detector guesses the fourth corner from blurred
+/// finder edges and can end up a module off; screenshots are square and
+/// unskewed, so one exact side is enough. Both sides at the top-left corner
+/// are tried.
+fn resample(bounds: &[rqrr::Point; 4], size: usize, dark: &impl Fn(usize, usize) -> bool) -> Option<String> {
+    // The bounds lie one module beyond the grid
+    let modules = size as f64 + 1.0;
+    let origin = (bounds[0].x as f64, bounds[0].y as f64);
+    let along_top = ((bounds[1].x as f64 - origin.0) / modules, (bounds[1].y as f64 - origin.1) / modules);
+    let along_left = ((bounds[3].x as f64 - origin.0) / modules, (bounds[3].y as f64 - origin.1) / modules);
+
+    for (right, down) in [(along_top, (-along_top.1, along_top.0)), ((along_left.1, -along_left.0), along_left)] {
+        for (shift_x, shift_y) in JIGGLE {
+            let (ox, oy) = (origin.0 + shift_x * right.0 + shift_y * down.0, origin.1 + shift_x * right.1 + shift_y * down.1);
+            let grid = rqrr::SimpleGrid::from_func(size, |x, y| {
+                let (mx, my) = (x as f64 + 0.5, y as f64 + 0.5);
+                let px = ox + right.0 * mx + down.0 * my;
+                let py = oy + right.1 * mx + down.1 * my;
+                px >= 0.0 && py >= 0.0 && dark(px as usize, py as usize)
+            });
+            if let Ok((_, text)) = rqrr::Grid::new(grid).decode() {
+                return Some(text);
+            }
+        }
+    }
+    None
+}
+
+/// Luma of an image with the Otsu threshold between its dark and light
+struct Gray {
+    width: usize,
+    height: usize,
+    luma: Vec<u8>,
+    threshold: u8,
+}
+
+impl Gray {
+    fn new(image: &GrayImage) -> Gray {
+        Gray {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            luma: image.pixels().map(|pixel| pixel.0[0]).collect(),
+            threshold: otsu_threshold(image),
+        }
+    }
+
+    /// Whether the point is dark, interpolating between the four nearest
+    /// pixel centres
+    fn is_dark_at(&self, x: f64, y: f64) -> bool {
+        let (x, y) = ((x - 0.5).clamp(0.0, (self.width - 1) as f64), (y - 0.5).clamp(0.0, (self.height - 1) as f64));
+        let (left, top) = (x.floor() as usize, y.floor() as usize);
+        let (right, bottom) = ((left + 1).min(self.width - 1), (top + 1).min(self.height - 1));
+        let (fx, fy) = (x - left as f64, y - top as f64);
+        let luma = |x: usize, y: usize| self.luma[y * self.width + x] as f64;
+        let upper = luma(left, top) * (1.0 - fx) + luma(right, top) * fx;
+        let lower = luma(left, bottom) * (1.0 - fx) + luma(right, bottom) * fx;
+        upper * (1.0 - fy) + lower * fy <= self.threshold as f64
+    }
+}
+
+/// Level between dark and light that best separates the two (Otsu)
+pub(super) fn otsu_threshold(image: &GrayImage) -> u8 {
+    let mut histogram = [0usize; 256];
+    for pixel in image.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+    let total = image.pixels().len() as f64;
+    let sum: f64 = histogram.iter().enumerate().map(|(value, &count)| value as f64 * count as f64).sum();
+
+    let (mut background_sum, mut background_count) = (0.0, 0.0);
+    let (mut best, mut best_variance) = (127u8, 0.0);
+    for (value, &count) in histogram.iter().enumerate() {
+        background_count += count as f64;
+        if background_count == 0.0 {
+            continue;
+        }
+        let foreground_count = total - background_count;
+        if foreground_count == 0.0 {
+            break;
+        }
+        background_sum += value as f64 * count as f64;
+        let background_mean = background_sum / background_count;
+        let foreground_mean = (sum - background_sum) / foreground_count;
+        let variance = background_count * foreground_count * (background_mean - foreground_mean).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best = value as u8;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{imageops, Luma};
+    use qrcode::bits::Bits;
+    use qrcode::canvas::{Canvas, MaskPattern};
+    use qrcode::render::Renderer;
+    use qrcode::{ec, Color, EcLevel, QrCode, Version};
+
+    const MASKS: [MaskPattern; 8] = [
+        MaskPattern::Checkerboard,
+        MaskPattern::HorizontalLines,
+        MaskPattern::VerticalLines,
+        MaskPattern::DiagonalLines,
+        MaskPattern::LargeCheckerboard,
+        MaskPattern::Fields,
+        MaskPattern::Diamonds,
+        MaskPattern::Meadow,
+    ];
+
+    /// `text` drawn by the `qrcode` crate with `scale` pixels per module
+    fn reference(text: &str, scale: u32) -> GrayImage {
+        QrCode::new(text).unwrap().render::<Luma<u8>>().module_dimensions(scale, scale).build()
+    }
+
+    /// Modules of a symbol with version, level and mask chosen here rather
+    /// than by the encoder
+    fn symbol(text: &str, version: i16, level: EcLevel, mask: MaskPattern) -> Vec<Color> {
+        let version = Version::Normal(version);
+        let mut bits = Bits::new(version);
+        bits.push_optimal_data(text.as_bytes()).unwrap();
+        bits.push_terminator(level).unwrap();
+        let (data, ecc) = ec::construct_codewords(&bits.into_bytes(), version, level).unwrap();
+        let mut canvas = Canvas::new(version, level);
+        canvas.draw_all_functional_patterns();
+        canvas.draw_data(&data, &ecc);
+        canvas.apply_mask(mask);
+        canvas.into_colors()
+    }
+
+    /// Longest mix of digits, capitals and lower case that fits the symbol
+    fn filler(version: i16, level: EcLevel) -> String {
+        let capacity = Bits::new(Version::Normal(version)).max_len(level).unwrap();
+        "HTTPS://EXAMPLE.COM/20240101/report?id=".chars().cycle().take((capacity - 32) / 8).collect()
+    }
+
+    #[test]
+    fn test_round_trips() {
+        for text in ["hello", "https://example.com/path?query=1&b=2", "Grüße, 世界 ✓", &"long text ".repeat(60)] {
+            assert_eq!(decode(&reference(text, 4)), vec![text.to_string()], "{text}");
+        }
+        assert_eq!(decode(&reference("tiny", 2)), vec!["tiny".to_string()]);
+        assert_eq!(decode(&reference("odd scale", 5)), vec!["odd scale".to_string()]);
+    }
+
+    #[test]
+    fn test_versions_levels_and_masks() {
+        for version in [1, 2, 7, 12] {
+            for level in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+                let text = filler(version, level);
+                for mask in MASKS {
+                    let colors = symbol(&text, version, level, mask);
+                    let width = Version::Normal(version).width() as usize;
+                    let image = Renderer::<Luma<u8>>::new(&colors, width, 4).module_dimensions(1, 1).build();
+                    // Scaled by a fraction, like a screenshot on a HiDPI display
+                    let side = image.width() * 5 / 2;
+                    let screenshot = imageops::resize(&image, side, side, imageops::FilterType::Triangle);
+                    assert_eq!(decode(&screenshot), vec![text.clone()], "version {version}, {level:?}, {mask:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotated_and_embedded_symbols() {
+        let symbol = reference("https://example.com", 3);
+        assert_eq!(decode(&imageops::rotate90(&symbol)), vec!["https://example.com".to_string()]);
+        assert_eq!(decode(&imageops::rotate180(&symbol)), vec!["https://example.com".to_string()]);
+
+        // Somewhere on a larger screenshot with other content around it
+        let mut screenshot = GrayImage::from_pixel(400, 300, Luma([235]));
+        for x in 10..390 {
+            for y in 250..260 {
+                screenshot.put_pixel(x, y, Luma([20]));
+            }
+        }
+        imageops::overlay(&mut screenshot, &symbol, 200, 40);
+        assert_eq!(decode(&screenshot), vec!["https://example.com".to_string()]);
+    }
+
+    #[test]
+    fn test_two_symbols_in_one_image() {
+        let first = reference("first", 4);
+        let second = reference("second code", 4);
+        let mut image = GrayImage::from_pixel(first.width() + second.width() + 20, first.height().max(second.height()) + 20, Luma([255]));
+        imageops::overlay(&mut image, &first, 0, 0);
+        imageops::overlay(&mut image, &second, first.width() as i64 + 20, 20);
+        let mut found = decode(&image);
+        found.sort();
+        assert_eq!(found, vec!["first".to_string(), "second code".to_string()]);
+    }
+
+    #[test]
+    fn test_damaged_modules_are_corrected() {
+        // Version 2-M leaves room for a few wrong modules in each block
+        let code = QrCode::with_version("error correction", Version::Normal(2), EcLevel::M).unwrap();
+        let scale = 4;
+        let mut image = code.render::<Luma<u8>>().quiet_zone(true).module_dimensions(scale, scale).build();
+        for (x, y) in [(10, 10), (11, 12), (15, 20), (20, 15), (9, 18)] {
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let (px, py) = ((x + 4) * scale + dx, (y + 4) * scale + dy);
+                    let pixel = image.get_pixel_mut(px, py);
+                    pixel.0[0] = 255 - pixel.0[0];
+                }
+            }
+        }
+        assert_eq!(decode(&image), vec!["error correction".to_string()]);
+    }
+
+    #[test]
+    fn test_images_without_codes() {
+        assert!(decode(&GrayImage::from_pixel(50, 50, Luma([255]))).is_empty());
+        let mut stripes = GrayImage::new(120, 80);
+        for (x, _, pixel) in stripes.enumerate_pixels_mut() {
+            *pixel = Luma([if (x / 3) % 2 == 0 { 0 } else { 255 }]);
+        }
+        assert!(decode(&stripes).is_empty());
+    }
+}
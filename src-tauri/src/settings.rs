@@ -0,0 +1,359 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::sync::OnceLock;
+use tokio::sync::watch;
+
+use crate::clipboard::ocr;
+use crate::clipboard::selection::SelectionSettings;
+use crate::database::{self, DatabaseOptions};
+use crate::paste::{self, PasteOptions, PasteStrategy};
+use crate::shortcuts::{self, ShortcutBindings};
+use crate::sync::SyncSettings;
+
+/// User-configurable settings. Each field is stored as its own row in the
+/// `settings` table (key = field name, value = JSON), so adding a field later
+/// doesn't invalidate what's already saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// How often the clipboard monitor polls for changes
+    pub poll_interval_ms: u64,
+    /// Default number of entries `get_clipboard_history` returns
+    pub history_limit: i64,
+    /// Non-favorite entries older than this are deleted (0 = keep forever)
+    pub retention_days: i64,
+    /// Hours between automatic database backups (0 = no automatic backups)
+    pub backup_interval_hours: u64,
+    /// Number of backups kept; older ones are deleted
+    pub backup_keep: usize,
+    pub shortcuts: ShortcutBindings,
+    /// Paste settings for apps without a paste profile
+    pub restore_clipboard: bool,
+    pub paste_strategy: PasteStrategy,
+    pub type_rate: u32,
+    pub paste_pre_delay_ms: u64,
+    pub paste_post_delay_ms: u64,
+    /// Connection settings, applied the next time the app starts
+    pub database: DatabaseOptions,
+    /// Sharing history with other devices through a synced folder
+    pub sync: SyncSettings,
+    /// PRIMARY selection capture and syncing on Linux
+    pub selection: SelectionSettings,
+    /// Copies larger than this are not recorded at all
+    pub max_capture_bytes: u64,
+    /// Texts larger than this are kept in the blob store with only a preview
+    /// in the database. They aren't synced to other devices.
+    pub offload_threshold_bytes: u64,
+    /// Recognize text in image entries with Tesseract, when it's installed
+    pub ocr_enabled: bool,
+    /// Tesseract languages, e.g. "eng" or "eng+deu"
+    pub ocr_languages: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: 500,
+            history_limit: 100,
+            retention_days: 0,
+            backup_interval_hours: 24,
+            backup_keep: 7,
+            shortcuts: shortcuts::default_bindings(),
+            restore_clipboard: false,
+            paste_strategy: PasteStrategy::default(),
+            type_rate: 0,
+            paste_pre_delay_ms: paste::DEFAULT_PRE_DELAY_MS,
+            paste_post_delay_ms: paste::DEFAULT_POST_DELAY_MS,
+            database: DatabaseOptions::default(),
+            sync: SyncSettings::default(),
+            selection: SelectionSettings::default(),
+            max_capture_bytes: 16 * 1024 * 1024,
+            offload_threshold_bytes: 256 * 1024,
+            ocr_enabled: true,
+            ocr_languages: "eng".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<()> {
+        if !(100..=10_000).contains(&self.poll_interval_ms) {
+            bail!("poll_interval_ms must be between 100 and 10000");
+        }
+        if !(1..=10_000).contains(&self.history_limit) {
+            bail!("history_limit must be between 1 and 10000");
+        }
+        if !(0..=3650).contains(&self.retention_days) {
+            bail!("retention_days must be between 0 and 3650");
+        }
+        if self.backup_interval_hours > 24 * 365 {
+            bail!("backup_interval_hours must be at most 8760");
+        }
+        if !(1..=100).contains(&self.backup_keep) {
+            bail!("backup_keep must be between 1 and 100");
+        }
+        if self.paste_pre_delay_ms > 5000 || self.paste_post_delay_ms > 5000 {
+            bail!("Paste delays must be at most 5000ms");
+        }
+        if !(1024..=1024 * 1024 * 1024).contains(&self.max_capture_bytes) {
+            bail!("max_capture_bytes must be between 1 KiB and 1 GiB");
+        }
+        // Offloaded texts keep a preview of up to 2000 characters inline
+        if !(8 * 1024..=self.max_capture_bytes.max(8 * 1024)).contains(&self.offload_threshold_bytes) {
+            bail!("offload_threshold_bytes must be at least 8 KiB and at most max_capture_bytes");
+        }
+        shortcuts::check_bindings(&self.shortcuts)?;
+        self.database.validate()?;
+        self.sync.validate()?;
+        self.selection.validate()?;
+        ocr::check_languages(&self.ocr_languages)?;
+
+        Ok(())
+    }
+
+    /// A copy safe to hand to the frontend: the sync passphrase is left out
+    pub fn without_secrets(&self) -> Settings {
+        let mut settings = self.clone();
+        settings.sync.passphrase.clear();
+        settings
+    }
+
+    /// Paste options for a target app that has no profile
+    pub fn paste_options(&self) -> PasteOptions {
+        PasteOptions {
+            restore_clipboard: self.restore_clipboard,
+            strategy: self.paste_strategy,
+            type_rate: self.type_rate,
+            pre_delay_ms: self.paste_pre_delay_ms,
+            post_delay_ms: self.paste_post_delay_ms,
+            ..PasteOptions::default()
+        }
+    }
+}
+
+// Current settings; subscribers are woken whenever they change
+static SETTINGS: OnceLock<watch::Sender<Settings>> = OnceLock::new();
+
+fn sender() -> &'static watch::Sender<Settings> {
+    SETTINGS.get_or_init(|| watch::channel(Settings::default()).0)
+}
+
+pub fn current() -> Settings {
+    sender().borrow().clone()
+}
+
+/// Receiver that sees every settings change, for long-running tasks
+pub fn subscribe() -> watch::Receiver<Settings> {
+    sender().subscribe()
+}
+
+pub fn publish(settings: Settings) {
+    sender().send_replace(settings);
+}
+
+/// Read stored settings, falling back to the default for any field that is
+/// missing or unreadable
+pub async fn load(pool: &SqlitePool) -> Result<Settings> {
+    let rows = database::get_all_settings(pool).await?;
+    let mut merged = serde_json::to_value(Settings::default())?;
+
+    for (key, value) in rows {
+        let Some(slot) = merged.get(&key).cloned() else {
+            continue;
+        };
+        let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&value) else {
+            println!("⚠️  [WARNING] Ignoring unreadable setting {}", key);
+            continue;
+        };
+
+        // A binding for an action that no longer exists is dropped on its own
+        // instead of resetting every shortcut
+        if let (true, serde_json::Value::Object(bindings)) = (key == "shortcuts", &mut value) {
+            bindings.retain(|action, _| serde_json::from_value::<shortcuts::ShortcutAction>(action.as_str().into()).is_ok());
+        }
+
+        merged[&key] = value;
+        if serde_json::from_value::<Settings>(merged.clone()).is_err() {
+            println!("⚠️  [WARNING] Ignoring invalid setting {}", key);
+            merged[&key] = slot;
+        }
+    }
+
+    let settings: Settings = serde_json::from_value(merged)?;
+    if let Err(e) = settings.validate() {
+        println!("⚠️  [WARNING] Stored settings are invalid ({}), using defaults", e);
+        return Ok(Settings::default());
+    }
+
+    Ok(settings)
+}
+
+pub async fn save(pool: &SqlitePool, settings: &Settings) -> Result<()> {
+    let serde_json::Value::Object(fields) = serde_json::to_value(settings)? else {
+        bail!("Settings must serialize to an object");
+    };
+
+    let entries: Vec<(String, String)> = fields
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect();
+
+    database::set_settings(pool, &entries).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shortcuts::ShortcutAction;
+
+    async fn setup_test_db() -> Result<SqlitePool> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await?;
+        database::migrations::run_migrations(&pool).await?;
+        Ok(pool)
+    }
+
+    async fn store(pool: &SqlitePool, key: &str, value: &str) -> Result<()> {
+        database::set_settings(pool, &[(key.to_string(), value.to_string())]).await
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+        // History was never deleted automatically before settings existed
+        assert_eq!(Settings::default().retention_days, 0);
+    }
+
+    #[test]
+    fn test_validation_rejects_out_of_range() {
+        let settings = Settings { poll_interval_ms: 10, ..Settings::default() };
+        assert!(settings.validate().is_err());
+
+        let settings = Settings { history_limit: 0, ..Settings::default() };
+        assert!(settings.validate().is_err());
+
+        let settings = Settings { paste_post_delay_ms: 60_000, ..Settings::default() };
+        assert!(settings.validate().is_err());
+
+        let settings = Settings { backup_keep: 0, ..Settings::default() };
+        assert!(settings.validate().is_err());
+
+        let settings = Settings { offload_threshold_bytes: 1024, ..Settings::default() };
+        assert!(settings.validate().is_err());
+
+        let settings = Settings { max_capture_bytes: 64 * 1024, offload_threshold_bytes: 128 * 1024, ..Settings::default() };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_checks_shortcuts() {
+        let mut settings = Settings::default();
+        settings.shortcuts.insert(ShortcutAction::OpenFavorites, "Alt+I".to_string());
+
+        assert!(settings.validate().is_err(), "Two actions can't share a shortcut");
+    }
+
+    #[test]
+    fn test_paste_options_from_settings() {
+        let settings = Settings {
+            restore_clipboard: true,
+            paste_strategy: PasteStrategy::Type,
+            paste_pre_delay_ms: 90,
+            ..Settings::default()
+        };
+
+        let options = settings.paste_options();
+        assert!(options.restore_clipboard);
+        assert_eq!(options.strategy, PasteStrategy::Type);
+        assert_eq!(options.pre_delay_ms, 90);
+    }
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let settings: Settings = serde_json::from_str(r#"{"history_limit": 50}"#).unwrap();
+        assert_eq!(settings.history_limit, 50);
+        assert_eq!(settings.poll_interval_ms, Settings::default().poll_interval_ms);
+    }
+
+    #[tokio::test]
+    async fn test_load_without_stored_settings() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        assert_eq!(load(&pool).await?, Settings::default());
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_roundtrip() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        let mut settings = Settings {
+            poll_interval_ms: 250,
+            retention_days: 30,
+            paste_strategy: PasteStrategy::Type,
+            ..Settings::default()
+        };
+        settings.shortcuts.insert(ShortcutAction::ToggleRecording, "Ctrl+Alt+P".to_string());
+
+        save(&pool, &settings).await?;
+        assert_eq!(load(&pool).await?, settings);
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bad_field_falls_back_to_default() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        store(&pool, "history_limit", "250").await?;
+        store(&pool, "poll_interval_ms", "\"fast\"").await?;
+        store(&pool, "type_rate", "not json").await?;
+        store(&pool, "removed_setting", "1").await?;
+
+        let settings = load(&pool).await?;
+        assert_eq!(settings.history_limit, 250);
+        assert_eq!(settings.poll_interval_ms, Settings::default().poll_interval_ms);
+        assert_eq!(settings.type_rate, 0);
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shortcuts_row_is_a_settings_field() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        store(&pool, "shortcuts", r#"{"show_history":"Ctrl+Alt+V"}"#).await?;
+
+        let settings = load(&pool).await?;
+        assert_eq!(
+            settings.shortcuts.get(&ShortcutAction::ShowHistory).map(String::as_str),
+            Some("Ctrl+Alt+V")
+        );
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_binding_for_removed_action_is_dropped() -> Result<()> {
+        let pool = setup_test_db().await?;
+
+        store(&pool, "shortcuts", r#"{"show_history":"Ctrl+Alt+V","open_clipboard_manager":"Ctrl+Alt+M"}"#).await?;
+
+        let settings = load(&pool).await?;
+        assert_eq!(settings.shortcuts.len(), 1);
+        assert_eq!(
+            settings.shortcuts.get(&ShortcutAction::ShowHistory).map(String::as_str),
+            Some("Ctrl+Alt+V")
+        );
+
+        pool.close().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,443 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::clipboard;
+use crate::commands::AppState;
+use crate::database;
+use crate::paste;
+use crate::settings;
+use crate::window;
+
+/// Combos the OS or nearly every app already uses
+const RESERVED_SHORTCUTS: &[&str] = &[
+    "CommandOrControl+A",
+    "CommandOrControl+C",
+    "CommandOrControl+Q",
+    "CommandOrControl+S",
+    "CommandOrControl+V",
+    "CommandOrControl+W",
+    "CommandOrControl+X",
+    "CommandOrControl+Z",
+    "Alt+Tab",
+    "Alt+F4",
+    "Super+Space",
+    "Super+Tab",
+    "Super+L",
+];
+
+/// How long paste_previous waits for the shortcut's modifiers to be let go
+const MODIFIER_RELEASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    ShowHistory,
+    /// Show the window with only favorite entries
+    OpenFavorites,
+    /// Show the window with the entries queued on the paste stack
+    OpenPasteStack,
+    /// Paste the entry before the current clipboard without opening the window
+    PastePrevious,
+    /// Pause or resume saving new copies to history
+    ToggleRecording,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 5] = [
+        ShortcutAction::ShowHistory,
+        ShortcutAction::OpenFavorites,
+        ShortcutAction::OpenPasteStack,
+        ShortcutAction::PastePrevious,
+        ShortcutAction::ToggleRecording,
+    ];
+}
+
+/// Accelerator string per action. Actions missing from the map are unbound.
+pub type ShortcutBindings = BTreeMap<ShortcutAction, String>;
+
+/// What the UI shows for one action
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutStatus {
+    pub action: ShortcutAction,
+    pub accelerator: Option<String>,
+    pub registered: bool,
+    /// Why registration failed, e.g. another app already owns the combo
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+struct ActiveBinding {
+    action: ShortcutAction,
+    accelerator: String,
+    /// Set once the OS accepted the registration
+    shortcut: Option<Shortcut>,
+    error: Option<String>,
+}
+
+static ACTIVE_BINDINGS: Mutex<Vec<ActiveBinding>> = Mutex::new(Vec::new());
+
+// Held for a whole register_all or rebind. ACTIVE_BINDINGS itself is never held
+// while calling the plugin: register/unregister block until the main thread
+// runs them, and the main thread locks ACTIVE_BINDINGS in handle_event.
+static CHANGING_BINDINGS: Mutex<()> = Mutex::new(());
+
+fn active_bindings() -> MutexGuard<'static, Vec<ActiveBinding>> {
+    ACTIVE_BINDINGS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn default_bindings() -> ShortcutBindings {
+    let mut bindings = ShortcutBindings::new();
+    bindings.insert(ShortcutAction::ShowHistory, "Alt+I".to_string());
+    bindings
+}
+
+/// Parse an accelerator like "Ctrl+Shift+V"
+pub fn parse_accelerator(accelerator: &str) -> Result<Shortcut> {
+    let shortcut = Shortcut::from_str(accelerator.trim())
+        .map_err(|e| anyhow!("Invalid shortcut \"{}\": {}", accelerator, e))?;
+
+    // A bare letter or digit would swallow normal typing
+    if shortcut.mods.is_empty() && is_character_key(shortcut.key) {
+        bail!("Shortcut \"{}\" needs at least one modifier", accelerator);
+    }
+
+    Ok(shortcut)
+}
+
+/// Reject unparsable accelerators and two actions sharing one combo
+pub fn check_bindings(bindings: &ShortcutBindings) -> Result<()> {
+    let mut seen: Vec<(Shortcut, ShortcutAction)> = Vec::new();
+
+    for (action, accelerator) in bindings {
+        let shortcut = parse_accelerator(accelerator)?;
+
+        if let Some((_, other)) = seen.iter().find(|(existing, _)| existing.id() == shortcut.id()) {
+            bail!("{} is already bound to {:?}", shortcut, other);
+        }
+        seen.push((shortcut, *action));
+    }
+
+    Ok(())
+}
+
+/// Non-fatal problems with a combo that registers fine but is likely to clash
+pub fn shortcut_warnings(shortcut: &Shortcut) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let reserved = RESERVED_SHORTCUTS
+        .iter()
+        .filter_map(|accelerator| Shortcut::from_str(accelerator).ok())
+        .any(|reserved| reserved.id() == shortcut.id());
+    if reserved {
+        warnings.push(format!("{} is a common system or editor shortcut and will stop working elsewhere", shortcut));
+    }
+
+    // Option+letter on macOS and AltGr layouts elsewhere use Alt to type characters
+    if shortcut.mods == Modifiers::ALT && is_character_key(shortcut.key) {
+        warnings.push(format!("{} may be needed to type characters on some keyboard layouts", shortcut));
+    }
+
+    warnings
+}
+
+fn is_character_key(key: Code) -> bool {
+    let name = format!("{:?}", key);
+    name.starts_with("Key") || name.starts_with("Digit")
+}
+
+/// Register every binding, replacing whatever is active. Failures are kept per
+/// action so the UI can show them; they don't stop the other bindings.
+pub fn register_all(app: &AppHandle, bindings: &ShortcutBindings) {
+    let _changing = CHANGING_BINDINGS.lock().unwrap_or_else(|e| e.into_inner());
+
+    let previous = std::mem::take(&mut *active_bindings());
+    for binding in previous {
+        if let Some(shortcut) = binding.shortcut {
+            let _ = app.global_shortcut().unregister(shortcut);
+        }
+    }
+
+    let mut registered = Vec::new();
+    for (action, accelerator) in bindings {
+        let result = parse_accelerator(accelerator).and_then(|shortcut| {
+            app.global_shortcut()
+                .register(shortcut)
+                .map_err(|e| anyhow!("Couldn't register {}: {}", shortcut, e))?;
+            Ok(shortcut)
+        });
+
+        match result {
+            Ok(shortcut) => {
+                println!("✅ Registered {} for {:?}", shortcut, action);
+                registered.push(ActiveBinding {
+                    action: *action,
+                    accelerator: accelerator.clone(),
+                    shortcut: Some(shortcut),
+                    error: None,
+                });
+            }
+            Err(e) => {
+                eprintln!("❌ [ERROR] {}", e);
+                registered.push(ActiveBinding {
+                    action: *action,
+                    accelerator: accelerator.clone(),
+                    shortcut: None,
+                    error: Some(e.to_string()),
+                });
+            }
+        }
+    }
+
+    *active_bindings() = registered;
+}
+
+/// Change or clear the binding for one action. The old combo is only released
+/// once the new one is registered, so a failed change leaves things as they were.
+pub fn rebind(app: &AppHandle, action: ShortcutAction, accelerator: Option<&str>) -> Result<ShortcutBindings> {
+    let _changing = CHANGING_BINDINGS.lock().unwrap_or_else(|e| e.into_inner());
+
+    let (bindings, old_shortcut) = {
+        let active = active_bindings();
+        let mut bindings: ShortcutBindings = active
+            .iter()
+            .map(|binding| (binding.action, binding.accelerator.clone()))
+            .collect();
+        match accelerator {
+            Some(accelerator) => bindings.insert(action, accelerator.trim().to_string()),
+            None => bindings.remove(&action),
+        };
+        let old_shortcut = active
+            .iter()
+            .find(|binding| binding.action == action)
+            .and_then(|binding| binding.shortcut);
+        (bindings, old_shortcut)
+    };
+    check_bindings(&bindings)?;
+
+    let new_binding = match accelerator {
+        Some(accelerator) => {
+            let shortcut = parse_accelerator(accelerator)?;
+
+            // Re-binding the same combo is a no-op for the OS
+            if old_shortcut.map(|old| old.id()) != Some(shortcut.id()) {
+                if let Err(e) = app.global_shortcut().register(shortcut) {
+                    bail!("Couldn't register {}: {}. It may be in use by another application.", shortcut, e);
+                }
+
+                if let Some(old_shortcut) = old_shortcut {
+                    let _ = app.global_shortcut().unregister(old_shortcut);
+                }
+            }
+
+            Some(ActiveBinding {
+                action,
+                accelerator: accelerator.trim().to_string(),
+                shortcut: Some(shortcut),
+                error: None,
+            })
+        }
+        None => {
+            if let Some(old_shortcut) = old_shortcut {
+                app.global_shortcut().unregister(old_shortcut)?;
+            }
+            None
+        }
+    };
+
+    let mut active = active_bindings();
+    active.retain(|binding| binding.action != action);
+    active.extend(new_binding);
+
+    Ok(bindings)
+}
+
+/// Status of every action, bound or not
+pub fn statuses() -> Vec<ShortcutStatus> {
+    let active = active_bindings();
+
+    ShortcutAction::ALL
+        .iter()
+        .map(|action| match active.iter().find(|binding| binding.action == *action) {
+            Some(binding) => ShortcutStatus {
+                action: *action,
+                accelerator: Some(binding.accelerator.clone()),
+                registered: binding.shortcut.is_some(),
+                error: binding.error.clone(),
+                warnings: parse_accelerator(&binding.accelerator)
+                    .map(|shortcut| shortcut_warnings(&shortcut))
+                    .unwrap_or_default(),
+            },
+            None => ShortcutStatus {
+                action: *action,
+                accelerator: None,
+                registered: false,
+                error: None,
+                warnings: Vec::new(),
+            },
+        })
+        .collect()
+}
+
+/// Global shortcut plugin handler
+pub fn handle_event(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    let action = ACTIVE_BINDINGS.lock().ok().and_then(|active| {
+        active
+            .iter()
+            .find(|binding| binding.shortcut.is_some_and(|bound| bound.id() == shortcut.id()))
+            .map(|binding| binding.action)
+    });
+    let Some(action) = action else {
+        return;
+    };
+
+    // Pasting starts once the shortcut's key is up; the rest react right away
+    let trigger = match action {
+        ShortcutAction::PastePrevious => ShortcutState::Released,
+        _ => ShortcutState::Pressed,
+    };
+    if event.state == trigger {
+        println!("🔧 [DEBUG] {} pressed - {:?}", shortcut, action);
+        run_action(app, action);
+    }
+}
+
+fn run_action(app: &AppHandle, action: ShortcutAction) {
+    match action {
+        ShortcutAction::ShowHistory => {
+            let _ = window::show_window(app);
+        }
+        ShortcutAction::OpenFavorites | ShortcutAction::OpenPasteStack => {
+            let _ = window::show_window(app);
+            // After window-shown, which resets the view to all entries
+            let _ = app.emit("shortcut-action", action);
+        }
+        ShortcutAction::PastePrevious => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = paste_previous(&app).await {
+                    eprintln!("❌ [ERROR] Paste previous failed: {}", e);
+                    paste::diagnostics::record_paste_error(&e.to_string());
+                }
+            });
+        }
+        ShortcutAction::ToggleRecording => {
+            let paused = !clipboard::is_recording_paused();
+            clipboard::set_recording_paused(paused);
+            println!("🔧 [DEBUG] Clipboard recording {}", if paused { "paused" } else { "resumed" });
+            let _ = app.emit("recording-paused", paused);
+        }
+    }
+}
+
+/// Paste the second most recent entry into the focused app. The most recent
+/// one is what's on the clipboard already, so plain Ctrl+V covers it.
+async fn paste_previous(app: &AppHandle) -> Result<()> {
+    let pool = app.state::<AppState>().pool.clone();
+    let entries = database::get_recent_entries(&pool, 2).await?;
+
+    let Some(previous) = entries.get(1) else {
+        return Ok(());
+    };
+    let Some(content) = database::get_entry_content(&pool, previous.id).await? else {
+        return Ok(());
+    };
+
+    // The window stays hidden, so the target is whatever app is in front now
+    let target_app = window::frontmost_app().or_else(window::get_previous_app);
+    let user_profiles = database::get_paste_profiles(&pool).await?;
+    let options = match paste::profiles::find_profile(target_app.as_deref(), &user_profiles) {
+        Some(profile) => paste::profiles::options_for_profile(&profile)?,
+        None => settings::current().paste_options(),
+    };
+
+    // Ctrl+V injected while the shortcut's Alt is still down would be Ctrl+Alt+V
+    paste::wait_for_modifiers_released(MODIFIER_RELEASE_TIMEOUT).await;
+
+    let outcome = paste::simulate_paste(content, &options).await?;
+    if outcome == paste::PasteOutcome::CopiedOnly {
+        let message = "Auto-paste isn't available in this session. The previous entry was copied - paste it with Ctrl+V.";
+        let _ = app.emit("paste-copied-only", message);
+
+        #[cfg(target_os = "linux")]
+        paste::wayland::notify_copy_only(message);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(pairs: &[(ShortcutAction, &str)]) -> ShortcutBindings {
+        pairs.iter().map(|(action, accelerator)| (*action, accelerator.to_string())).collect()
+    }
+
+    #[test]
+    fn test_default_bindings_are_valid() {
+        assert!(check_bindings(&default_bindings()).is_ok());
+        assert_eq!(default_bindings().get(&ShortcutAction::ShowHistory).map(String::as_str), Some("Alt+I"));
+    }
+
+    #[test]
+    fn test_parse_accelerator() {
+        let shortcut = parse_accelerator(" Ctrl+Shift+V ").unwrap();
+        assert_eq!(shortcut.mods, Modifiers::CONTROL | Modifiers::SHIFT);
+        assert_eq!(shortcut.key, Code::KeyV);
+
+        assert!(parse_accelerator("F13").is_ok(), "Function keys work without modifiers");
+        assert!(parse_accelerator("V").is_err(), "Bare letters would swallow typing");
+        assert!(parse_accelerator("Ctrl+").is_err());
+        assert!(parse_accelerator("Ctrl+Nope").is_err());
+    }
+
+    #[test]
+    fn test_duplicate_bindings_conflict() {
+        let result = check_bindings(&bindings(&[
+            (ShortcutAction::ShowHistory, "Ctrl+Alt+V"),
+            (ShortcutAction::OpenFavorites, "Alt+Control+V"),
+        ]));
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("already bound"), "{}", error);
+    }
+
+    #[test]
+    fn test_distinct_bindings_are_valid() {
+        let result = check_bindings(&bindings(&[
+            (ShortcutAction::ShowHistory, "Ctrl+Alt+V"),
+            (ShortcutAction::OpenFavorites, "Ctrl+Alt+F"),
+            (ShortcutAction::OpenPasteStack, "Ctrl+Alt+S"),
+            (ShortcutAction::ToggleRecording, "Ctrl+Alt+P"),
+        ]));
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_shortcut_warnings() {
+        let copy = parse_accelerator("CommandOrControl+C").unwrap();
+        assert_eq!(shortcut_warnings(&copy).len(), 1);
+
+        let alt_letter = parse_accelerator("Alt+I").unwrap();
+        assert_eq!(shortcut_warnings(&alt_letter).len(), 1, "Alt+letter clashes with AltGr/Option layouts");
+
+        let safe = parse_accelerator("Ctrl+Alt+V").unwrap();
+        assert!(shortcut_warnings(&safe).is_empty());
+    }
+
+    #[test]
+    fn test_bindings_json_uses_action_names() {
+        let json = serde_json::to_string(&bindings(&[(ShortcutAction::PastePrevious, "Ctrl+Alt+B")])).unwrap();
+        assert_eq!(json, r#"{"paste_previous":"Ctrl+Alt+B"}"#);
+
+        let parsed: ShortcutBindings = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.get(&ShortcutAction::PastePrevious).map(String::as_str), Some("Ctrl+Alt+B"));
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Row, SqliteConnection};
+use std::collections::HashMap;
+
+use crate::clipboard::classify;
+
+/// An entry as it travels between devices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedEntry {
+    pub content_type: String,
+    pub text_content: Option<String>,
+    pub metadata: Option<String>,
+    pub source_app: Option<String>,
+    pub created_at: i64,
+    pub favorite: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ChangeKind {
+    Add(SyncedEntry),
+    Update { text_content: Option<String> },
+    Pin { favorite: bool },
+    /// Tags live in the entry's metadata JSON, so this replaces the metadata
+    Tag { metadata: Option<String> },
+    Delete,
+}
+
+/// One line of a device's change log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub sync_id: String,
+    /// Milliseconds since the epoch on the device that made the change
+    pub ts: i64,
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+/// Contents of one log file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogFile {
+    pub device: String,
+    pub changes: Vec<Change>,
+}
+
+impl Change {
+    /// The per-field values this change sets. An add sets every field at once.
+    fn fields(&self) -> Result<Vec<(&'static str, Value)>> {
+        Ok(match &self.kind {
+            ChangeKind::Add(entry) => vec![
+                ("add", serde_json::to_value(entry)?),
+                ("text", serde_json::to_value(&entry.text_content)?),
+                ("favorite", Value::Bool(entry.favorite)),
+                ("metadata", serde_json::to_value(&entry.metadata)?),
+            ],
+            ChangeKind::Update { text_content } => vec![("text", serde_json::to_value(text_content)?)],
+            ChangeKind::Pin { favorite } => vec![("favorite", Value::Bool(*favorite))],
+            ChangeKind::Tag { metadata } => vec![("metadata", serde_json::to_value(metadata)?)],
+            ChangeKind::Delete => vec![("delete", Value::Null)],
+        })
+    }
+}
+
+/// Last write of one field: the newest (timestamp, device) wins, the device id
+/// breaks ties so every device picks the same winner
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    ts: i64,
+    device: String,
+}
+
+/// Merge `change` into the stored field versions, then rewrite the local entry
+/// from the winners. Returns whether any field changed. Applying the same
+/// change twice, or changes in any order, gives the same result.
+pub async fn apply_change(conn: &mut SqliteConnection, change: &Change, device: &str) -> Result<bool> {
+    let incoming = Version { ts: change.ts, device: device.to_string() };
+    let mut changed = false;
+
+    for (field, value) in change.fields()? {
+        let value = value.to_string();
+        let current = sqlx::query("SELECT ts, device, value FROM sync_versions WHERE sync_id = ? AND field = ?")
+            .bind(&change.sync_id)
+            .bind(field)
+            .fetch_optional(&mut *conn)
+            .await?
+            .map(|row| (Version { ts: row.get("ts"), device: row.get("device") }, row.get::<String, _>("value")));
+
+        // A device's changes are replayed in the order it made them, so of two
+        // changes within the same millisecond the later one wins
+        if let Some((version, current_value)) = current {
+            if version > incoming || (version == incoming && current_value == value) {
+                continue;
+            }
+        }
+
+        sqlx::query(
+            "INSERT INTO sync_versions (sync_id, field, ts, device, value) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(sync_id, field) DO UPDATE SET ts = excluded.ts, device = excluded.device, value = excluded.value",
+        )
+        .bind(&change.sync_id)
+        .bind(field)
+        .bind(incoming.ts)
+        .bind(&incoming.device)
+        .bind(&value)
+        .execute(&mut *conn)
+        .await?;
+        changed = true;
+    }
+
+    if changed {
+        materialize(conn, &change.sync_id).await?;
+    }
+    Ok(changed)
+}
+
+/// Make the local row match the winning field values: insert, update or delete it
+async fn materialize(conn: &mut SqliteConnection, sync_id: &str) -> Result<()> {
+    let rows = sqlx::query("SELECT field, ts, device, value FROM sync_versions WHERE sync_id = ?")
+        .bind(sync_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let fields: HashMap<String, (Version, Value)> = rows
+        .iter()
+        .map(|row| {
+            let version = Version { ts: row.get("ts"), device: row.get("device") };
+            let value = serde_json::from_str(row.get::<&str, _>("value")).unwrap_or(Value::Null);
+            (row.get::<String, _>("field"), (version, value))
+        })
+        .collect();
+
+    // Field changes for an entry whose add hasn't arrived yet wait for it
+    let Some((added, add_value)) = fields.get("add") else {
+        return Ok(());
+    };
+
+    // Deleted unless it was added again after the delete
+    if fields.get("delete").is_some_and(|(deleted, _)| deleted > added) {
+        sqlx::query("DELETE FROM clipboard_entries WHERE sync_id = ?")
+            .bind(sync_id)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(());
+    }
+
+    let mut entry: SyncedEntry = serde_json::from_value(add_value.clone())?;
+    if let Some((_, value)) = fields.get("text") {
+        entry.text_content = serde_json::from_value(value.clone())?;
+    }
+    if let Some((_, value)) = fields.get("favorite") {
+        entry.favorite = serde_json::from_value(value.clone())?;
+    }
+    if let Some((_, value)) = fields.get("metadata") {
+        entry.metadata = serde_json::from_value(value.clone())?;
+    }
+
+    let mut id: Option<i64> = sqlx::query_scalar("SELECT id FROM clipboard_entries WHERE sync_id = ?")
+        .bind(sync_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    // The same text copied on this device before it was synced
+    if id.is_none() {
+        id = sqlx::query_scalar(
+            "SELECT id FROM clipboard_entries WHERE sync_id IS NULL AND content_type = ? AND text_content IS ? LIMIT 1",
+        )
+        .bind(&entry.content_type)
+        .bind(&entry.text_content)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if let Some(id) = id {
+            sqlx::query("UPDATE clipboard_entries SET sync_id = ? WHERE id = ?")
+                .bind(sync_id)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    let classification = entry.text_content.as_deref().filter(|_| entry.content_type == "text").map(classify);
+    let kind = classification.map(|c| c.kind.as_str());
+    let kind_confidence = classification.map(|c| c.confidence);
+    let language = classification.and_then(|c| c.language).map(|language| language.as_str());
+
+    match id {
+        Some(id) => {
+            sqlx::query(
+                "UPDATE clipboard_entries SET text_content = ?, favorite = ?, metadata = ?, kind = ?, kind_confidence = ?, language = ?
+                 WHERE id = ?",
+            )
+            .bind(&entry.text_content)
+            .bind(entry.favorite)
+            .bind(&entry.metadata)
+            .bind(kind)
+            .bind(kind_confidence)
+            .bind(language)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+        }
+        None => {
+            sqlx::query(
+                "INSERT INTO clipboard_entries (content_type, text_content, metadata, created_at, favorite, source_app, sync_id, kind, kind_confidence, language)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&entry.content_type)
+            .bind(&entry.text_content)
+            .bind(&entry.metadata)
+            .bind(entry.created_at)
+            .bind(entry.favorite)
+            .bind(&entry.source_app)
+            .bind(sync_id)
+            .bind(kind)
+            .bind(kind_confidence)
+            .bind(language)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_json_shape() {
+        let change = Change { sync_id: "abc".to_string(), ts: 5, kind: ChangeKind::Pin { favorite: true } };
+        let json = serde_json::to_string(&change).unwrap();
+        assert_eq!(json, r#"{"sync_id":"abc","ts":5,"op":"pin","favorite":true}"#);
+        assert_eq!(serde_json::from_str::<Change>(&json).unwrap(), change);
+
+        let delete: Change = serde_json::from_str(r#"{"sync_id":"abc","ts":6,"op":"delete"}"#).unwrap();
+        assert_eq!(delete.kind, ChangeKind::Delete);
+    }
+
+    #[test]
+    fn test_version_order_breaks_ties_by_device() {
+        let a = Version { ts: 10, device: "a".to_string() };
+        let b = Version { ts: 10, device: "b".to_string() };
+        assert!(b > a);
+        assert!(Version { ts: 11, device: "a".to_string() } > b);
+    }
+}
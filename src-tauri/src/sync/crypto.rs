@@ -0,0 +1,122 @@
+use anyhow::{anyhow, bail, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+
+// File layout: MAGIC | salt | nonce | ChaCha20-Poly1305 ciphertext and tag
+const MAGIC: &[u8; 8] = b"SKBDSYN1";
+pub const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// Keys derived from the sync passphrase. Every device encrypts with its own
+/// salt, so derived keys are cached per salt.
+pub struct Keys {
+    passphrase: String,
+    derived: HashMap<[u8; SALT_LEN], [u8; KEY_LEN]>,
+}
+
+impl Keys {
+    pub fn new(passphrase: &str) -> Self {
+        Self { passphrase: passphrase.to_string(), derived: HashMap::new() }
+    }
+
+    fn key_for(&mut self, salt: &[u8; SALT_LEN]) -> [u8; KEY_LEN] {
+        *self.derived.entry(*salt).or_insert_with(|| {
+            let mut key = [0u8; KEY_LEN];
+            pbkdf2::derive(
+                pbkdf2::PBKDF2_HMAC_SHA256,
+                NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+                salt,
+                self.passphrase.as_bytes(),
+                &mut key,
+            );
+            key
+        })
+    }
+
+    pub fn seal(&mut self, salt: &[u8; SALT_LEN], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("No randomness available"))?;
+
+        let mut header = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(salt);
+        header.extend_from_slice(&nonce);
+
+        let mut data = plaintext.to_vec();
+        self.cipher(salt)?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(&header), &mut data)
+            .map_err(|_| anyhow!("Encryption failed"))?;
+
+        header.extend_from_slice(&data);
+        Ok(header)
+    }
+
+    pub fn open(&mut self, file: &[u8]) -> Result<Vec<u8>> {
+        let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+        if file.len() < header_len || &file[..MAGIC.len()] != MAGIC {
+            bail!("Not a sync log file");
+        }
+
+        let (header, ciphertext) = file.split_at(header_len);
+        let salt: [u8; SALT_LEN] = header[MAGIC.len()..MAGIC.len() + SALT_LEN].try_into()?;
+        let nonce: [u8; NONCE_LEN] = header[MAGIC.len() + SALT_LEN..].try_into()?;
+
+        let mut data = ciphertext.to_vec();
+        let plaintext = self
+            .cipher(&salt)?
+            .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(header), &mut data)
+            .map_err(|_| anyhow!("Can't decrypt, the passphrase differs or the file is damaged"))?;
+        Ok(plaintext.to_vec())
+    }
+
+    fn cipher(&mut self, salt: &[u8; SALT_LEN]) -> Result<LessSafeKey> {
+        let key = self.key_for(salt);
+        let unbound = UnboundKey::new(&CHACHA20_POLY1305, &key).map_err(|_| anyhow!("Invalid key"))?;
+        Ok(LessSafeKey::new(unbound))
+    }
+}
+
+/// Random bytes as lowercase hex, for device ids and salts
+pub fn random_hex(len: usize) -> Result<String> {
+    let mut bytes = vec![0u8; len];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| anyhow!("No randomness available"))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+pub fn parse_salt(hex: &str) -> Result<[u8; SALT_LEN]> {
+    if hex.len() != SALT_LEN * 2 {
+        bail!("Invalid sync salt");
+    }
+    let mut salt = [0u8; SALT_LEN];
+    for (i, byte) in salt.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)?;
+    }
+    Ok(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let salt = parse_salt(&random_hex(SALT_LEN).unwrap()).unwrap();
+        let sealed = Keys::new("correct horse").seal(&salt, b"secret clipboard").unwrap();
+
+        assert!(!sealed.windows(6).any(|window| window == b"secret"), "Plaintext must not be visible");
+        assert_eq!(Keys::new("correct horse").open(&sealed).unwrap(), b"secret clipboard");
+        assert!(Keys::new("wrong horse").open(&sealed).is_err());
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(Keys::new("correct horse").open(&tampered).is_err());
+    }
+}
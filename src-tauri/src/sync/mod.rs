@@ -0,0 +1,626 @@
+// Folder-based sync between devices. Each device appends encrypted change
+// logs to its own subdirectory of a shared folder (Syncthing, Dropbox, a NAS
+// mount) and merges the other devices' logs field by field, newest write wins.
+// Image entries stay local: their files aren't part of the history database.
+
+pub mod changes;
+mod crypto;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+use crate::transfer::content_hash;
+use changes::{apply_change, Change, ChangeKind, LogFile, SyncedEntry};
+use crypto::Keys;
+
+const LOG_EXTENSION: &str = "log";
+
+// One sync at a time, so a change is never sent or merged twice at once
+static RUNNING: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    /// Shared folder the logs are written to and read from
+    pub directory: String,
+    /// Encrypts the logs; every device needs the same one
+    pub passphrase: String,
+    pub interval_secs: u64,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: String::new(),
+            passphrase: String::new(),
+            interval_secs: 300,
+        }
+    }
+}
+
+impl SyncSettings {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if !Path::new(&self.directory).is_absolute() {
+            bail!("Sync directory must be an absolute path");
+        }
+        if self.passphrase.chars().count() < 8 {
+            bail!("Sync passphrase must be at least 8 characters");
+        }
+        if !(10..=86_400).contains(&self.interval_secs) {
+            bail!("Sync interval must be between 10 seconds and a day");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SyncReport {
+    /// Local changes written to this device's log
+    pub exported: usize,
+    /// Log files from other devices read this run
+    pub files_read: usize,
+    /// Changes from other devices that won over local state
+    pub applied: usize,
+    /// Files that couldn't be read, e.g. written with another passphrase
+    pub errors: Vec<String>,
+}
+
+/// Start or stop recording local changes to match `settings`. Disabling drops
+/// the unsent changes; enabling again shares every entry afresh.
+pub async fn configure(pool: &SqlitePool, settings: &SyncSettings) -> Result<()> {
+    if settings.enabled {
+        let mut conn = pool.acquire().await?;
+        identity(&mut conn).await?;
+        sqlx::query("INSERT OR IGNORE INTO sync_state (key, value) VALUES ('enabled', '1')")
+            .execute(&mut *conn)
+            .await?;
+    } else {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM sync_state WHERE key = 'enabled'").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM sync_changes").execute(&mut *tx).await?;
+        sqlx::query("UPDATE clipboard_entries SET sync_id = NULL").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM sync_versions").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM sync_state WHERE key LIKE 'seen:%'").execute(&mut *tx).await?;
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+/// Write local changes to this device's log, then merge every log from other
+/// devices that hasn't been read yet
+pub async fn sync_now(pool: &SqlitePool, settings: &SyncSettings) -> Result<SyncReport> {
+    if !settings.enabled {
+        bail!("Sync is not enabled");
+    }
+    settings.validate()?;
+    let _running = RUNNING.lock().await;
+    configure(pool, settings).await?;
+
+    let dir = PathBuf::from(&settings.directory);
+    let mut keys = Keys::new(&settings.passphrase);
+    let mut report = SyncReport::default();
+    let (device, salt) = identity(&mut *pool.acquire().await?).await?;
+
+    // Key derivation and file access happen before the write transaction, so
+    // the clipboard monitor never waits on them
+    let outgoing = pending_changes(&mut *pool.acquire().await?).await?;
+    if !outgoing.is_empty() {
+        let changes = outgoing.changes();
+        keys = tokio::task::spawn_blocking({
+            let (dir, device) = (dir.clone(), device.clone());
+            move || write_log(&dir, &device, &salt, &mut keys, &changes).map(|_| keys)
+        })
+        .await??;
+        report.exported = outgoing.len();
+    }
+
+    let paths = unread_logs(&mut *pool.acquire().await?, &dir, &device).await?;
+    let logs = tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .map(|path| {
+                let log = read_log(&path, &mut keys);
+                (path, log)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await?;
+
+    // Only the merge runs in the transaction. Edits made since the changes
+    // were collected stay in sync_changes for the next sync.
+    let mut tx = pool.begin().await?;
+
+    // Edits made while applying must not be recorded as local changes
+    sqlx::query("INSERT OR REPLACE INTO sync_state (key, value) VALUES ('applying', '1')")
+        .execute(&mut *tx)
+        .await?;
+
+    for change in outgoing.commit(&mut tx).await? {
+        apply_change(&mut tx, &change, &device).await?;
+    }
+
+    for (path, log) in logs {
+        let log = match log {
+            Ok(log) => log,
+            Err(e) => {
+                report.errors.push(format!("{}: {:#}", path.display(), e));
+                continue;
+            }
+        };
+
+        for change in &log.changes {
+            if apply_change(&mut tx, change, &log.device).await? {
+                report.applied += 1;
+            }
+        }
+        sqlx::query("INSERT OR IGNORE INTO sync_state (key, value) VALUES (?, '1')")
+            .bind(seen_key(&dir, &path))
+            .execute(&mut *tx)
+            .await?;
+        report.files_read += 1;
+    }
+
+    sqlx::query("DELETE FROM sync_state WHERE key = 'applying'")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(report)
+}
+
+/// This device's id and encryption salt, created on first use
+async fn identity(conn: &mut SqliteConnection) -> Result<(String, [u8; crypto::SALT_LEN])> {
+    sqlx::query("INSERT OR IGNORE INTO sync_state (key, value) VALUES ('device_id', ?), ('salt', ?)")
+        .bind(crypto::random_hex(8)?)
+        .bind(crypto::random_hex(crypto::SALT_LEN)?)
+        .execute(&mut *conn)
+        .await?;
+
+    let device: String = sqlx::query_scalar("SELECT value FROM sync_state WHERE key = 'device_id'")
+        .fetch_one(&mut *conn)
+        .await?;
+    let salt: String = sqlx::query_scalar("SELECT value FROM sync_state WHERE key = 'salt'")
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok((device, crypto::parse_salt(&salt)?))
+}
+
+/// Local changes not written to a log yet. They are collected without
+/// changing anything; `commit` marks them as sent once the log is written.
+#[derive(Default)]
+struct Outgoing {
+    /// Entries shared for the first time, with their local row id
+    added: Vec<(i64, Change)>,
+    /// Changes the triggers recorded, and the last `sync_changes` row among them
+    recorded: Vec<Change>,
+    last_seq: Option<i64>,
+}
+
+impl Outgoing {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.recorded.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.added.len() + self.recorded.len()
+    }
+
+    fn changes(&self) -> Vec<Change> {
+        self.added.iter().map(|(_, change)| change.clone()).chain(self.recorded.iter().cloned()).collect()
+    }
+
+    /// Give the added entries their ids and drop the sent recorded changes.
+    /// Returns the changes to merge into the local field versions.
+    async fn commit(self, conn: &mut SqliteConnection) -> Result<Vec<Change>> {
+        let mut sent = Vec::new();
+        for (id, change) in self.added {
+            let assigned = sqlx::query("UPDATE OR IGNORE clipboard_entries SET sync_id = ? WHERE id = ? AND sync_id IS NULL")
+                .bind(&change.sync_id)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?
+                .rows_affected();
+            if assigned > 0 {
+                sent.push(change);
+                continue;
+            }
+
+            // Deleted after the log was written: take it back on the other devices
+            let exists: Option<i64> = sqlx::query_scalar("SELECT 1 FROM clipboard_entries WHERE sync_id = ?")
+                .bind(&change.sync_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+            if exists.is_none() {
+                sqlx::query("INSERT INTO sync_changes (sync_id, op, value, ts) VALUES (?, 'delete', NULL, ?)")
+                    .bind(&change.sync_id)
+                    .bind(chrono::Utc::now().timestamp_millis())
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+
+        if let Some(last_seq) = self.last_seq {
+            sqlx::query("DELETE FROM sync_changes WHERE seq <= ?")
+                .bind(last_seq)
+                .execute(&mut *conn)
+                .await?;
+        }
+        sent.extend(self.recorded);
+        Ok(sent)
+    }
+}
+
+/// Entries that were never synced, each with a new id and an add change, and
+/// the changes the triggers recorded since the last sync
+async fn pending_changes(conn: &mut SqliteConnection) -> Result<Outgoing> {
+    let mut outgoing = share_new_entries(conn).await?;
+    let (recorded, last_seq) = recorded_changes(conn).await?;
+    outgoing.recorded = recorded;
+    outgoing.last_seq = last_seq;
+    Ok(outgoing)
+}
+
+/// Add changes for entries that were never synced. The id comes from the
+/// content, so the same text copied on two devices becomes one entry.
+async fn share_new_entries(conn: &mut SqliteConnection) -> Result<Outgoing> {
+    let rows = sqlx::query(
+        "SELECT id, content_type, text_content, metadata, source_app, created_at, favorite FROM clipboard_entries
+         WHERE sync_id IS NULL AND content_type != 'image' AND text_content IS NOT NULL AND NOT truncated",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut outgoing = Outgoing::default();
+    let mut shared = HashSet::new();
+    for row in rows {
+        let entry = SyncedEntry {
+            content_type: row.get("content_type"),
+            text_content: row.get("text_content"),
+            metadata: row.get("metadata"),
+            source_app: row.get("source_app"),
+            created_at: row.get("created_at"),
+            favorite: row.get("favorite"),
+        };
+        let sync_id = content_hash(&entry.content_type, entry.text_content.as_deref(), None, None);
+
+        // A local duplicate of an entry that already has this id stays unsynced
+        let taken: Option<i64> = sqlx::query_scalar("SELECT 1 FROM clipboard_entries WHERE sync_id = ?")
+            .bind(&sync_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if taken.is_some() || !shared.insert(sync_id.clone()) {
+            continue;
+        }
+
+        let change = Change { sync_id, ts: entry.created_at * 1000, kind: ChangeKind::Add(entry) };
+        outgoing.added.push((row.get("id"), change));
+    }
+    Ok(outgoing)
+}
+
+/// Changes the triggers recorded since the last sync, oldest first
+async fn recorded_changes(conn: &mut SqliteConnection) -> Result<(Vec<Change>, Option<i64>)> {
+    let rows = sqlx::query("SELECT seq, sync_id, op, value, ts FROM sync_changes ORDER BY seq")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut changes = Vec::new();
+    for row in &rows {
+        let value: Option<String> = row.get("value");
+        let kind = match row.get::<&str, _>("op") {
+            "pin" => ChangeKind::Pin { favorite: value.as_deref() == Some("true") },
+            "update" => ChangeKind::Update { text_content: serde_json::from_str(value.as_deref().unwrap_or("null"))? },
+            "tag" => ChangeKind::Tag { metadata: serde_json::from_str(value.as_deref().unwrap_or("null"))? },
+            "delete" => ChangeKind::Delete,
+            other => bail!("Unknown recorded change {}", other),
+        };
+        changes.push(Change { sync_id: row.get("sync_id"), ts: row.get("ts"), kind });
+    }
+
+    Ok((changes, rows.last().map(|row| row.get::<i64, _>("seq"))))
+}
+
+/// Write `changes` as a new file in this device's directory. The file is
+/// renamed into place so sync tools never pick up half of it.
+fn write_log(dir: &Path, device: &str, salt: &[u8; crypto::SALT_LEN], keys: &mut Keys, changes: &[Change]) -> Result<PathBuf> {
+    let device_dir = dir.join(device);
+    std::fs::create_dir_all(&device_dir).with_context(|| format!("Can't create {}", device_dir.display()))?;
+
+    let log = LogFile { device: device.to_string(), changes: changes.to_vec() };
+    let sealed = keys.seal(salt, &serde_json::to_vec(&log)?)?;
+
+    // Millisecond timestamp first so a device's logs list in the order written
+    let name = format!(
+        "{:013}-{}.{}",
+        chrono::Utc::now().timestamp_millis(),
+        crypto::random_hex(4)?,
+        LOG_EXTENSION
+    );
+    let path = device_dir.join(&name);
+    let partial = device_dir.join(format!(".{}.tmp", name));
+
+    std::fs::write(&partial, sealed)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+fn read_log(path: &Path, keys: &mut Keys) -> Result<LogFile> {
+    let plaintext = keys.open(&std::fs::read(path)?)?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Other devices' log files that haven't been merged yet, oldest first per device
+async fn unread_logs(conn: &mut SqliteConnection, dir: &Path, device: &str) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut logs = Vec::new();
+    for device_dir in std::fs::read_dir(dir)? {
+        let device_dir = device_dir?.path();
+        if !device_dir.is_dir() || device_dir.file_name().is_some_and(|name| name == device) {
+            continue;
+        }
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&device_dir)?
+            .filter_map(|file| file.ok().map(|file| file.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == LOG_EXTENSION))
+            .collect();
+        files.sort();
+        logs.extend(files);
+    }
+
+    let mut unread = Vec::new();
+    for path in logs {
+        let seen: Option<String> = sqlx::query_scalar("SELECT value FROM sync_state WHERE key = ?")
+            .bind(seen_key(dir, &path))
+            .fetch_optional(&mut *conn)
+            .await?;
+        if seen.is_none() {
+            unread.push(path);
+        }
+    }
+    Ok(unread)
+}
+
+/// sync_state key marking a log file as merged: "seen:<device>/<file>"
+fn seen_key(dir: &Path, path: &Path) -> String {
+    format!("seen:{}", path.strip_prefix(dir).unwrap_or(path).to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{self, Database, NewClipboardEntry};
+    use tempfile::TempDir;
+
+    struct Device {
+        db: Database,
+        settings: SyncSettings,
+        _dir: TempDir,
+    }
+
+    async fn device(sync_dir: &Path) -> Result<Device> {
+        let dir = tempfile::tempdir()?;
+        let db = Database::new(dir.path().join("clipboard.db")).await?;
+        let settings = SyncSettings {
+            enabled: true,
+            directory: sync_dir.to_string_lossy().into_owned(),
+            passphrase: "shared secret".to_string(),
+            ..SyncSettings::default()
+        };
+        configure(db.pool(), &settings).await?;
+        Ok(Device { db, settings, _dir: dir })
+    }
+
+    impl Device {
+        async fn sync(&self) -> Result<SyncReport> {
+            sync_now(self.db.pool(), &self.settings).await
+        }
+
+        async fn add(&self, text: &str) -> Result<i64> {
+            database::insert_entry(self.db.pool(), NewClipboardEntry::new_text(text.to_string())).await
+        }
+
+        async fn find(&self, text: &str) -> Result<Option<database::ClipboardEntry>> {
+            Ok(database::get_entries_for_export(self.db.pool(), &Default::default())
+                .await?
+                .into_iter()
+                .find(|entry| entry.text_content.as_deref() == Some(text)))
+        }
+
+        async fn texts(&self) -> Result<Vec<String>> {
+            let mut texts: Vec<String> = database::get_entries_for_export(self.db.pool(), &Default::default())
+                .await?
+                .into_iter()
+                .filter_map(|entry| entry.text_content)
+                .collect();
+            texts.sort();
+            Ok(texts)
+        }
+    }
+
+    /// Give the next change a later millisecond timestamp
+    async fn tick() {
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    #[tokio::test]
+    async fn test_entries_and_pins_reach_the_other_device() -> Result<()> {
+        let shared = tempfile::tempdir()?;
+        let laptop = device(shared.path()).await?;
+        let desktop = device(shared.path()).await?;
+
+        let id = laptop.add("git push --force-with-lease").await?;
+        database::set_favorite(laptop.db.pool(), id, true).await?;
+        desktop.add("ssh desktop").await?;
+
+        assert_eq!(laptop.sync().await?.exported, 1);
+        let report = desktop.sync().await?;
+        assert_eq!((report.files_read, report.exported), (1, 1));
+        laptop.sync().await?;
+
+        let expected = vec!["git push --force-with-lease".to_string(), "ssh desktop".to_string()];
+        assert_eq!(laptop.texts().await?, expected);
+        assert_eq!(desktop.texts().await?, expected);
+        assert!(desktop.find("git push --force-with-lease").await?.unwrap().favorite);
+
+        // Nothing new: syncing again changes nothing
+        let report = desktop.sync().await?;
+        assert_eq!((report.exported, report.files_read, report.applied), (0, 0, 0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_same_text_on_both_devices_is_one_entry() -> Result<()> {
+        let shared = tempfile::tempdir()?;
+        let laptop = device(shared.path()).await?;
+        let desktop = device(shared.path()).await?;
+
+        laptop.add("cargo build --release").await?;
+        desktop.add("cargo build --release").await?;
+
+        laptop.sync().await?;
+        desktop.sync().await?;
+        laptop.sync().await?;
+
+        assert_eq!(laptop.texts().await?, vec!["cargo build --release"]);
+        assert_eq!(desktop.texts().await?, vec!["cargo build --release"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_last_pin_wins_per_field() -> Result<()> {
+        let shared = tempfile::tempdir()?;
+        let laptop = device(shared.path()).await?;
+        let desktop = device(shared.path()).await?;
+
+        laptop.add("shared snippet").await?;
+        laptop.sync().await?;
+        desktop.sync().await?;
+
+        // Both edit while apart: the laptop pins, later the desktop tags
+        let laptop_entry = laptop.find("shared snippet").await?.unwrap();
+        database::set_favorite(laptop.db.pool(), laptop_entry.id, true).await?;
+        tick().await;
+        let desktop_entry = desktop.find("shared snippet").await?.unwrap();
+        database::set_tags(desktop.db.pool(), desktop_entry.id, &["work".to_string()]).await?;
+        tick().await;
+        // ... and then unpins, which is newer than the laptop's pin
+        database::set_favorite(desktop.db.pool(), desktop_entry.id, true).await?;
+        database::set_favorite(desktop.db.pool(), desktop_entry.id, false).await?;
+
+        laptop.sync().await?;
+        desktop.sync().await?;
+        laptop.sync().await?;
+
+        for device in [&laptop, &desktop] {
+            let entry = device.find("shared snippet").await?.unwrap();
+            assert!(!entry.favorite, "The desktop's later unpin wins");
+            assert_eq!(entry.metadata.as_deref(), Some(r#"{"tags":["work"]}"#), "The tag is kept alongside");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_leaves_a_tombstone() -> Result<()> {
+        let shared = tempfile::tempdir()?;
+        let laptop = device(shared.path()).await?;
+        let desktop = device(shared.path()).await?;
+
+        laptop.add("temporary").await?;
+        laptop.sync().await?;
+        desktop.sync().await?;
+
+        let entry = desktop.find("temporary").await?.unwrap();
+        tick().await;
+        database::delete_entry(desktop.db.pool(), entry.id).await?;
+        desktop.sync().await?;
+        laptop.sync().await?;
+        assert!(laptop.find("temporary").await?.is_none());
+
+        // A third device reading every log from the start doesn't bring it back
+        let tablet = device(shared.path()).await?;
+        tablet.sync().await?;
+        assert!(tablet.find("temporary").await?.is_none());
+        assert_eq!(tablet.texts().await?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retention_cleanup_stays_local() -> Result<()> {
+        let shared = tempfile::tempdir()?;
+        let laptop = device(shared.path()).await?;
+        let desktop = device(shared.path()).await?;
+
+        let id = laptop.add("note from last quarter").await?;
+        laptop.sync().await?;
+        desktop.sync().await?;
+
+        // The laptop keeps 30 days, the desktop keeps everything
+        sqlx::query("UPDATE clipboard_entries SET created_at = created_at - 60 * 86400 WHERE id = ?")
+            .bind(id)
+            .execute(laptop.db.pool())
+            .await?;
+        assert_eq!(database::delete_old_entries(laptop.db.pool(), 30).await?, 1);
+
+        assert_eq!(laptop.sync().await?.exported, 0);
+        desktop.sync().await?;
+        assert!(laptop.find("note from last quarter").await?.is_none());
+        assert!(desktop.find("note from last quarter").await?.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_entry_deleted_while_its_log_is_written_is_taken_back() -> Result<()> {
+        let shared = tempfile::tempdir()?;
+        let laptop = device(shared.path()).await?;
+        let id = laptop.add("copied and deleted").await?;
+
+        let pool = laptop.db.pool();
+        let outgoing = pending_changes(&mut *pool.acquire().await?).await?;
+        assert_eq!(outgoing.len(), 1);
+        database::delete_entry(pool, id).await?;
+
+        let mut tx = pool.begin().await?;
+        assert!(outgoing.commit(&mut tx).await?.is_empty());
+        tx.commit().await?;
+
+        let (recorded, _) = recorded_changes(&mut *pool.acquire().await?).await?;
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].kind, ChangeKind::Delete);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_logs_are_encrypted_and_need_the_passphrase() -> Result<()> {
+        let shared = tempfile::tempdir()?;
+        let laptop = device(shared.path()).await?;
+        laptop.add("top secret token").await?;
+        laptop.sync().await?;
+
+        for device_dir in std::fs::read_dir(shared.path())? {
+            for file in std::fs::read_dir(device_dir?.path())? {
+                let data = std::fs::read(file?.path())?;
+                assert!(!data.windows(6).any(|window| window == b"secret"));
+            }
+        }
+
+        let mut stranger = device(shared.path()).await?;
+        stranger.settings.passphrase = "another passphrase".to_string();
+        let report = stranger.sync().await?;
+        assert_eq!(report.errors.len(), 1);
+        assert!(stranger.find("top secret token").await?.is_none());
+        Ok(())
+    }
+}
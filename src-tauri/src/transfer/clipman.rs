@@ -0,0 +1,153 @@
+use anyhow::{bail, Result};
+use std::path::Path;
+
+use super::import::extension_of;
+use super::{ImportItem, ImportRecord, ImportedImage};
+use crate::database::NewClipboardEntry;
+
+/// Read Xfce Clipman's `textsrc` key file plus the `image<N>.png` files saved
+/// next to it. Clipman keeps no timestamps, so items get one second per
+/// position counting back from now, newest first.
+pub fn read_items(path: &Path) -> Result<Vec<ImportItem>> {
+    let contents = std::fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let now = chrono::Utc::now().timestamp();
+
+    let mut items: Vec<ImportItem> = parse_texts(&contents)?
+        .into_iter()
+        .map(|text| {
+            if text.trim().is_empty() {
+                return ImportItem::Skipped;
+            }
+            ImportItem::Entry(ImportRecord {
+                entry: NewClipboardEntry::new_text_with_source(text, Some("Clipman".to_string())),
+                created_at: 0,
+                favorite: false,
+                image: None,
+            })
+        })
+        .collect();
+
+    for index in 0.. {
+        let image_path = dir.join(format!("image{}.png", index));
+        if !image_path.is_file() {
+            break;
+        }
+        items.push(match std::fs::read(&image_path) {
+            Ok(data) => ImportItem::Entry(ImportRecord {
+                entry: NewClipboardEntry {
+                    content_type: "image".to_string(),
+                    text_content: None,
+                    file_path: None,
+                    metadata: None,
+                    source_app: Some("Clipman".to_string()),
+                },
+                created_at: 0,
+                favorite: false,
+                image: Some(ImportedImage { data, extension: extension_of(&image_path) }),
+            }),
+            Err(e) => ImportItem::Invalid(format!("{}: {}", image_path.display(), e)),
+        });
+    }
+
+    let records = items.iter_mut().filter_map(|item| match item {
+        ImportItem::Entry(record) => Some(record),
+        _ => None,
+    });
+    for (position, record) in records.enumerate() {
+        record.created_at = now - position as i64;
+    }
+
+    Ok(items)
+}
+
+/// The `texts` string list from the `[texts]` group
+fn parse_texts(contents: &str) -> Result<Vec<String>> {
+    let mut in_texts = false;
+
+    for line in contents.lines() {
+        let line = line.trim_start();
+        if line.starts_with('[') {
+            in_texts = line.trim_end() == "[texts]";
+        } else if in_texts {
+            if let Some(value) = line.strip_prefix("texts=") {
+                return Ok(split_string_list(value));
+            }
+        }
+    }
+
+    bail!("no [texts] group with a texts= key")
+}
+
+/// Split a GKeyFile string list, undoing its escapes (`\;`, `\n`, `\s`, ...)
+fn split_string_list(value: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next() {
+                Some('n') => current.push('\n'),
+                Some('t') => current.push('\t'),
+                Some('r') => current.push('\r'),
+                Some('s') => current.push(' '),
+                Some(other) => current.push(other),
+                None => current.push('\\'),
+            },
+            ';' => texts.push(std::mem::take(&mut current)),
+            _ => current.push(ch),
+        }
+    }
+
+    // A list normally ends with ';', but the last separator is optional
+    if !current.is_empty() {
+        texts.push(current);
+    }
+    texts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_string_list_unescapes() {
+        assert_eq!(
+            split_string_list(r"one;two\;three;line\nbreak;\stab\there;back\\slash;"),
+            vec!["one", "two;three", "line\nbreak", " tab\there", r"back\slash"]
+        );
+        assert_eq!(split_string_list("no trailing separator"), vec!["no trailing separator"]);
+    }
+
+    #[test]
+    fn test_reads_textsrc_and_images() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/importers/clipman/textsrc");
+        let items = read_items(&path).unwrap();
+
+        let records: Vec<&ImportRecord> = items
+            .iter()
+            .filter_map(|item| match item {
+                ImportItem::Entry(record) => Some(record),
+                _ => None,
+            })
+            .collect();
+
+        let texts: Vec<Option<&str>> = records.iter().map(|record| record.entry.text_content.as_deref()).collect();
+        assert_eq!(
+            texts,
+            vec![Some("ssh deploy@example.org"), Some("fn main() {\n    println!(\"hi; there\");\n}"), None]
+        );
+        assert_eq!(records[2].entry.content_type, "image");
+        assert!(records[2].image.as_ref().is_some_and(|image| image::load_from_memory(&image.data).is_ok()));
+
+        // Newest first, one second apart
+        assert_eq!(records[0].created_at - records[1].created_at, 1);
+        assert_eq!(items.iter().filter(|item| matches!(item, ImportItem::Skipped)).count(), 1);
+    }
+
+    #[test]
+    fn test_rejects_file_without_texts() {
+        assert!(parse_texts("[settings]\nmax=10\n").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::import::extension_of;
+use super::{ImportItem, ImportRecord, ImportedImage};
+use crate::database::NewClipboardEntry;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp", "svg"];
+
+/// Read a CopyQ tab synchronized to a directory (the "Synchronize items to
+/// files" plugin). Each item is one or more files sharing a base name, e.g.
+/// `copyq_0001.txt` plus `copyq_0001.html`; the file time is the item's time.
+pub fn read_items(dir: &Path) -> Result<Vec<ImportItem>> {
+    // Base name → files of that item
+    let mut groups: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+    for file in std::fs::read_dir(dir)? {
+        let path = file?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        // Hidden index files and CopyQ's own binary item data
+        if name.starts_with('.') || name.ends_with("_copyq.dat") || !path.is_file() {
+            continue;
+        }
+
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or(name).to_string();
+        groups.entry(stem).or_default().push(path);
+    }
+
+    Ok(groups.into_values().map(|files| read_item(&files)).collect())
+}
+
+fn read_item(files: &[PathBuf]) -> ImportItem {
+    let find = |extensions: &[&str]| {
+        files
+            .iter()
+            .find(|file| extensions.contains(&extension_of(file).as_str()))
+    };
+
+    let result = if let Some(file) = find(&["txt"]) {
+        read_text(file, "text")
+    } else if let Some(file) = find(&["html", "htm"]) {
+        read_text(file, "html")
+    } else if let Some(file) = find(IMAGE_EXTENSIONS) {
+        read_image(file)
+    } else {
+        return ImportItem::Skipped;
+    };
+
+    match result {
+        Ok(Some(record)) => ImportItem::Entry(record),
+        Ok(None) => ImportItem::Skipped,
+        Err(e) => ImportItem::Invalid(format!("{}: {}", files[0].display(), e)),
+    }
+}
+
+fn read_text(file: &Path, content_type: &str) -> Result<Option<ImportRecord>> {
+    let text = std::fs::read_to_string(file)?;
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(ImportRecord {
+        entry: NewClipboardEntry {
+            content_type: content_type.to_string(),
+            ..NewClipboardEntry::new_text_with_source(text, Some("CopyQ".to_string()))
+        },
+        created_at: modified_at(file)?,
+        favorite: false,
+        image: None,
+    }))
+}
+
+fn read_image(file: &Path) -> Result<Option<ImportRecord>> {
+    Ok(Some(ImportRecord {
+        entry: NewClipboardEntry {
+            content_type: "image".to_string(),
+            text_content: None,
+            file_path: None,
+            metadata: None,
+            source_app: Some("CopyQ".to_string()),
+        },
+        created_at: modified_at(file)?,
+        favorite: false,
+        image: Some(ImportedImage { data: std::fs::read(file)?, extension: extension_of(file) }),
+    }))
+}
+
+fn modified_at(file: &Path) -> Result<i64> {
+    let modified = std::fs::metadata(file)?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH).map(|age| age.as_secs() as i64).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/importers/copyq")
+    }
+
+    fn records(items: &[ImportItem]) -> Vec<&ImportRecord> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                ImportItem::Entry(record) => Some(record),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reads_synced_tab() {
+        let items = read_items(&fixture_dir()).unwrap();
+        let records = records(&items);
+
+        let kinds: Vec<(&str, Option<&str>)> = records
+            .iter()
+            .map(|record| (record.entry.content_type.as_str(), record.entry.text_content.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("text", Some("git log --oneline --graph\n")),
+                ("text", Some("Plain text wins over its HTML copy\n")),
+                ("html", Some("<b>Only HTML</b>\n")),
+                ("image", None),
+            ]
+        );
+
+        assert!(records.iter().all(|record| record.entry.source_app.as_deref() == Some("CopyQ")));
+        assert!(records[3].image.as_ref().is_some_and(|image| image.extension == "png"));
+        assert!(records[3].image.as_ref().is_some_and(|image| image::load_from_memory(&image.data).is_ok()));
+    }
+
+    #[test]
+    fn test_skips_empty_and_unknown_items() {
+        let items = read_items(&fixture_dir()).unwrap();
+        let skipped = items.iter().filter(|item| matches!(item, ImportItem::Skipped)).count();
+
+        // copyq_0004.txt is blank and copyq_0005.bin has no readable format
+        assert_eq!(skipped, 2);
+    }
+}
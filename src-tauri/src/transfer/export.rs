@@ -0,0 +1,122 @@
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::{content_hash, image_data, ExportedEntry, ARCHIVE_BLOB_DIR, ARCHIVE_HISTORY_FILE};
+use crate::database::{self, ClipboardEntry, ExportFilter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON object per line; image entries only keep their file path
+    #[default]
+    Jsonl,
+    /// .tar.gz with history.jsonl plus the image files, for moving to another machine
+    Archive,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub entries: usize,
+    pub blobs: usize,
+}
+
+/// Write the entries matching `filter` to `path`
+pub async fn export_history(pool: &SqlitePool, path: &Path, format: ExportFormat, filter: &ExportFilter) -> Result<ExportSummary> {
+    let mut entries = database::get_entries_for_export(pool, filter).await?;
+    // Offloaded texts are exported whole, as ordinary entries
+    for entry in entries.iter_mut().filter(|entry| entry.truncated) {
+        entry.text_content = database::offload::load_text(entry).await?;
+        entry.file_path = None;
+        entry.truncated = false;
+    }
+    let path = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || match format {
+        ExportFormat::Jsonl => write_jsonl(&entries, &path),
+        ExportFormat::Archive => write_archive(&entries, &path),
+    })
+    .await?
+}
+
+fn write_jsonl(entries: &[ClipboardEntry], path: &Path) -> Result<ExportSummary> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    for entry in entries {
+        let data = image_data(&entry.content_type, entry.file_path.as_deref());
+        let exported = to_exported(entry, data.as_deref(), None);
+        serde_json::to_writer(&mut writer, &exported)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+
+    Ok(ExportSummary { entries: entries.len(), blobs: 0 })
+}
+
+fn write_archive(entries: &[ClipboardEntry], path: &Path) -> Result<ExportSummary> {
+    let encoder = GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default());
+    let mut archive = tar::Builder::new(encoder);
+
+    let mut lines = Vec::new();
+    let mut written_blobs = HashSet::new();
+
+    for entry in entries {
+        let data = image_data(&entry.content_type, entry.file_path.as_deref());
+
+        let blob = data.as_ref().map(|data| {
+            let hash = content_hash(&entry.content_type, None, Some(data), None);
+            let extension = entry
+                .file_path
+                .as_deref()
+                .and_then(|file_path| Path::new(file_path).extension())
+                .and_then(|extension| extension.to_str())
+                .unwrap_or("bin");
+            format!("{}/{}.{}", ARCHIVE_BLOB_DIR, hash, extension)
+        });
+
+        // Identical images are stored once
+        if let (Some(blob), Some(data)) = (&blob, &data) {
+            if written_blobs.insert(blob.clone()) {
+                append_file(&mut archive, blob, data, entry.created_at)?;
+            }
+        }
+
+        serde_json::to_writer(&mut lines, &to_exported(entry, data.as_deref(), blob))?;
+        lines.push(b'\n');
+    }
+
+    append_file(&mut archive, ARCHIVE_HISTORY_FILE, &lines, chrono::Utc::now().timestamp())?;
+    archive.into_inner()?.finish()?.flush()?;
+
+    Ok(ExportSummary { entries: entries.len(), blobs: written_blobs.len() })
+}
+
+fn append_file<W: Write>(archive: &mut tar::Builder<W>, name: &str, data: &[u8], mtime: i64) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(mtime.max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+fn to_exported(entry: &ClipboardEntry, data: Option<&[u8]>, blob: Option<String>) -> ExportedEntry {
+    ExportedEntry {
+        content_type: entry.content_type.clone(),
+        text_content: entry.text_content.clone(),
+        file_path: entry.file_path.clone(),
+        blob,
+        metadata: entry.metadata.clone(),
+        created_at: entry.created_at,
+        favorite: entry.favorite,
+        source_app: entry.source_app.clone(),
+        content_hash: content_hash(&entry.content_type, entry.text_content.as_deref(), data, entry.file_path.as_deref()),
+    }
+}
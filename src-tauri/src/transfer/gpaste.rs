@@ -0,0 +1,178 @@
+use anyhow::{anyhow, Result};
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::path::Path;
+
+use super::import::extension_of;
+use super::{ImportItem, ImportRecord, ImportedImage};
+use crate::database::NewClipboardEntry;
+
+/// Read a GPaste `history.xml`. Items are listed newest first; items without
+/// a `date` attribute get one second per position so the order survives.
+pub fn read_items(path: &Path) -> Result<Vec<ImportItem>> {
+    let xml = std::fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    parse_history(&xml, base_dir, chrono::Utc::now().timestamp())
+}
+
+/// An `<item>` as it appears in the file
+#[derive(Default)]
+struct RawItem {
+    kind: String,
+    date: Option<i64>,
+    value: String,
+}
+
+fn parse_history(xml: &str, base_dir: &Path, now: i64) -> Result<Vec<ImportItem>> {
+    let mut reader = Reader::from_str(xml);
+    let mut items = Vec::new();
+    let mut current: Option<RawItem> = None;
+    let mut in_value = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) if start.name().as_ref() == b"item" => {
+                current = Some(raw_item(&start)?);
+            }
+            Event::Empty(start) if start.name().as_ref() == b"item" => {
+                let raw = raw_item(&start)?;
+                items.push(to_item(raw, base_dir, now - items.len() as i64));
+            }
+            Event::Start(start) if start.name().as_ref() == b"value" => in_value = current.is_some(),
+            Event::End(end) if end.name().as_ref() == b"value" => in_value = false,
+            Event::End(end) if end.name().as_ref() == b"item" => {
+                if let Some(raw) = current.take() {
+                    items.push(to_item(raw, base_dir, now - items.len() as i64));
+                }
+            }
+            Event::Text(text) if in_value => push_value(&mut current, &text.decode()?),
+            Event::CData(data) if in_value => push_value(&mut current, &data.decode()?),
+            Event::GeneralRef(reference) if in_value => {
+                let resolved = match reference.resolve_char_ref()? {
+                    Some(ch) => ch.to_string(),
+                    None => {
+                        let name = reference.decode()?;
+                        resolve_predefined_entity(&name)
+                            .ok_or_else(|| anyhow!("unknown entity &{};", name))?
+                            .to_string()
+                    }
+                };
+                push_value(&mut current, &resolved);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(items)
+}
+
+fn raw_item(start: &BytesStart) -> Result<RawItem> {
+    let mut raw = RawItem::default();
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        let value = attribute.unescape_value()?;
+        match attribute.key.as_ref() {
+            b"kind" => raw.kind = value.into_owned(),
+            b"date" => raw.date = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+    Ok(raw)
+}
+
+fn push_value(current: &mut Option<RawItem>, text: &str) {
+    if let Some(raw) = current {
+        raw.value.push_str(text);
+    }
+}
+
+fn to_item(raw: RawItem, base_dir: &Path, fallback_time: i64) -> ImportItem {
+    let created_at = raw.date.unwrap_or(fallback_time);
+
+    let entry = match raw.kind.as_str() {
+        // Uris are newline-separated file URIs, kept as the text that was copied
+        "Text" | "Uris" if !raw.value.trim().is_empty() => {
+            NewClipboardEntry::new_text_with_source(raw.value, Some("GPaste".to_string()))
+        }
+        "Image" => {
+            let image_path = base_dir.join(raw.value.trim());
+            let data = match std::fs::read(&image_path) {
+                Ok(data) => data,
+                Err(e) => return ImportItem::Invalid(format!("{}: {}", image_path.display(), e)),
+            };
+
+            return ImportItem::Entry(ImportRecord {
+                entry: NewClipboardEntry {
+                    content_type: "image".to_string(),
+                    text_content: None,
+                    file_path: None,
+                    metadata: None,
+                    source_app: Some("GPaste".to_string()),
+                },
+                created_at,
+                favorite: false,
+                image: Some(ImportedImage { data, extension: extension_of(&image_path) }),
+            });
+        }
+        // Passwords stay in GPaste; unknown kinds have nothing to map to
+        _ => return ImportItem::Skipped,
+    };
+
+    ImportItem::Entry(ImportRecord { entry, created_at, favorite: false, image: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/importers/gpaste/history.xml")
+    }
+
+    #[test]
+    fn test_reads_history_xml() {
+        let xml = std::fs::read_to_string(fixture()).unwrap();
+        let items = parse_history(&xml, fixture().parent().unwrap(), 2_000_000_000).unwrap();
+
+        let summary: Vec<(String, Option<String>, i64)> = items
+            .iter()
+            .filter_map(|item| match item {
+                ImportItem::Entry(record) => Some((
+                    record.entry.content_type.clone(),
+                    record.entry.text_content.clone(),
+                    record.created_at,
+                )),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("text".to_string(), Some("cargo test --workspace".to_string()), 1_700_000_300),
+                ("text".to_string(), Some("a < b && \"c\" é".to_string()), 1_999_999_999),
+                ("text".to_string(), Some("file:///home/user/notes.txt".to_string()), 1_700_000_100),
+                ("image".to_string(), None, 1_700_000_050),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skips_passwords_and_unknown_kinds() {
+        let xml = std::fs::read_to_string(fixture()).unwrap();
+        let items = parse_history(&xml, fixture().parent().unwrap(), 2_000_000_000).unwrap();
+
+        let skipped = items.iter().filter(|item| matches!(item, ImportItem::Skipped)).count();
+        assert_eq!(skipped, 2);
+        assert!(!items.iter().any(|item| matches!(item, ImportItem::Invalid(_))));
+    }
+
+    #[test]
+    fn test_missing_image_is_invalid() {
+        let xml = r#"<history version="2.0"><item kind="Image" date="1"><value><![CDATA[gone.png]]></value></item></history>"#;
+        let items = parse_history(xml, Path::new("/nonexistent"), 0).unwrap();
+        assert!(matches!(&items[0], ImportItem::Invalid(message) if message.contains("gone.png")));
+    }
+}
@@ -0,0 +1,504 @@
+use anyhow::{anyhow, bail, Result};
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::{content_hash, image_data, ExportedEntry, ARCHIVE_BLOB_DIR, ARCHIVE_HISTORY_FILE};
+use crate::database::{self, offload, ExportFilter, NewClipboardEntry};
+use crate::settings;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// What an import did, or would do for a dry run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: usize,
+    /// Already in the database (or earlier in the same file)
+    pub duplicates: usize,
+    /// Duplicates that were favorites in the import but not locally
+    pub favorites_updated: usize,
+    /// Items deliberately left out, e.g. passwords or unsupported kinds
+    pub skipped: usize,
+    pub invalid: usize,
+    /// One message per invalid item
+    pub errors: Vec<String>,
+}
+
+/// An entry to merge into the history, whatever format it came from
+#[derive(Debug, Clone)]
+pub struct ImportRecord {
+    pub entry: NewClipboardEntry,
+    pub created_at: i64,
+    pub favorite: bool,
+    /// Image bytes to keep a copy of in the blob dir; the entry then points at the copy
+    pub image: Option<ImportedImage>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedImage {
+    pub data: Vec<u8>,
+    pub extension: String,
+}
+
+/// One item read from an import source
+#[derive(Debug, Clone)]
+pub enum ImportItem {
+    Entry(ImportRecord),
+    Skipped,
+    /// Unreadable item, with a message that says where it is
+    Invalid(String),
+}
+
+/// Contents of a JSON Lines file or an export archive
+struct ImportSource {
+    history: String,
+    blobs: HashMap<String, Vec<u8>>,
+}
+
+/// Merge an export (JSON Lines or archive) into the database. Entries whose
+/// content already exists are skipped. Image data from an archive is written
+/// to `blob_dir`. With `dry_run` nothing is written.
+pub async fn import_history(pool: &SqlitePool, path: &Path, blob_dir: &Path, dry_run: bool) -> Result<ImportReport> {
+    let source_path = path.to_path_buf();
+    let source = tokio::task::spawn_blocking(move || read_source(&source_path)).await??;
+
+    let items = source
+        .history
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| match parse_record(line, &source.blobs) {
+            Ok(record) => ImportItem::Entry(record),
+            Err(e) => ImportItem::Invalid(format!("line {}: {}", index + 1, e)),
+        })
+        .collect();
+
+    merge_items(pool, items, blob_dir, dry_run).await
+}
+
+/// Add `items` to the database, skipping content that's already there.
+/// Texts over `max_capture_bytes` are skipped, large ones are offloaded to
+/// the blob store like copied ones.
+pub async fn merge_items(pool: &SqlitePool, items: Vec<ImportItem>, blob_dir: &Path, dry_run: bool) -> Result<ImportReport> {
+    let current = settings::current();
+    let mut known = existing_hashes(pool).await?;
+    let mut report = ImportReport { dry_run, ..Default::default() };
+
+    for item in items {
+        report.total += 1;
+
+        let mut record = match item {
+            ImportItem::Entry(record) => record,
+            ImportItem::Skipped => {
+                report.skipped += 1;
+                continue;
+            }
+            ImportItem::Invalid(message) => {
+                report.invalid += 1;
+                report.errors.push(message);
+                continue;
+            }
+        };
+
+        if record.entry.text_content.as_ref().is_some_and(|text| text.len() as u64 > current.max_capture_bytes) {
+            report.skipped += 1;
+            continue;
+        }
+
+        let local = match record.image {
+            Some(_) => None,
+            None => image_data(&record.entry.content_type, record.entry.file_path.as_deref()),
+        };
+        let data = record.image.as_ref().map(|image| image.data.as_slice()).or(local.as_deref());
+        let hash = content_hash(
+            &record.entry.content_type,
+            record.entry.text_content.as_deref(),
+            data,
+            record.entry.file_path.as_deref(),
+        );
+
+        if let Some((id, favorite)) = known.get_mut(&hash) {
+            report.duplicates += 1;
+            if record.favorite && !*favorite {
+                if !dry_run {
+                    database::set_favorite(pool, *id, true).await?;
+                }
+                *favorite = true;
+                report.favorites_updated += 1;
+            }
+            continue;
+        }
+
+        if let Some(image) = &record.image {
+            let target = blob_dir.join(format!("{}.{}", hash, image.extension));
+            if !dry_run {
+                std::fs::create_dir_all(blob_dir)?;
+                std::fs::write(&target, &image.data)?;
+            }
+            record.entry.file_path = Some(target.to_string_lossy().into_owned());
+        }
+
+        let id = if dry_run {
+            0
+        } else {
+            offload::insert_text_at(
+                pool,
+                blob_dir,
+                record.entry,
+                record.created_at,
+                record.favorite,
+                current.offload_threshold_bytes,
+            )
+            .await?
+        };
+        known.insert(hash, (id, record.favorite));
+        report.imported += 1;
+    }
+
+    Ok(report)
+}
+
+fn parse_record(line: &str, blobs: &HashMap<String, Vec<u8>>) -> Result<ImportRecord> {
+    let record: ExportedEntry = serde_json::from_str(line)?;
+
+    if record.content_type.is_empty() {
+        bail!("content_type is empty");
+    }
+    if record.text_content.is_none() && record.file_path.is_none() && record.blob.is_none() {
+        bail!("entry has no content");
+    }
+
+    let image = match &record.blob {
+        Some(blob) => {
+            let data = blobs
+                .get(blob)
+                .ok_or_else(|| anyhow!("{} is missing from the archive", blob))?;
+            Some(ImportedImage { data: data.clone(), extension: extension_of(blob) })
+        }
+        None => None,
+    };
+
+    Ok(ImportRecord {
+        entry: NewClipboardEntry {
+            content_type: record.content_type,
+            text_content: record.text_content,
+            file_path: record.file_path,
+            metadata: record.metadata,
+            source_app: record.source_app,
+        },
+        created_at: record.created_at,
+        favorite: record.favorite,
+        image,
+    })
+}
+
+/// Lowercased file extension, "bin" if there is none
+pub fn extension_of(path: impl AsRef<Path>) -> String {
+    path.as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase())
+        .unwrap_or_else(|| "bin".to_string())
+}
+
+fn read_source(path: &Path) -> Result<ImportSource> {
+    let mut magic = [0u8; 2];
+    let is_archive = File::open(path)?.read(&mut magic)? == 2 && magic == GZIP_MAGIC;
+
+    if !is_archive {
+        return Ok(ImportSource { history: std::fs::read_to_string(path)?, blobs: HashMap::new() });
+    }
+
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    let mut history = None;
+    let mut blobs = HashMap::new();
+
+    for file in archive.entries()? {
+        let mut file = file?;
+        let name = file.path()?.to_string_lossy().into_owned();
+
+        if name == ARCHIVE_HISTORY_FILE {
+            let mut text = String::new();
+            file.read_to_string(&mut text)?;
+            history = Some(text);
+        } else if name.starts_with(&format!("{}/", ARCHIVE_BLOB_DIR)) {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            blobs.insert(name, data);
+        }
+    }
+
+    match history {
+        Some(history) => Ok(ImportSource { history, blobs }),
+        None => bail!("{} is not a SuperKBD export (no {})", path.display(), ARCHIVE_HISTORY_FILE),
+    }
+}
+
+/// Content hash → (id, favorite) for everything already in the database.
+/// Offloaded texts are hashed by their full body, like an export carries them.
+async fn existing_hashes(pool: &SqlitePool) -> Result<HashMap<String, (i64, bool)>> {
+    let entries = database::get_entries_for_export(pool, &ExportFilter::default()).await?;
+
+    let mut hashes = HashMap::with_capacity(entries.len());
+    for entry in entries {
+        let text = if entry.truncated {
+            offload::load_text(&entry).await?
+        } else {
+            entry.text_content.clone()
+        };
+        let data = image_data(&entry.content_type, entry.file_path.as_deref());
+        let hash = content_hash(&entry.content_type, text.as_deref(), data.as_deref(), entry.file_path.as_deref());
+        hashes.insert(hash, (entry.id, entry.favorite));
+    }
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transfer::{export_history, ExportFormat};
+    use tempfile::TempDir;
+
+    async fn setup_test_db() -> Result<SqlitePool> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await?;
+        database::migrations::run_migrations(&pool).await?;
+        Ok(pool)
+    }
+
+    /// A history with every field that has to survive: favorites, metadata,
+    /// source apps, old timestamps and an image on disk
+    async fn seed(pool: &SqlitePool, dir: &TempDir) -> Result<()> {
+        let image_path = dir.path().join("shot.png");
+        std::fs::write(&image_path, b"\x89PNG fake image")?;
+
+        let entries = [
+            (NewClipboardEntry::new_text_with_source("plain".to_string(), Some("Terminal".to_string())), 1_600_000_000, false),
+            (
+                NewClipboardEntry {
+                    metadata: Some(r#"{"tags":["work"]}"#.to_string()),
+                    ..NewClipboardEntry::new_text("tagged favorite".to_string())
+                },
+                1_650_000_000,
+                true,
+            ),
+            (
+                NewClipboardEntry {
+                    content_type: "image".to_string(),
+                    text_content: None,
+                    file_path: Some(image_path.to_string_lossy().into_owned()),
+                    metadata: Some(r#"{"width":10}"#.to_string()),
+                    source_app: Some("Preview".to_string()),
+                },
+                1_700_000_000,
+                false,
+            ),
+        ];
+
+        for (entry, created_at, favorite) in entries {
+            database::insert_entry_at(pool, &entry, created_at, favorite).await?;
+        }
+        Ok(())
+    }
+
+    /// Everything but ids and image locations
+    async fn snapshot(pool: &SqlitePool) -> Result<Vec<(String, Option<String>, Option<String>, i64, bool, Option<String>)>> {
+        Ok(database::get_entries_for_export(pool, &ExportFilter::default())
+            .await?
+            .into_iter()
+            .map(|e| (e.content_type, e.text_content, e.metadata, e.created_at, e.favorite, e.source_app))
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = setup_test_db().await?;
+        seed(&source, &dir).await?;
+
+        let path = dir.path().join("history.jsonl");
+        let summary = export_history(&source, &path, ExportFormat::Jsonl, &ExportFilter::default()).await?;
+        assert_eq!(summary.entries, 3);
+
+        let target = setup_test_db().await?;
+        let report = import_history(&target, &path, &dir.path().join("blobs"), false).await?;
+        assert_eq!(report.imported, 3);
+        assert_eq!(report.invalid, 0);
+
+        assert_eq!(snapshot(&target).await?, snapshot(&source).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_archive_round_trip_carries_images() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = setup_test_db().await?;
+        seed(&source, &dir).await?;
+
+        let path = dir.path().join("history.tar.gz");
+        let summary = export_history(&source, &path, ExportFormat::Archive, &ExportFilter::default()).await?;
+        assert_eq!(summary.blobs, 1);
+
+        // The original image is gone, as on a new laptop
+        std::fs::remove_file(dir.path().join("shot.png"))?;
+
+        let target = setup_test_db().await?;
+        let blob_dir = dir.path().join("new-blobs");
+        let report = import_history(&target, &path, &blob_dir, false).await?;
+        assert_eq!(report.imported, 3);
+
+        assert_eq!(snapshot(&target).await?, snapshot(&source).await?);
+
+        let image = database::get_entries_for_export(&target, &ExportFilter { content_type: Some("image".to_string()), ..Default::default() }).await?;
+        let image_path = image[0].file_path.clone().unwrap();
+        assert!(image_path.starts_with(&*blob_dir.to_string_lossy()));
+        assert_eq!(std::fs::read(image_path)?, b"\x89PNG fake image");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reimport_skips_duplicates_and_merges_favorites() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pool = setup_test_db().await?;
+        seed(&pool, &dir).await?;
+
+        let path = dir.path().join("history.jsonl");
+        export_history(&pool, &path, ExportFormat::Jsonl, &ExportFilter::default()).await?;
+
+        // Locally unpin the favorite; importing the backup pins it again
+        let favorite = database::get_entries_for_export(&pool, &ExportFilter { favorites_only: true, ..Default::default() }).await?;
+        database::set_favorite(&pool, favorite[0].id, false).await?;
+
+        let report = import_history(&pool, &path, &dir.path().join("blobs"), false).await?;
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.duplicates, 3);
+        assert_eq!(report.favorites_updated, 1);
+        assert!(database::get_entry(&pool, favorite[0].id).await?.unwrap().favorite);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_writes_nothing() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = setup_test_db().await?;
+        seed(&source, &dir).await?;
+
+        let path = dir.path().join("history.tar.gz");
+        export_history(&source, &path, ExportFormat::Archive, &ExportFilter::default()).await?;
+
+        let target = setup_test_db().await?;
+        let blob_dir = dir.path().join("dry-blobs");
+        let report = import_history(&target, &path, &blob_dir, true).await?;
+
+        assert!(report.dry_run);
+        assert_eq!(report.imported, 3);
+        assert!(snapshot(&target).await?.is_empty());
+        assert!(!blob_dir.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_lines_are_reported() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pool = setup_test_db().await?;
+
+        let path = dir.path().join("broken.jsonl");
+        std::fs::write(
+            &path,
+            concat!(
+                r#"{"content_type":"text","text_content":"ok","file_path":null,"metadata":null,"created_at":1,"source_app":null,"content_hash":""}"#,
+                "\n",
+                "not json\n",
+                "\n",
+                r#"{"content_type":"text","text_content":null,"file_path":null,"metadata":null,"created_at":1,"source_app":null,"content_hash":""}"#,
+                "\n",
+                r#"{"content_type":"text","text_content":"ok","file_path":null,"metadata":null,"created_at":2,"source_app":null,"content_hash":""}"#,
+                "\n",
+            ),
+        )?;
+
+        let report = import_history(&pool, &path, &dir.path().join("blobs"), false).await?;
+        assert_eq!(report.total, 4);
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.duplicates, 1, "Duplicates within one file are skipped too");
+        assert_eq!(report.invalid, 2);
+        assert!(report.errors[0].starts_with("line 2:"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filtered_export() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pool = setup_test_db().await?;
+        seed(&pool, &dir).await?;
+
+        let path = dir.path().join("favorites.jsonl");
+        let filter = ExportFilter { favorites_only: true, ..Default::default() };
+        assert_eq!(export_history(&pool, &path, ExportFormat::Jsonl, &filter).await?.entries, 1);
+
+        let filter = ExportFilter { since: Some(1_650_000_000), ..Default::default() };
+        assert_eq!(export_history(&pool, &path, ExportFormat::Jsonl, &filter).await?.entries, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_offloaded_text_is_exported_whole() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = setup_test_db().await?;
+        let log = "GET /health 200\n".repeat(2000);
+        database::offload::upsert_text(&source, &dir.path().join("blobs"), NewClipboardEntry::new_text(log.clone()), 8 * 1024).await?;
+
+        let path = dir.path().join("history.jsonl");
+        export_history(&source, &path, ExportFormat::Jsonl, &ExportFilter::default()).await?;
+
+        let target = setup_test_db().await?;
+        import_history(&target, &path, &dir.path().join("imported"), false).await?;
+        let imported = database::get_entries_for_export(&target, &ExportFilter::default()).await?;
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].text_content.as_deref(), Some(log.as_str()));
+        assert_eq!(imported[0].file_path, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_imported_texts_go_to_the_blob_store() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let blob_dir = dir.path().join("blobs");
+        let current = settings::current();
+        let source = setup_test_db().await?;
+        let log = "GET /health 200\n".repeat(current.offload_threshold_bytes as usize / 16 + 1);
+        database::offload::upsert_text(&source, &blob_dir, NewClipboardEntry::new_text(log.clone()), current.offload_threshold_bytes).await?;
+
+        let path = dir.path().join("history.jsonl");
+        export_history(&source, &path, ExportFormat::Jsonl, &ExportFilter::default()).await?;
+
+        // The export carries the whole text, which matches the offloaded entry
+        let report = import_history(&source, &path, &blob_dir, false).await?;
+        assert_eq!((report.imported, report.duplicates), (0, 1));
+
+        let target = setup_test_db().await?;
+        assert_eq!(import_history(&target, &path, &blob_dir, false).await?.imported, 1);
+        let imported = database::get_entries_for_export(&target, &ExportFilter::default()).await?;
+        assert!(imported[0].truncated);
+        assert_eq!(imported[0].content_size, Some(log.len() as i64));
+        assert_eq!(database::offload::load_text(&imported[0]).await?.as_deref(), Some(log.as_str()));
+
+        // Over the capture limit, as if it had been copied
+        let huge = ImportItem::Entry(ImportRecord {
+            entry: NewClipboardEntry::new_text("x".repeat(current.max_capture_bytes as usize + 1)),
+            created_at: 1_700_000_000,
+            favorite: false,
+            image: None,
+        });
+        let report = merge_items(&target, vec![huge], &blob_dir, false).await?;
+        assert_eq!((report.imported, report.skipped), (0, 1));
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+pub mod clipman;
+pub mod copyq;
+pub mod export;
+pub mod gpaste;
+pub mod import;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+pub use export::{export_history, ExportFormat, ExportSummary};
+pub use import::{import_history, merge_items, ImportItem, ImportRecord, ImportReport, ImportedImage};
+
+/// Name of the entry list inside an export archive
+pub const ARCHIVE_HISTORY_FILE: &str = "history.jsonl";
+/// Directory for image data inside an export archive
+pub const ARCHIVE_BLOB_DIR: &str = "blobs";
+
+/// One line of a JSON Lines export. Everything except the database id is kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedEntry {
+    pub content_type: String,
+    pub text_content: Option<String>,
+    pub file_path: Option<String>,
+    /// Archive path of the entry's image data, e.g. "blobs/<hash>.png"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+    /// Free-form JSON, including tags once entries have them
+    pub metadata: Option<String>,
+    pub created_at: i64,
+    #[serde(default)]
+    pub favorite: bool,
+    pub source_app: Option<String>,
+    pub content_hash: String,
+}
+
+/// Another clipboard manager whose history can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForeignSource {
+    /// A CopyQ tab synchronized to a directory of item files
+    CopyQ,
+    /// GPaste's history.xml
+    GPaste,
+    /// Xfce Clipman's textsrc
+    Clipman,
+}
+
+impl ForeignSource {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "copyq" => Some(Self::CopyQ),
+            "gpaste" => Some(Self::GPaste),
+            "clipman" => Some(Self::Clipman),
+            _ => None,
+        }
+    }
+
+    /// Where the manager keeps its history by default. CopyQ has none: its own
+    /// tab files are binary, so a tab has to be synchronized to a directory first.
+    pub fn default_path(self) -> Option<PathBuf> {
+        match self {
+            Self::CopyQ => None,
+            Self::GPaste => dirs::data_dir().map(|dir| dir.join("gpaste").join("history.xml")),
+            Self::Clipman => dirs::cache_dir().map(|dir| dir.join("xfce4").join("clipman").join("textsrc")),
+        }
+    }
+}
+
+/// Merge another clipboard manager's history into the database. `path`
+/// defaults to the manager's usual location.
+pub async fn import_from_manager(
+    pool: &SqlitePool,
+    source: ForeignSource,
+    path: Option<&Path>,
+    blob_dir: &Path,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => source
+            .default_path()
+            .ok_or_else(|| anyhow!("{:?} has no default location, pass the path to import", source))?,
+    };
+
+    let items = tokio::task::spawn_blocking(move || match source {
+        ForeignSource::CopyQ => copyq::read_items(&path),
+        ForeignSource::GPaste => gpaste::read_items(&path),
+        ForeignSource::Clipman => clipman::read_items(&path),
+    })
+    .await??;
+
+    merge_items(pool, items, blob_dir, dry_run).await
+}
+
+/// Identity of an entry's content, used to skip duplicates on import. Image
+/// entries hash their bytes so the same screenshot matches wherever it's stored.
+pub fn content_hash(content_type: &str, text: Option<&str>, data: Option<&[u8]>, file_path: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content_type.as_bytes());
+    hasher.update([0]);
+
+    if let Some(data) = data {
+        hasher.update(b"data:");
+        hasher.update(data);
+    } else if let Some(text) = text {
+        hasher.update(b"text:");
+        hasher.update(text.as_bytes());
+    } else if let Some(file_path) = file_path {
+        hasher.update(b"path:");
+        hasher.update(file_path.as_bytes());
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
+/// Image bytes of an entry, if it is an image whose file still exists
+fn image_data(content_type: &str, file_path: Option<&str>) -> Option<Vec<u8>> {
+    if content_type != "image" {
+        return None;
+    }
+    std::fs::read(file_path?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash_distinguishes_kinds() {
+        let text = content_hash("text", Some("abc"), None, None);
+        assert_eq!(text, content_hash("text", Some("abc"), None, None));
+        assert_ne!(text, content_hash("text", Some("abd"), None, None));
+        assert_ne!(text, content_hash("html", Some("abc"), None, None));
+        assert_ne!(text, content_hash("text", None, None, Some("abc")));
+    }
+
+    #[test]
+    fn test_image_hash_ignores_location() {
+        let a = content_hash("image", None, Some(b"png bytes"), Some("/old/a.png"));
+        let b = content_hash("image", None, Some(b"png bytes"), Some("/new/b.png"));
+        assert_eq!(a, b);
+    }
+
+    #[tokio::test]
+    async fn test_import_from_manager_dedups_on_rerun() -> Result<()> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().connect("sqlite::memory:").await?;
+        crate::database::migrations::run_migrations(&pool).await?;
+        let blobs = tempfile::tempdir()?;
+
+        let history = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/importers/gpaste/history.xml");
+        let first = import_from_manager(&pool, ForeignSource::GPaste, Some(&history), blobs.path(), false).await?;
+        assert_eq!((first.total, first.imported, first.skipped), (6, 4, 2));
+
+        let entries = crate::database::get_entries_for_export(&pool, &Default::default()).await?;
+        assert!(entries.iter().all(|entry| entry.source_app.as_deref() == Some("GPaste")));
+        let image = entries.iter().find(|entry| entry.content_type == "image").unwrap();
+        assert!(image.file_path.as_deref().unwrap().starts_with(&*blobs.path().to_string_lossy()));
+        // Imported images are decoded for their thumbnails like copied ones
+        assert_eq!(crate::clipboard::thumbnail::process_pending(&pool).await?, 1);
+
+        let second = import_from_manager(&pool, ForeignSource::GPaste, Some(&history), blobs.path(), false).await?;
+        assert_eq!((second.imported, second.duplicates), (0, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_copyq_needs_a_path() {
+        assert!(ForeignSource::CopyQ.default_path().is_none());
+        assert_eq!(ForeignSource::parse("GPaste"), Some(ForeignSource::GPaste));
+        assert_eq!(ForeignSource::parse("klipper"), None);
+    }
+}
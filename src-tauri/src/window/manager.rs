@@ -6,30 +6,12 @@ use std::sync::Mutex;
 static PREVIOUS_APP: Mutex<Option<String>> = Mutex::new(None);
 
 pub fn show_window(app: &AppHandle) -> Result<()> {
-    // Before showing SuperKBD, get the currently frontmost application
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-
-        println!("🔧 [DEBUG] Getting frontmost app before showing SuperKBD...");
-        let result = Command::new("osascript")
-            .arg("-e")
-            .arg(r#"tell application "System Events"
-    set frontApp to first application process whose frontmost is true
-    return name of frontApp
-end tell"#)
-            .output();
-
-        if let Ok(output) = result {
-            if output.status.success() {
-                let app_name = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                if !app_name.is_empty() && app_name != "SuperKBD" && app_name != "superkbd" {
-                    println!("🔧 [DEBUG] Storing previous app: {}", app_name);
-                    if let Ok(mut prev) = PREVIOUS_APP.lock() {
-                        *prev = Some(app_name);
-                    }
-                }
-            }
+    // Before showing SuperKBD, remember the frontmost application so pastes
+    // go back to it and paste profiles can match it
+    if let Some(app_name) = frontmost_app() {
+        println!("🔧 [DEBUG] Storing previous app: {}", app_name);
+        if let Ok(mut prev) = PREVIOUS_APP.lock() {
+            *prev = Some(app_name);
         }
     }
 
@@ -44,6 +26,29 @@ end tell"#)
     Ok(())
 }
 
+/// Window offering ways out when the history database can't be opened. It
+/// replaces the history window until the user picks one.
+pub fn show_recovery_window(app: &AppHandle) -> Result<()> {
+    if let Some(window) = app.get_webview_window("main") {
+        window.hide()?;
+    }
+
+    if let Some(window) = app.get_webview_window("recovery") {
+        window.show()?;
+        window.set_focus()?;
+        return Ok(());
+    }
+
+    tauri::WebviewWindowBuilder::new(app, "recovery", tauri::WebviewUrl::App("recovery".into()))
+        .title("SuperKBD")
+        .inner_size(480.0, 440.0)
+        .center()
+        .resizable(false)
+        .always_on_top(true)
+        .build()?;
+    Ok(())
+}
+
 pub fn hide_window(app: &AppHandle) -> Result<()> {
     if let Some(window) = app.get_webview_window("main") {
         // First hide the window
@@ -88,6 +93,38 @@ pub fn hide_window(app: &AppHandle) -> Result<()> {
     Ok(())
 }
 
+/// Name of the application in front, other than SuperKBD itself: the process
+/// name on macOS, the window class on Linux (X11)
+pub fn frontmost_app() -> Option<String> {
+    #[cfg(target_os = "macos")]
+    let app_name = {
+        use std::process::Command;
+
+        println!("🔧 [DEBUG] Getting frontmost app...");
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    return name of frontApp
+end tell"#)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    #[cfg(target_os = "linux")]
+    let app_name = active_window_class()?;
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    let app_name = String::new();
+
+    if app_name.is_empty() || app_name.eq_ignore_ascii_case("superkbd") {
+        return None;
+    }
+    Some(app_name)
+}
+
 pub fn get_previous_app() -> Option<String> {
     if let Ok(prev) = PREVIOUS_APP.lock() {
         prev.clone()
@@ -96,6 +133,38 @@ pub fn get_previous_app() -> Option<String> {
     }
 }
 
+/// WM_CLASS class name of the focused X11 window, read with `xprop`
+#[cfg(target_os = "linux")]
+fn active_window_class() -> Option<String> {
+    use std::process::Command;
+
+    // "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007"
+    let output = Command::new("xprop")
+        .args(["-root", "_NET_ACTIVE_WINDOW"])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let window_id = stdout.split("# ").nth(1)?.trim().to_string();
+
+    let output = Command::new("xprop")
+        .args(["-id", &window_id, "WM_CLASS"])
+        .output()
+        .ok()?;
+
+    parse_wm_class(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Extract the class (second value) from `WM_CLASS(STRING) = "instance", "Class"`
+#[cfg(any(target_os = "linux", test))]
+fn parse_wm_class(output: &str) -> Option<String> {
+    let values = output.split_once('=')?.1;
+    values
+        .split(',')
+        .map(|value| value.trim().trim_matches('"'))
+        .rfind(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
 pub fn setup_window_events(window: &WebviewWindow) {
     let window_clone = window.clone();
     window.on_window_event(move |event| {
@@ -111,6 +180,16 @@ pub fn setup_window_events(window: &WebviewWindow) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_wm_class() {
+        assert_eq!(
+            parse_wm_class(r#"WM_CLASS(STRING) = "gnome-terminal-server", "Gnome-terminal""#),
+            Some("Gnome-terminal".to_string())
+        );
+        assert_eq!(parse_wm_class(r#"WM_CLASS(STRING) = "kitty""#), Some("kitty".to_string()));
+        assert_eq!(parse_wm_class("WM_CLASS:  not found."), None);
+    }
+
     #[test]
     fn test_window_functions_exist() {
         // These are mainly integration tests
@@ -1,4 +1,4 @@
-import { writable, derived } from 'svelte/store';
+import { writable, derived, get } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 
@@ -11,35 +11,128 @@ export interface ClipboardEntry {
   created_at: number;
   favorite: boolean;
   source_app: string | null;
+  kind: string | null;
+  kind_confidence: number | null;
+  language: string | null;
+  parent_id: number | null;
+  ocr_text: string | null;
+  content_size: number | null;
+  // text_content is only a preview; getEntryContent loads the rest
+  truncated: boolean;
 }
 
+export interface HighlightSpan {
+  kind: 'keyword' | 'string' | 'number' | 'comment' | 'function' | 'property' | 'plain';
+  text: string;
+}
+
+export interface Highlighted {
+  language: string | null;
+  spans: HighlightSpan[];
+}
+
+export interface ActionInfo {
+  id: string;
+  label: string;
+}
+
+export type ActionOutput =
+  | { type: 'text'; text: string }
+  | { type: 'open_url'; url: string }
+  | { type: 'reveal_path'; path: string }
+  | { type: 'open_path'; path: string };
+
 export const searchQuery = writable('');
 export const allEntries = writable<ClipboardEntry[]>([]);
 export const selectedIndex = writable(0);
 
+// Set by the open-favorites and open-paste-stack shortcuts; showing the
+// window again goes back to all entries
+export type HistoryView = 'history' | 'favorites' | 'stack';
+export const view = writable<HistoryView>('history');
+
+// Result of evaluating the search query as math or a unit conversion, shown
+// as the first item so it can be pasted like history
+export const calculation = writable<ClipboardEntry | null>(null);
+
+let latestQuery = '';
+
+searchQuery.subscribe(async (query) => {
+  latestQuery = query;
+  if (!query.trim()) {
+    calculation.set(null);
+    return;
+  }
+  try {
+    const entries = await invoke<ClipboardEntry[]>('get_clipboard_history', {
+      searchQuery: query,
+      limit: 1,
+      favoritesOnly: false
+    });
+    // Ignore answers for a query the user has already typed past
+    if (query === latestQuery) {
+      calculation.set(entries[0]?.content_type === 'calculation' ? entries[0] : null);
+    }
+  } catch (error) {
+    console.error('Failed to evaluate search query:', error);
+    calculation.set(null);
+  }
+});
+
 // Derived store for filtered entries
 export const filteredEntries = derived(
-  [allEntries, searchQuery],
-  ([$allEntries, $searchQuery]) => {
+  [allEntries, searchQuery, calculation],
+  ([$allEntries, $searchQuery, $calculation]) => {
     if (!$searchQuery.trim()) {
       return $allEntries;
     }
 
-    const query = $searchQuery.toLowerCase();
-    return $allEntries.filter(entry =>
-      entry.text_content?.toLowerCase().includes(query)
+    // "type:url github" matches URLs containing "github", like search_entries
+    const words = $searchQuery.toLowerCase().split(/\s+/).filter(Boolean);
+    const types = words.filter(word => word.startsWith('type:') && word.length > 5).map(word => word.slice(5));
+    const query = words.filter(word => !(word.startsWith('type:') && word.length > 5)).join(' ');
+
+    const matches = $allEntries.filter(entry =>
+      (types.length === 0 ||
+        types.includes(entry.kind ?? '') ||
+        types.includes(entry.content_type) ||
+        types.includes(entry.language ?? '')) &&
+      (!query ||
+        (entry.text_content?.toLowerCase().includes(query) ?? false) ||
+        (entry.ocr_text?.toLowerCase().includes(query) ?? false))
     );
+    return $calculation ? [$calculation, ...matches] : matches;
   }
 );
 
+// Image entries are served by the backend's clipimg:// protocol rather than
+// over IPC; Windows webviews only reach custom schemes through http
+const IMAGE_BASE = navigator.userAgent.includes('Windows') ? 'http://clipimg.localhost' : 'clipimg://localhost';
+
+export function thumbnailUrl(id: number, size: number = 64): string {
+  return `${IMAGE_BASE}/thumbnail/${id}?size=${size}`;
+}
+
+export function originalImageUrl(id: number): string {
+  return `${IMAGE_BASE}/original/${id}`;
+}
+
+let historyRequest = 0;
+
 export async function loadHistory() {
+  const request = ++historyRequest;
   try {
-    const entries = await invoke<ClipboardEntry[]>('get_clipboard_history', {
-      searchQuery: null,
-      limit: 100,
-      favoritesOnly: false
-    });
-    allEntries.set(entries);
+    const entries = get(view) === 'stack'
+      ? await invoke<ClipboardEntry[]>('get_paste_stack')
+      : await invoke<ClipboardEntry[]>('get_clipboard_history', {
+          searchQuery: null,
+          limit: 100,
+          favoritesOnly: get(view) === 'favorites'
+        });
+    // A later load (e.g. after switching views) wins over a slower earlier one
+    if (request === historyRequest) {
+      allEntries.set(entries);
+    }
   } catch (error) {
     console.error('Failed to load clipboard history:', error);
   }
@@ -54,6 +147,80 @@ export async function toggleFavorite(id: number) {
   }
 }
 
+export async function getHighlighted(id: number): Promise<Highlighted | null> {
+  try {
+    return await invoke<Highlighted>('get_highlighted', { id });
+  } catch (error) {
+    console.error('Failed to highlight entry:', error);
+    return null;
+  }
+}
+
+export async function listActions(id: number): Promise<ActionInfo[]> {
+  try {
+    return await invoke<ActionInfo[]>('list_actions', { id });
+  } catch (error) {
+    console.error('Failed to list actions:', error);
+    return [];
+  }
+}
+
+export async function runAction(id: number, action: string): Promise<ActionOutput | null> {
+  try {
+    return await invoke<ActionOutput>('run_action', { id, action });
+  } catch (error) {
+    console.error('Failed to run action:', error);
+    return null;
+  }
+}
+
+// Complete text of an entry whose list item only carries a preview
+export async function getEntryContent(id: number): Promise<string | null> {
+  try {
+    return await invoke<string>('get_entry_content', { id });
+  } catch (error) {
+    console.error('Failed to load entry content:', error);
+    return null;
+  }
+}
+
+// PNG of a text entry as a QR code, as an object URL for an <img>
+export async function renderQr(id: number): Promise<string | null> {
+  try {
+    const png = await invoke<ArrayBuffer>('render_qr', { id });
+    return URL.createObjectURL(new Blob([png], { type: 'image/png' }));
+  } catch (error) {
+    console.error('Failed to render QR code:', error);
+    return null;
+  }
+}
+
+export async function addToPasteStack(id: number) {
+  try {
+    await invoke('add_to_paste_stack', { id });
+  } catch (error) {
+    console.error('Failed to add to paste stack:', error);
+  }
+}
+
+// Takes the entry off the stack, so the next paste from it gets the one after
+export async function removeFromPasteStack(id: number) {
+  try {
+    await invoke('remove_from_paste_stack', { id });
+  } catch (error) {
+    console.error('Failed to remove from paste stack:', error);
+  }
+}
+
+export async function clearPasteStack() {
+  try {
+    await invoke('clear_paste_stack');
+    await loadHistory();
+  } catch (error) {
+    console.error('Failed to clear paste stack:', error);
+  }
+}
+
 export async function pasteAndClose(content: string) {
   try {
     console.log('[Frontend] pasteAndClose called with:', content);
@@ -78,8 +245,25 @@ export function initializeListeners() {
     await loadHistory();
   });
 
-  listen('window-shown', () => {
+  listen('window-shown', async () => {
     selectedIndex.set(0);
     searchQuery.set('');
+    if (get(view) !== 'history') {
+      await showView('history');
+    }
   });
+
+  listen<string>('shortcut-action', async (event) => {
+    if (event.payload === 'open_favorites') {
+      await showView('favorites');
+    } else if (event.payload === 'open_paste_stack') {
+      await showView('stack');
+    }
+  });
+}
+
+export async function showView(next: HistoryView) {
+  view.set(next);
+  selectedIndex.set(0);
+  await loadHistory();
 }